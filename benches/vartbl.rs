@@ -45,7 +45,7 @@ pub fn vartbl_benchmark(c: &mut Criterion) {
 
     c.bench_function("get variables from vartable", |b: &mut Bencher| {
         b.iter(|| {
-            let mut vartbl = vartbl.clone();
+            let vartbl = vartbl.clone();
 
             for var in &vars {
                 vartbl.get(&var.id).unwrap();
@@ -0,0 +1,741 @@
+//! Symbolic simplification of expressions containing free symbols.
+//!
+
+// Copyright (c) 2022 Charles M. Thompson
+//
+// This file is part of ApeCrunch.
+//
+// ApeCrunch is free software: you can redistribute it and/or modify it under
+// the terms only of version 3 of the GNU General Public License as published
+// by the Free Software Foundation
+//
+// ApeCrunch is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License
+// for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// ApeCrunch(in a file named COPYING).
+// If not, see <https://www.gnu.org/licenses/>.
+
+use crate::number::Number;
+use crate::parser::Token;
+use crate::polynomial::Polynomial;
+use std::cmp::Ordering;
+
+/// Largest power a sum will be multiplied out to when expanding, so (x + 1)^100000 can't lock up the calculator.
+///
+/// **NOT PUBLIC.**
+///
+const MAX_EXPAND_POWER: i64 = 64;
+
+/// Largest denominator a coefficient is written as a fraction with, anything bigger is written as a decimal.
+///
+/// **NOT PUBLIC.**
+///
+const MAX_FRACTION_DENOMINATOR: i64 = 1000;
+
+/// Collects like terms and folds constants, so 2x + 3x + 1 + 2 becomes 5x + 3 and x/x becomes 1.
+///
+/// Products of sums are left alone, except for constants which are multiplied through.
+///
+pub fn collect(token: &Token) -> Token {
+    Sum::from_token(token, false).to_token()
+}
+
+/// Multiplies out every product and whole number power of sums, then collects like terms.
+///
+/// For example, (x + 1)^2 expands into x^2 + 2x + 1.
+///
+pub fn expand(token: &Token) -> Token {
+    Sum::from_token(token, true).to_token()
+}
+
+/// Factors an expression, pulling out common factors and, for polynomials in a single symbol, every rational root.
+///
+/// For example, 2x^2 - 2 factors into 2(x - 1)(x + 1).
+///
+pub fn factor(token: &Token, decimal_places: u32) -> Token {
+    let sum = Sum::from_token(token, true);
+
+    if sum.terms.len() < 2 {
+        return sum.to_token();
+    }
+
+    let common = sum.common_term();
+    let rest = sum.divide_term(&common);
+
+    let mut result = common;
+
+    match rest.univariate_polynomial() {
+        Some((symbol, polynomial)) => {
+            let (constant, factors) = factor_polynomial(&polynomial, decimal_places);
+
+            result.coefficient = result.coefficient.multiply(&constant);
+
+            for (factor, power) in factors {
+                let factor = Sum::from_polynomial(&factor, &symbol);
+
+                result = result.multiply(&factor.to_term(power));
+            }
+        }
+        None => result = result.multiply(&rest.to_term(Number::one())),
+    }
+
+    // Nothing factored out, so there's no need for parenthesis around the whole thing
+    match (
+        result.coefficient == Number::one(),
+        result.factors.as_slice(),
+    ) {
+        (true, [(base, power)]) if *power == Number::one() => base.clone(),
+        _ => result.to_token(),
+    }
+}
+
+/// Splits a polynomial into a constant and factors with their powers, one factor for each rational root plus whatever is left over.
+///
+/// **PRIVATE FUNCTION**
+///
+fn factor_polynomial(
+    polynomial: &Polynomial,
+    decimal_places: u32,
+) -> (Number, Vec<(Polynomial, Number)>) {
+    let mut remaining = polynomial.clone();
+    let mut factors = Vec::<(Polynomial, Number)>::new();
+
+    // Roots that make the polynomial exactly zero are rational, anything else is left in the remaining factor
+    let mut roots = polynomial
+        .roots(decimal_places)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|root| root.is_real() && polynomial.evaluate(root).is_zero())
+        .map(|root| root.real)
+        .collect::<Vec<Number>>();
+
+    roots.dedup();
+
+    for root in roots {
+        // A root of n/d gives the factor dx - n, so there are no fractions inside the factor
+        let linear = Polynomial::new(vec![root.numerator().negative(), root.denominator()]);
+        let mut power = 0;
+
+        loop {
+            match remaining.div_rem(&linear) {
+                Ok((quotient, remainder)) if remainder.degree().is_none() => {
+                    remaining = quotient;
+                    power += 1;
+                }
+                _ => break,
+            }
+        }
+
+        factors.push((linear, Number::from_i64(power)));
+    }
+
+    match remaining.degree() {
+        Some(0) => (remaining.coefficients()[0].clone(), factors),
+        _ => {
+            factors.push((remaining, Number::one()));
+
+            (Number::one(), factors)
+        }
+    }
+}
+
+/// Product of a coefficient and factors raised to powers, such as 3x^2y.
+///
+/// **NOT PUBLIC.**
+///
+#[derive(Debug, Clone, PartialEq)]
+struct Term {
+    /// Constant the factors are multiplied by.
+    coefficient: Number,
+    /// Bases along with their powers, sorted by base with no base appearing twice and no power of zero.
+    factors: Vec<(Token, Number)>,
+}
+
+impl Term {
+    /// Creates a term that is just a constant.
+    ///
+    fn constant(coefficient: Number) -> Self {
+        Self {
+            coefficient,
+            factors: Vec::new(),
+        }
+    }
+
+    /// Creates a term that is a single base raised to a power.
+    ///
+    fn power(base: Token, power: Number) -> Self {
+        Self::constant(Number::one()).multiply(&Self {
+            coefficient: Number::one(),
+            factors: vec![(base, power)],
+        })
+    }
+
+    /// Returns true if the term has no factors.
+    ///
+    fn is_constant(&self) -> bool {
+        self.factors.is_empty()
+    }
+
+    /// Multiplies two terms, adding the powers of matching bases.
+    ///
+    fn multiply(&self, other: &Term) -> Term {
+        let mut factors = self.factors.clone();
+
+        for (base, power) in &other.factors {
+            match factors.binary_search_by(|(i, _)| compare_bases(i, base)) {
+                Ok(i) => factors[i].1 = factors[i].1.add(power),
+                Err(i) => factors.insert(i, (base.clone(), power.clone())),
+            }
+        }
+
+        factors.retain(|(_, power)| !power.is_zero());
+
+        Term {
+            coefficient: self.coefficient.multiply(&other.coefficient),
+            factors,
+        }
+    }
+
+    /// Raises the term to a power, None if it can't be done without changing the meaning of the term.
+    ///
+    fn pow(&self, power: &Number) -> Option<Term> {
+        if power.is_zero() {
+            return Some(Term::constant(Number::one()));
+        }
+
+        match power.to_i64() {
+            Some(_) => Some(Term {
+                coefficient: self.coefficient.exponent(power, 0),
+                factors: self
+                    .factors
+                    .iter()
+                    .map(|(base, factor_power)| (base.clone(), factor_power.multiply(power)))
+                    .collect(),
+            }),
+            // Fractional powers only pass through a single plain base, since (x^2)^0.5 isn't always x
+            None => match (self.coefficient == Number::one(), self.factors.as_slice()) {
+                (true, [(base, factor_power)]) if *factor_power == Number::one() => {
+                    Some(Term::power(base.clone(), power.clone()))
+                }
+                _ => None,
+            },
+        }
+    }
+
+    /// Total degree of the term, the sum of every power.
+    ///
+    fn degree(&self) -> Number {
+        self.factors
+            .iter()
+            .fold(Number::zero(), |degree, (_, power)| degree.add(power))
+    }
+
+    /// Orders terms from the highest degree down, with ties broken by their bases.
+    ///
+    fn compare(&self, other: &Term) -> Ordering {
+        let by_degree = other
+            .degree()
+            .partial_cmp(&self.degree())
+            .unwrap_or(Ordering::Equal);
+
+        let by_factors = self
+            .factors
+            .iter()
+            .zip(&other.factors)
+            .map(|((left_base, left_power), (right_base, right_power))| {
+                compare_bases(left_base, right_base).then(
+                    right_power
+                        .partial_cmp(left_power)
+                        .unwrap_or(Ordering::Equal),
+                )
+            })
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or(Ordering::Equal);
+
+        by_degree.then(by_factors)
+    }
+
+    /// Converts the term back into tokens, putting negative powers and the denominator of the coefficient under a division.
+    ///
+    fn to_token(&self) -> Token {
+        if self.coefficient.is_negative() {
+            let positive = Term {
+                coefficient: self.coefficient.negative(),
+                factors: self.factors.clone(),
+            };
+
+            return Token::Negative(Box::new(positive.to_token()));
+        }
+
+        let mut numerator = Vec::<Token>::new();
+        let mut denominator = Vec::<Token>::new();
+
+        // Simple fractions are kept exact, like x / 3, but something like ln(2) is better off as a decimal
+        if self.coefficient.denominator() > Number::from_i64(MAX_FRACTION_DENOMINATOR) {
+            numerator.push(Token::Number(self.coefficient.clone()));
+        } else {
+            if self.coefficient.numerator() != Number::one() {
+                numerator.push(Token::Number(self.coefficient.numerator()));
+            }
+
+            if self.coefficient.denominator() != Number::one() {
+                denominator.push(Token::Number(self.coefficient.denominator()));
+            }
+        }
+
+        for (base, power) in &self.factors {
+            match power.is_negative() {
+                true => denominator.push(power_token(base, &power.negative())),
+                false => numerator.push(power_token(base, power)),
+            }
+        }
+
+        let numerator = product_token(numerator);
+
+        match denominator.len() {
+            0 => numerator,
+            1 => Token::Divide(Box::new(numerator), Box::new(product_token(denominator))),
+            _ => Token::Divide(
+                Box::new(numerator),
+                Box::new(Token::Parenthesis(Box::new(product_token(denominator)))),
+            ),
+        }
+    }
+}
+
+/// Sum of terms, the form every symbolic expression is collected into.
+///
+/// **NOT PUBLIC.**
+///
+#[derive(Debug, Clone, PartialEq)]
+struct Sum {
+    /// Terms, with like terms merged, no zero coefficients, and sorted from the highest degree down.
+    terms: Vec<Term>,
+}
+
+impl Sum {
+    /// Creates a sum from a list of terms, merging like terms.
+    ///
+    fn new(terms: Vec<Term>) -> Self {
+        let mut merged = Vec::<Term>::with_capacity(terms.len());
+
+        for term in terms {
+            match merged.iter_mut().find(|i| i.factors == term.factors) {
+                Some(like) => like.coefficient = like.coefficient.add(&term.coefficient),
+                None => merged.push(term),
+            }
+        }
+
+        merged.retain(|term| !term.coefficient.is_zero());
+        merged.sort_by(|a, b| a.compare(b));
+
+        Self { terms: merged }
+    }
+
+    /// Creates a sum of a single term.
+    ///
+    fn from_term(term: Term) -> Self {
+        Self::new(vec![term])
+    }
+
+    /// Collects an expression into a sum, multiplying out products of sums if expand is true.
+    ///
+    fn from_token(token: &Token, expand: bool) -> Self {
+        match token {
+            Token::Number(number) => Self::from_term(Term::constant(number.clone())),
+            Token::Symbol(_) => Self::from_term(Term::power(token.clone(), Number::one())),
+            Token::Add(left, right) => {
+                Self::from_token(left, expand).add(&Self::from_token(right, expand))
+            }
+            Token::Subtract(left, right) => Self::from_token(left, expand)
+                .add(&Self::from_token(right, expand).scale(&Number::neg_one())),
+            Token::Multiply(left, right) => {
+                Self::from_token(left, expand).multiply(&Self::from_token(right, expand), expand)
+            }
+            Token::Divide(left, right) => {
+                let numerator = Self::from_token(left, expand);
+                let denominator = Self::from_token(right, expand);
+
+                match denominator.constant() {
+                    // Division by zero is kept as it is instead of becoming a multiple of infinity
+                    Some(constant) if constant.is_zero() => Self::from_term(Term::power(
+                        Token::Divide(
+                            Box::new(numerator.to_token()),
+                            Box::new(denominator.to_token()),
+                        ),
+                        Number::one(),
+                    )),
+                    _ => numerator.multiply(&denominator.pow(&Number::neg_one(), expand), expand),
+                }
+            }
+            Token::Exponent(left, right) => {
+                let base = Self::from_token(left, expand);
+                let power = Self::from_token(right, expand);
+
+                match power.constant() {
+                    Some(power) => base.pow(&power, expand),
+                    // Symbolic powers can't be collected any further
+                    None => Self::from_term(Term::power(
+                        Token::Exponent(Box::new(base.to_token()), Box::new(power.to_token())),
+                        Number::one(),
+                    )),
+                }
+            }
+            Token::Negative(expression) => {
+                Self::from_token(expression, expand).scale(&Number::neg_one())
+            }
+            Token::Parenthesis(expression) => Self::from_token(expression, expand),
+            _ => Self::from_term(Term::power(token.clone(), Number::one())),
+        }
+    }
+
+    /// Converts a polynomial in a symbol into a sum.
+    ///
+    fn from_polynomial(polynomial: &Polynomial, symbol: &str) -> Self {
+        Self::new(
+            polynomial
+                .coefficients()
+                .iter()
+                .enumerate()
+                .map(|(power, coefficient)| Term {
+                    coefficient: coefficient.clone(),
+                    factors: match power {
+                        0 => Vec::new(),
+                        _ => vec![(
+                            Token::Symbol(symbol.to_string()),
+                            Number::from_i64(power as i64),
+                        )],
+                    },
+                })
+                .collect(),
+        )
+    }
+
+    /// Gets the value of the sum if it is just a constant.
+    ///
+    fn constant(&self) -> Option<Number> {
+        match self.terms.as_slice() {
+            [] => Some(Number::zero()),
+            [term] if term.is_constant() => Some(term.coefficient.clone()),
+            _ => None,
+        }
+    }
+
+    /// Adds two sums.
+    ///
+    fn add(&self, other: &Sum) -> Sum {
+        Self::new(self.terms.iter().chain(&other.terms).cloned().collect())
+    }
+
+    /// Multiplies every term by a constant.
+    ///
+    fn scale(&self, scalar: &Number) -> Sum {
+        self.multiply(&Self::from_term(Term::constant(scalar.clone())), false)
+    }
+
+    /// Multiplies two sums.
+    ///
+    /// Sums are only multiplied out when expanding or when one side is a constant, otherwise they're kept together as a single factor.
+    ///
+    fn multiply(&self, other: &Sum, expand: bool) -> Sum {
+        if expand || self.terms.len() < 2 && other.terms.len() < 2 {
+            return Self::distribute(self, other);
+        }
+
+        match (self.constant(), other.constant()) {
+            (Some(_), _) | (_, Some(_)) => Self::distribute(self, other),
+            _ => Self::from_term(
+                self.to_term(Number::one())
+                    .multiply(&other.to_term(Number::one())),
+            ),
+        }
+    }
+
+    /// Multiplies every term of one sum by every term of another.
+    ///
+    fn distribute(left: &Sum, right: &Sum) -> Sum {
+        Self::new(
+            left.terms
+                .iter()
+                .flat_map(|a| right.terms.iter().map(move |b| a.multiply(b)))
+                .collect(),
+        )
+    }
+
+    /// Raises the sum to a constant power.
+    ///
+    /// Sums of more than one term are only multiplied out when expanding with a small enough whole number power.
+    ///
+    fn pow(&self, power: &Number, expand: bool) -> Sum {
+        match self.terms.as_slice() {
+            [] if power.is_negative() => {
+                Self::from_term(Term::constant(Number::zero().exponent(power, 0)))
+            }
+            [] if power.is_zero() => Self::from_term(Term::constant(Number::one())),
+            [] => self.clone(),
+            [term] => match term.pow(power) {
+                Some(term) => Self::from_term(term),
+                None => Self::from_term(self.to_term(power.clone())),
+            },
+            _ => match (expand, power.to_i64()) {
+                (true, Some(exponent)) if (0..=MAX_EXPAND_POWER).contains(&exponent) => {
+                    let mut result = Self::from_term(Term::constant(Number::one()));
+
+                    for _ in 0..exponent {
+                        result = Self::distribute(&result, self);
+                    }
+
+                    result
+                }
+                _ => Self::from_term(self.to_term(power.clone())),
+            },
+        }
+    }
+
+    /// Converts the sum into a single term raised to a power, keeping sums of more than one term together as a single base.
+    ///
+    fn to_term(&self, power: Number) -> Term {
+        match self.terms.as_slice() {
+            [term] => term
+                .pow(&power)
+                .unwrap_or_else(|| Term::power(self.to_token(), power)),
+            _ => Term::power(self.to_token(), power),
+        }
+    }
+
+    /// Gets the largest term that divides every term of the sum, taking out whole number factors and the lowest power of each base.
+    ///
+    /// The sign of the first term is taken out too, so the sum left over starts positive.
+    ///
+    fn common_term(&self) -> Term {
+        let mut common = Term::constant(Number::one());
+
+        if let Some(gcd) = self.terms.iter().try_fold(0i64, |gcd, term| {
+            term.coefficient
+                .to_i64()
+                .map(|coefficient| gcd_i64(gcd, coefficient))
+        }) {
+            if gcd > 1 {
+                common.coefficient = Number::from_i64(gcd);
+            }
+        }
+
+        if let Some(first) = self.terms.first() {
+            if first.coefficient.is_negative() {
+                common.coefficient = common.coefficient.negative();
+            }
+
+            for (base, power) in &first.factors {
+                let lowest = self.terms.iter().try_fold(power.clone(), |lowest, term| {
+                    term.factors
+                        .iter()
+                        .find(|(other, _)| other == base)
+                        .map(|(_, other_power)| match *other_power < lowest {
+                            true => other_power.clone(),
+                            false => lowest,
+                        })
+                });
+
+                if let Some(lowest) = lowest.filter(|lowest| !lowest.is_negative()) {
+                    common = common.multiply(&Term::power(base.clone(), lowest));
+                }
+            }
+        }
+
+        common
+    }
+
+    /// Divides every term by a term.
+    ///
+    fn divide_term(&self, divisor: &Term) -> Sum {
+        let reciprocal = Term {
+            coefficient: Number::one().divide(&divisor.coefficient),
+            factors: divisor
+                .factors
+                .iter()
+                .map(|(base, power)| (base.clone(), power.negative()))
+                .collect(),
+        };
+
+        Self::new(
+            self.terms
+                .iter()
+                .map(|term| term.multiply(&reciprocal))
+                .collect(),
+        )
+    }
+
+    /// Converts the sum into a polynomial, if it is one in exactly one symbol.
+    ///
+    fn univariate_polynomial(&self) -> Option<(String, Polynomial)> {
+        let mut symbol: Option<&str> = None;
+        let mut coefficients = Vec::<Number>::new();
+
+        for term in &self.terms {
+            let power = match term.factors.as_slice() {
+                [] => 0,
+                [(Token::Symbol(id), power)] => {
+                    if *symbol.get_or_insert(id.as_str()) != id.as_str() {
+                        return None;
+                    }
+
+                    match power.to_i64() {
+                        Some(power) if power > 0 => power as usize,
+                        _ => return None,
+                    }
+                }
+                _ => return None,
+            };
+
+            if coefficients.len() <= power {
+                coefficients.resize(power + 1, Number::zero());
+            }
+
+            coefficients[power] = term.coefficient.clone();
+        }
+
+        symbol.map(|symbol| (symbol.to_string(), Polynomial::new(coefficients)))
+    }
+
+    /// Converts the sum back into tokens.
+    ///
+    fn to_token(&self) -> Token {
+        let mut terms = self.terms.iter();
+
+        let mut result = match terms.next() {
+            Some(term) => term.to_token(),
+            None => return Token::Number(Number::zero()),
+        };
+
+        for term in terms {
+            result = match term.coefficient.is_negative() {
+                true => {
+                    let positive = Term {
+                        coefficient: term.coefficient.negative(),
+                        factors: term.factors.clone(),
+                    };
+
+                    Token::Subtract(Box::new(result), Box::new(positive.to_token()))
+                }
+                false => Token::Add(Box::new(result), Box::new(term.to_token())),
+            };
+        }
+
+        result
+    }
+}
+
+/// Orders bases with plain symbols first, then everything else by its structure.
+///
+/// **PRIVATE FUNCTION**
+///
+fn compare_bases(left: &Token, right: &Token) -> Ordering {
+    let rank = |token: &Token| match token {
+        Token::Symbol(_) => 0,
+        Token::Function(..) => 1,
+        _ => 2,
+    };
+
+    rank(left)
+        .cmp(&rank(right))
+        .then_with(|| format!("{:?}", left).cmp(&format!("{:?}", right)))
+}
+
+/// Converts a base raised to a positive power into tokens, with parenthesis where they're needed.
+///
+/// **PRIVATE FUNCTION**
+///
+fn power_token(base: &Token, power: &Number) -> Token {
+    let base = match (base, *power == Number::one()) {
+        (Token::Symbol(_) | Token::Function(..) | Token::Number(_), _) => base.clone(),
+        (Token::Exponent(..), true) => return base.clone(),
+        _ => Token::Parenthesis(Box::new(base.clone())),
+    };
+
+    if *power == Number::one() {
+        return base;
+    }
+
+    let power = match power.denominator() == Number::one() {
+        true => Token::Number(power.clone()),
+        false => Token::Parenthesis(Box::new(Token::Divide(
+            Box::new(Token::Number(power.numerator())),
+            Box::new(Token::Number(power.denominator())),
+        ))),
+    };
+
+    Token::Exponent(Box::new(base), Box::new(power))
+}
+
+/// Multiplies a list of tokens together, one if the list is empty.
+///
+/// **PRIVATE FUNCTION**
+///
+fn product_token(tokens: Vec<Token>) -> Token {
+    tokens
+        .into_iter()
+        .reduce(|product, token| Token::Multiply(Box::new(product), Box::new(token)))
+        .unwrap_or_else(|| Token::Number(Number::one()))
+}
+
+/// Greatest common divisor of two whole numbers, always positive.
+///
+/// **PRIVATE FUNCTION**
+///
+fn gcd_i64(a: i64, b: i64) -> i64 {
+    match b {
+        0 => a.abs(),
+        _ => gcd_i64(b, a % b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::op_engine;
+    use crate::parser;
+    use crate::session::Session;
+
+    fn check(function: fn(&Token) -> Token, expression: &str, expected: &str) {
+        let mut session = Session::_new_test().unwrap();
+
+        let tokens = parser::parse_str(expression, &mut session).unwrap();
+        let result = function(&op_engine::simplify(&tokens, &mut session).unwrap());
+
+        assert_eq!(result.to_string(&session), expected);
+    }
+
+    #[test]
+    fn test_algebra_collect() {
+        check(collect, "2x + 3x", "5x");
+        check(collect, "x + 1 + x + 2", "2x + 3");
+        check(collect, "x/x", "1");
+        check(collect, "2x*3y/x", "6y");
+        check(collect, "x*x*x - x^3", "0");
+        check(collect, "3(x+1) - 3", "3x");
+        check(collect, "(x+1)(x+1)", "( x + 1 )^2");
+        check(collect, "x/2 - y", "x / 2 - y");
+        check(collect, "1/(2x)", "1 / ( 2x )");
+    }
+
+    #[test]
+    fn test_algebra_expand() {
+        check(expand, "(x+1)^2", "x^2 + 2x + 1");
+        check(expand, "(x+y)(x-y)", "x^2 - y^2");
+    }
+
+    #[test]
+    fn test_algebra_factor() {
+        let factor_6 = |token: &Token| factor(token, 6);
+
+        check(factor_6, "x^2 - 3x + 2", "( x - 1 ) * ( x - 2 )");
+        check(factor_6, "2x^2 - 2", "2 * ( x + 1 ) * ( x - 1 )");
+        check(factor_6, "x^3 - 2x^2 + x", "x * ( x - 1 )^2");
+        check(factor_6, "2x - 1", "2x - 1");
+        check(factor_6, "x^2 + 1", "x^2 + 1");
+        check(factor_6, "2x*y + 4x", "2x * ( y + 2 )");
+    }
+}
@@ -0,0 +1,579 @@
+//! Symbolic and numeric calculus on tokenized expressions.
+//!
+
+// Copyright (c) 2022 Charles M. Thompson
+//
+// This file is part of ApeCrunch.
+//
+// ApeCrunch is free software: you can redistribute it and/or modify it under
+// the terms only of version 3 of the GNU General Public License as published
+// by the Free Software Foundation
+//
+// ApeCrunch is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License
+// for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// ApeCrunch(in a file named COPYING).
+// If not, see <https://www.gnu.org/licenses/>.
+
+use crate::number::Number;
+use crate::op_engine;
+use crate::parser::Token;
+use crate::session::Session;
+use simple_error::*;
+use std::error::Error;
+use std::fmt;
+
+/// Error from a numeric method that couldn't get its error estimate below the tolerance.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvergenceError {
+    /// Name of the function that failed, like integrate.
+    pub method: String,
+    /// Best estimate of the error that was reached.
+    pub error_estimate: Number,
+    /// Error that was needed.
+    pub tolerance: Number,
+}
+
+impl fmt::Display for ConvergenceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} did not converge, the error estimate of {} is above the tolerance of {}!",
+            self.method,
+            self.error_estimate.to_string(ERROR_DISPLAY_PLACES),
+            self.tolerance.to_string(ERROR_DISPLAY_PLACES)
+        )
+    }
+}
+
+impl Error for ConvergenceError {}
+
+/// Decimal places error estimates are shown to in a ConvergenceError.
+///
+/// **NOT PUBLIC.**
+///
+const ERROR_DISPLAY_PLACES: u32 = 20;
+
+/// Extra decimal places carried through numeric methods, so rounding doesn't creep into the result.
+///
+/// **NOT PUBLIC.**
+///
+const NUMERIC_GUARD_DIGITS: u32 = 10;
+
+/// Most subintervals adaptive integration will split the range into before giving up.
+///
+/// **NOT PUBLIC.**
+///
+const MAX_INTEGRATION_INTERVALS: usize = 200;
+
+/// Most times numeric differentiation will shrink its step before giving up.
+///
+/// **NOT PUBLIC.**
+///
+const MAX_DERIVATIVE_STEPS: usize = 12;
+
+/// Positive nodes of the 15 point Kronrod rule on [-1, 1], the odd ones shared with the 7 point Gauss rule.
+///
+/// **NOT PUBLIC.**
+///
+const KRONROD_NODES: [&str; 8] = [
+    "0.991455371120812639206854697526329",
+    "0.949107912342758524526189684047851",
+    "0.864864423359769072789712788640926",
+    "0.741531185599394439863864773280788",
+    "0.586087235467691130294144845693013",
+    "0.405845151377397166906606412076961",
+    "0.207784955007898467600689403773245",
+    "0",
+];
+
+/// Weights of the 15 point Kronrod rule, matching KRONROD_NODES.
+///
+/// **NOT PUBLIC.**
+///
+const KRONROD_WEIGHTS: [&str; 8] = [
+    "0.022935322010529224963732008058970",
+    "0.063092092629978553290700663189204",
+    "0.104790010322250183839876322541518",
+    "0.140653259715525918745189590510238",
+    "0.169004726639267902826583426598550",
+    "0.190350578064785409913256402421014",
+    "0.204432940075298892414161999234649",
+    "0.209482141084727828012999174891714",
+];
+
+/// Weights of the 7 point Gauss rule, matching the odd KRONROD_NODES.
+///
+/// **NOT PUBLIC.**
+///
+const GAUSS_WEIGHTS: [&str; 4] = [
+    "0.129484966168869693270611432679082",
+    "0.279705391489276667901467771423780",
+    "0.381830050505118944950369775488975",
+    "0.417959183673469387755102040816327",
+];
+
+/// Gets the derivative of a simplified expression with respect to a symbol.
+///
+/// The derivative comes straight from the rules of differentiation and isn't simplified, so it should be run through op_engine::simplify.
+///
+/// Returns a simple error if the expression contains something that can't be differentiated, like an equality.
+///
+pub fn derivative(token: &Token, symbol: &str) -> Result<Token, Box<dyn Error>> {
+    // Anything that doesn't depend on the symbol is a constant
+    if !token.contains_symbol(symbol) {
+        return Ok(number(0));
+    }
+
+    let d = |token: &Token| derivative(token, symbol);
+
+    Ok(match token {
+        Token::Symbol(_) => number(1),
+        Token::Add(u, v) => Token::Add(Box::new(d(u)?), Box::new(d(v)?)),
+        Token::Subtract(u, v) => Token::Subtract(Box::new(d(u)?), Box::new(d(v)?)),
+        // (uv)' = u'v + uv'
+        Token::Multiply(u, v) => Token::Add(
+            Box::new(multiply(d(u)?, *v.clone())),
+            Box::new(multiply(*u.clone(), d(v)?)),
+        ),
+        // (u/v)' = (u'v - uv') / v^2
+        Token::Divide(u, v) => Token::Divide(
+            Box::new(Token::Subtract(
+                Box::new(multiply(d(u)?, *v.clone())),
+                Box::new(multiply(*u.clone(), d(v)?)),
+            )),
+            Box::new(Token::Exponent(v.clone(), Box::new(number(2)))),
+        ),
+        Token::Exponent(u, v) => {
+            let power = token.clone();
+
+            match (u.contains_symbol(symbol), v.contains_symbol(symbol)) {
+                // (u^n)' = n u^(n-1) u'
+                (true, false) => multiply(
+                    multiply(
+                        *v.clone(),
+                        Token::Exponent(
+                            u.clone(),
+                            Box::new(Token::Subtract(v.clone(), Box::new(number(1)))),
+                        ),
+                    ),
+                    d(u)?,
+                ),
+                // (a^v)' = a^v ln(a) v'
+                (false, _) => multiply(multiply(power, function("ln", *u.clone())), d(v)?),
+                // (u^v)' = u^v (v' ln(u) + v u' / u)
+                (true, true) => multiply(
+                    power,
+                    Token::Add(
+                        Box::new(multiply(d(v)?, function("ln", *u.clone()))),
+                        Box::new(Token::Divide(
+                            Box::new(multiply(*v.clone(), d(u)?)),
+                            u.clone(),
+                        )),
+                    ),
+                ),
+            }
+        }
+        Token::Negative(u) => Token::Negative(Box::new(d(u)?)),
+        Token::Parenthesis(u) => d(u)?,
+        Token::Matrix(rows) => Token::Matrix(
+            rows.iter()
+                .map(|row| row.iter().map(d).collect())
+                .collect::<Result<Vec<Vec<Token>>, Box<dyn Error>>>()?,
+        ),
+        Token::Function(id, args) => {
+            let u = match args.as_slice() {
+                [u] => u.clone(),
+                _ => bail!("Can't differentiate the function \"{}\"!", id),
+            };
+
+            // Chain rule, f(u)' = f'(u) u'
+            let outer = match id.as_str() {
+                "sqrt" => Token::Divide(
+                    Box::new(number(1)),
+                    Box::new(multiply(number(2), function("sqrt", u.clone()))),
+                ),
+                "exp" => function("exp", u.clone()),
+                "ln" => Token::Divide(Box::new(number(1)), Box::new(u.clone())),
+                "sin" => function("cos", u.clone()),
+                "cos" => Token::Negative(Box::new(function("sin", u.clone()))),
+                "tan" => Token::Divide(
+                    Box::new(number(1)),
+                    Box::new(Token::Exponent(
+                        Box::new(function("cos", u.clone())),
+                        Box::new(number(2)),
+                    )),
+                ),
+                "expand" | "factor" => number(1),
+                _ => bail!("Can't differentiate the function \"{}\"!", id),
+            };
+
+            multiply(outer, d(&u)?)
+        }
+        _ => bail!("Only expressions can be differentiated!"),
+    })
+}
+
+/// Numerically integrates an expression over a symbol from a to b with adaptive Gauss-Kronrod quadrature.
+///
+/// The range is split where the difference between the 15 point Kronrod and 7 point Gauss rules says the error is largest, until the total error
+/// estimate is within the session's decimal places. Returns a ConvergenceError if that doesn't happen within a reasonable number of splits.
+///
+pub fn integrate(
+    expression: &Token,
+    symbol: &str,
+    a: &Number,
+    b: &Number,
+    session: &mut Session,
+) -> Result<Number, Box<dyn Error>> {
+    let decimal_places = session.decimal_places;
+    let working = decimal_places + NUMERIC_GUARD_DIGITS;
+    let tolerance = Number::one().divide(&power_of_ten(decimal_places + 2));
+
+    let nodes = parse_constants(&KRONROD_NODES);
+    let kronrod_weights = parse_constants(&KRONROD_WEIGHTS);
+    let gauss_weights = parse_constants(&GAUSS_WEIGHTS);
+
+    with_precision(session, working, |session| {
+        // Each interval is its bounds, its integral and its error estimate
+        let mut intervals = Vec::<(Number, Number, Number, Number)>::new();
+
+        let mut gauss_kronrod = |a: &Number, b: &Number| -> Result<_, Box<dyn Error>> {
+            let center = a.add(b).divide(&Number::from_i64(2));
+            let half_width = b.subtract(a).divide(&Number::from_i64(2));
+
+            let mut kronrod = Number::zero();
+            let mut gauss = Number::zero();
+
+            for (i, node) in nodes.iter().enumerate() {
+                let offset = half_width.multiply(node);
+
+                let value = match node.is_zero() {
+                    true => evaluate(expression, symbol, &center, session)?,
+                    false => evaluate(expression, symbol, &center.add(&offset), session)?.add(
+                        &evaluate(expression, symbol, &center.subtract(&offset), session)?,
+                    ),
+                };
+
+                kronrod = kronrod.add(&value.multiply(&kronrod_weights[i]));
+
+                if i % 2 == 1 {
+                    gauss = gauss.add(&value.multiply(&gauss_weights[i / 2]));
+                }
+            }
+
+            let kronrod = kronrod.multiply(&half_width).approximate(working);
+            let gauss = gauss.multiply(&half_width).approximate(working);
+            let error = kronrod.subtract(&gauss).abs();
+
+            Ok((a.clone(), b.clone(), kronrod, error))
+        };
+
+        intervals.push(gauss_kronrod(a, b)?);
+
+        loop {
+            let total = intervals
+                .iter()
+                .fold(Number::zero(), |total, interval| total.add(&interval.2));
+            let error = intervals
+                .iter()
+                .fold(Number::zero(), |error, interval| error.add(&interval.3));
+
+            // Large results only need to be accurate relative to their size
+            let allowed = match total.abs() > Number::one() {
+                true => tolerance.multiply(&total.abs()),
+                false => tolerance.clone(),
+            };
+
+            if error <= allowed {
+                return Ok(total.round(decimal_places + 2));
+            }
+
+            if intervals.len() >= MAX_INTEGRATION_INTERVALS {
+                return Err(Box::new(ConvergenceError {
+                    method: "integrate".to_string(),
+                    error_estimate: error,
+                    tolerance: allowed,
+                }) as Box<dyn Error>);
+            }
+
+            // Split the interval with the largest error in half
+            let worst = (0..intervals.len())
+                .reduce(|worst, i| match intervals[i].3 > intervals[worst].3 {
+                    true => i,
+                    false => worst,
+                })
+                .unwrap(); // There's always at least one interval
+
+            let (a, b, _, _) = intervals.swap_remove(worst);
+            let middle = a.add(&b).divide(&Number::from_i64(2));
+
+            intervals.push(gauss_kronrod(&a, &middle)?);
+            intervals.push(gauss_kronrod(&middle, &b)?);
+        }
+    })
+}
+
+/// Numerically differentiates an expression over a symbol at a point with Ridders' method.
+///
+/// Central differences with shrinking steps are extrapolated towards a step of zero, with the error estimated from how much each extrapolation
+/// moves. Returns a ConvergenceError if the error estimate doesn't get within the session's decimal places.
+///
+pub fn nderiv(
+    expression: &Token,
+    symbol: &str,
+    at: &Number,
+    session: &mut Session,
+) -> Result<Number, Box<dyn Error>> {
+    let decimal_places = session.decimal_places;
+    let working = decimal_places + NUMERIC_GUARD_DIGITS;
+    let tolerance = Number::one().divide(&power_of_ten(decimal_places + 2));
+
+    with_precision(session, working, |session| {
+        let mut central_difference = |step: &Number| -> Result<Number, Box<dyn Error>> {
+            let above = evaluate(expression, symbol, &at.add(step), session)?;
+            let below = evaluate(expression, symbol, &at.subtract(step), session)?;
+
+            Ok(above
+                .subtract(&below)
+                .divide(&Number::from_i64(2).multiply(step))
+                .approximate(working))
+        };
+
+        let four = Number::from_i64(4);
+        let mut step = Number::from_str("0.1").unwrap();
+
+        // Each row of the table is one more extrapolation of the row before it
+        let mut previous_row = vec![central_difference(&step)?];
+        let mut best = previous_row[0].clone();
+        let mut best_error: Option<Number> = None;
+
+        for _ in 1..MAX_DERIVATIVE_STEPS {
+            step = step.divide(&Number::from_i64(2));
+
+            let mut row = vec![central_difference(&step)?];
+            let mut factor = four.clone();
+
+            for j in 1..=previous_row.len() {
+                // Halving the step cancels the next even power of the step out of the error
+                let extrapolated = row[j - 1]
+                    .multiply(&factor)
+                    .subtract(&previous_row[j - 1])
+                    .divide(&factor.subtract(&Number::one()))
+                    .approximate(working);
+
+                let error = match extrapolated.subtract(&row[j - 1]).abs()
+                    > extrapolated.subtract(&previous_row[j - 1]).abs()
+                {
+                    true => extrapolated.subtract(&row[j - 1]).abs(),
+                    false => extrapolated.subtract(&previous_row[j - 1]).abs(),
+                };
+
+                let improves = match &best_error {
+                    Some(best_error) => error <= *best_error,
+                    None => true,
+                };
+
+                if improves {
+                    best = extrapolated.clone();
+                    best_error = Some(error);
+                }
+
+                factor = factor.multiply(&four);
+                row.push(extrapolated);
+            }
+
+            let diagonal_move = row[row.len() - 1]
+                .subtract(&previous_row[previous_row.len() - 1])
+                .abs();
+
+            previous_row = row;
+
+            if let Some(best_error) = &best_error {
+                // Done once accurate enough, or once rounding starts making the extrapolations worse
+                if *best_error <= tolerance
+                    || diagonal_move >= Number::from_i64(2).multiply(best_error)
+                {
+                    break;
+                }
+            }
+        }
+
+        let best_error = best_error.unwrap_or_else(Number::zero);
+
+        match best_error <= tolerance {
+            true => Ok(best.round(decimal_places + 2)),
+            false => Err(Box::new(ConvergenceError {
+                method: "nderiv".to_string(),
+                error_estimate: best_error,
+                tolerance,
+            }) as Box<dyn Error>),
+        }
+    })
+}
+
+/// Evaluates an expression with a number in place of a symbol, failing if the result isn't a finite number.
+///
+/// **PRIVATE FUNCTION**
+///
+fn evaluate(
+    expression: &Token,
+    symbol: &str,
+    x: &Number,
+    session: &mut Session,
+) -> Result<Number, Box<dyn Error>> {
+    match op_engine::simplify(
+        &expression.substitute(symbol, &Token::Number(x.clone())),
+        session,
+    )? {
+        Token::Number(number) if number.is_finite() => Ok(number),
+        result => bail!(
+            "Expression must be a finite number when {} = {}, not {}!",
+            symbol,
+            x.to_string(3),
+            result.to_string(session)
+        ),
+    }
+}
+
+/// Runs a closure with the session working to more decimal places, putting the decimal places back afterwards even if it fails.
+///
+/// **PRIVATE FUNCTION**
+///
+fn with_precision<T, F>(
+    session: &mut Session,
+    decimal_places: u32,
+    function: F,
+) -> Result<T, Box<dyn Error>>
+where
+    F: FnOnce(&mut Session) -> Result<T, Box<dyn Error>>,
+{
+    let original = session.decimal_places;
+
+    session.decimal_places = decimal_places;
+
+    let result = function(session);
+
+    session.decimal_places = original;
+
+    result
+}
+
+/// Parses a table of decimal constants.
+///
+/// **PRIVATE FUNCTION**
+///
+fn parse_constants(constants: &[&str]) -> Vec<Number> {
+    constants
+        .iter()
+        .map(|constant| Number::from_str(constant).unwrap()) // The constants are all valid decimals
+        .collect()
+}
+
+/// Gets 10 to the power of a whole number.
+///
+/// **PRIVATE FUNCTION**
+///
+fn power_of_ten(power: u32) -> Number {
+    Number::from_i64(10).exponent(&Number::from_i64(power as i64), 0)
+}
+
+/// Creates a number token from a whole number.
+///
+/// **PRIVATE FUNCTION**
+///
+fn number(number: i64) -> Token {
+    Token::Number(Number::from_i64(number))
+}
+
+/// Creates a multiply token.
+///
+/// **PRIVATE FUNCTION**
+///
+fn multiply(left: Token, right: Token) -> Token {
+    Token::Multiply(Box::new(left), Box::new(right))
+}
+
+/// Creates a call to a single argument function.
+///
+/// **PRIVATE FUNCTION**
+///
+fn function(id: &str, arg: Token) -> Token {
+    Token::Function(id.to_string(), vec![arg])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::op_engine;
+    use crate::parser;
+    use crate::session::Session;
+
+    #[test]
+    fn test_calculus_derivative() {
+        let mut session = Session::_new_test().unwrap();
+
+        let expressions = [
+            ("diff(x^3 + 2x, x)", "3x^2 + 2"),
+            ("diff(5, x)", "0"),
+            ("diff(x*y, x)", "y"),
+            ("diff(1/x, x)", "-1 / x^2"),
+            ("diff(sin(x), x)", "cos(x)"),
+            ("diff(cos(2x), x)", "-2 * sin(2x)"),
+            ("diff(ln(x^2), x)", "2 / x"),
+            ("diff(exp(x)*x, x)", "x * exp(x) + exp(x)"),
+            ("diff(x^x, x)", "( ln(x) + 1 ) * x^x"),
+            ("diff(x^3, x, 2)", "12"),
+            ("diff(2^x, x)", "0.693147... * 2^x"),
+        ];
+
+        for (expression, expected) in expressions {
+            let tokenized_expression = parser::parse_str(expression, &mut session).unwrap();
+            let result = op_engine::simplify(&tokenized_expression, &mut session).unwrap();
+
+            assert_eq!(result.to_string(&session), expected, "{}", expression);
+        }
+
+        // Can only differentiate with respect to a symbol
+        let tokenized_expression = parser::parse_str("diff(x, 2)", &mut session).unwrap();
+
+        op_engine::simplify(&tokenized_expression, &mut session).unwrap_err();
+    }
+
+    #[test]
+    fn test_calculus_numeric() {
+        let mut session = Session::_new_test().unwrap();
+
+        let expressions = [
+            ("integrate(x^2, x, 0, 3)", "9"),
+            ("integrate(sin(x), x, 0, pi)", "2"),
+            ("integrate(exp(x), x, 0, 1)", "1.718281..."),
+            ("nderiv(x^3, x, 2)", "12"),
+            ("nderiv(ln(x), x, 2)", "0.5"),
+        ];
+
+        for (expression, expected) in expressions {
+            let tokenized_expression = parser::parse_str(expression, &mut session).unwrap();
+            let result = op_engine::simplify(&tokenized_expression, &mut session).unwrap();
+
+            assert_eq!(result.to_string(&session), expected, "{}", expression);
+        }
+
+        // The working precision is only borrowed
+        assert_eq!(
+            session.decimal_places,
+            Session::_new_test().unwrap().decimal_places
+        );
+
+        // Diverging integrals give up with a convergence error
+        let tokenized_expression =
+            parser::parse_str("integrate(1/x, x, 0, 1)", &mut session).unwrap();
+        let error = op_engine::simplify(&tokenized_expression, &mut session).unwrap_err();
+
+        assert!(error.downcast_ref::<ConvergenceError>().is_some());
+    }
+}
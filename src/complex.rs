@@ -0,0 +1,210 @@
+//! Built-in complex number type.
+//!
+
+// Copyright (c) 2022 Charles M. Thompson
+//
+// This file is part of ApeCrunch.
+//
+// ApeCrunch is free software: you can redistribute it and/or modify it under
+// the terms only of version 3 of the GNU General Public License as published
+// by the Free Software Foundation
+//
+// ApeCrunch is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License
+// for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// ApeCrunch(in a file named COPYING).
+// If not, see <https://www.gnu.org/licenses/>.
+
+use crate::number::Number;
+use serde::Deserialize;
+use serde::Serialize;
+use simple_error::*;
+use std::error::Error;
+
+/// Largest power a complex number can be raised to, since both parts grow with the power.
+///
+/// **NOT PUBLIC.**
+///
+const MAX_POWER: u64 = 1 << 12;
+
+/// Complex number made of two Numbers, so both parts stay exact fractions.
+///
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Complex {
+    /// Real part.
+    pub real: Number,
+    /// Imaginary part.
+    pub imaginary: Number,
+}
+
+impl Complex {
+    pub fn new(real: Number, imaginary: Number) -> Self {
+        Self { real, imaginary }
+    }
+
+    /// Creates a complex number with no imaginary part.
+    ///
+    pub fn from_real(real: Number) -> Self {
+        Self::new(real, Number::zero())
+    }
+
+    /// Renders the complex number to a string, such as "1 + 2i".
+    ///
+    pub fn to_string(&self, prec: u32) -> String {
+        if self.imaginary.is_zero() {
+            return self.real.to_string(prec);
+        }
+
+        let imaginary = match self.imaginary.abs() == Number::one() {
+            true => "i".to_string(),
+            false => format!("{}i", self.imaginary.abs().to_string(prec)),
+        };
+
+        match (self.real.is_zero(), self.imaginary.is_negative()) {
+            (true, false) => imaginary,
+            (true, true) => format!("-{}", imaginary),
+            (false, false) => format!("{} + {}", self.real.to_string(prec), imaginary),
+            (false, true) => format!("{} - {}", self.real.to_string(prec), imaginary),
+        }
+    }
+
+    /// Returns true if both parts are zero.
+    ///
+    pub fn is_zero(&self) -> bool {
+        self.real.is_zero() && self.imaginary.is_zero()
+    }
+
+    /// Returns true if there is no imaginary part.
+    ///
+    pub fn is_real(&self) -> bool {
+        self.imaginary.is_zero()
+    }
+
+    /// Adds this number to another number.
+    ///
+    pub fn add(&self, other: &Complex) -> Complex {
+        Self::new(
+            self.real.add(&other.real),
+            self.imaginary.add(&other.imaginary),
+        )
+    }
+
+    /// Subtracts a number from this number.
+    ///
+    pub fn subtract(&self, other: &Complex) -> Complex {
+        Self::new(
+            self.real.subtract(&other.real),
+            self.imaginary.subtract(&other.imaginary),
+        )
+    }
+
+    /// Multiplies this number by another number.
+    ///
+    pub fn multiply(&self, other: &Complex) -> Complex {
+        Self::new(
+            self.real
+                .multiply(&other.real)
+                .subtract(&self.imaginary.multiply(&other.imaginary)),
+            self.real
+                .multiply(&other.imaginary)
+                .add(&self.imaginary.multiply(&other.real)),
+        )
+    }
+
+    /// Divides this number by another number.
+    ///
+    pub fn divide(&self, other: &Complex) -> Complex {
+        let divisor = other
+            .real
+            .multiply(&other.real)
+            .add(&other.imaginary.multiply(&other.imaginary));
+
+        Self::new(
+            self.real
+                .multiply(&other.real)
+                .add(&self.imaginary.multiply(&other.imaginary))
+                .divide(&divisor),
+            self.imaginary
+                .multiply(&other.real)
+                .subtract(&self.real.multiply(&other.imaginary))
+                .divide(&divisor),
+        )
+    }
+
+    /// Raises the number to an integer power, dividing one by the result for negative powers.
+    ///
+    pub fn power(&self, exponent: i64) -> Result<Complex, Box<dyn Error>> {
+        if exponent.unsigned_abs() > MAX_POWER {
+            bail!(
+                "Complex numbers can only be raised to powers up to {}!",
+                MAX_POWER
+            );
+        }
+
+        if exponent < 0 && self.is_zero() {
+            bail!("Can't raise zero to a negative power!");
+        }
+
+        // Exponentiation by squaring, going through the bits of the power from the lowest
+        let mut result = Self::from_real(Number::one());
+        let mut square = self.clone();
+        let mut bits = exponent.unsigned_abs();
+
+        while bits > 0 {
+            if bits & 1 == 1 {
+                result = result.multiply(&square);
+            }
+
+            bits >>= 1;
+
+            if bits > 0 {
+                square = square.multiply(&square);
+            }
+        }
+
+        match exponent < 0 {
+            true => Ok(Self::from_real(Number::one()).divide(&result)),
+            false => Ok(result),
+        }
+    }
+
+    /// Sum of the absolute values of both parts.
+    ///
+    /// Cheaper than the true magnitude since it needs no square root, and just as good for checking how close to zero a number is.
+    ///
+    pub fn manhattan_norm(&self) -> Number {
+        self.real.abs().add(&self.imaginary.abs())
+    }
+
+    /// Approximates both parts with a denominator of 10^decimal_places.
+    ///
+    pub fn approximate(&self, decimal_places: u32) -> Complex {
+        Self::new(
+            self.real.approximate(decimal_places),
+            self.imaginary.approximate(decimal_places),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complex_arithmetic() {
+        let a = Complex::new(Number::from_i64(1), Number::from_i64(2));
+        let b = Complex::new(Number::from_i64(3), Number::from_i64(-1));
+
+        assert_eq!(a.multiply(&b).to_string(6), "5 + 5i");
+        assert_eq!(a.multiply(&b).divide(&b), a);
+        assert_eq!(a.subtract(&a).to_string(6), "0");
+        assert_eq!(b.to_string(6), "3 - i");
+        assert_eq!(a.power(2).unwrap().to_string(6), "-3 + 4i");
+        assert_eq!(a.power(-1).unwrap().multiply(&a).to_string(6), "1");
+        assert_eq!(a.power(0).unwrap().to_string(6), "1");
+        a.power(1 << 20).unwrap_err();
+    }
+}
@@ -0,0 +1,327 @@
+//! Exporters for the whole history, for pasting calculations into notebooks and spreadsheets.
+//!
+
+// Copyright (c) 2022 Charles M. Thompson
+//
+// This file is part of ApeCrunch.
+//
+// ApeCrunch is free software: you can redistribute it and/or modify it under
+// the terms only of version 3 of the GNU General Public License as published
+// by the Free Software Foundation
+//
+// ApeCrunch is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License
+// for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// ApeCrunch(in a file named COPYING).
+// If not, see <https://www.gnu.org/licenses/>.
+
+use crate::history;
+use crate::number::ExactFraction;
+use crate::parser::Token;
+use crate::session::Session;
+use clap::ArgEnum;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::error::Error;
+use uuid::Uuid;
+
+/// Formats the history can be exported in.
+///
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Entries as they're shown in the TUI
+    Text,
+    /// Comma separated values with a header row
+    Csv,
+    /// Array of objects, one per entry
+    Json,
+    /// Markdown table
+    Markdown,
+}
+
+impl ExportFormat {
+    /// Gets the file extension used for exports in the format, without the dot.
+    ///
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Text => "txt",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// Single exported history entry, along with the session it was calculated in.
+///
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExportRecord {
+    /// Expression as it was entered.
+    pub expression: String,
+    /// Result as it's shown in the TUI.
+    pub result: String,
+    /// Exact fraction of the result, if it's a number.
+    pub fraction: Option<ExactFraction>,
+    /// UUID of the session the entry was calculated in.
+    pub session_uuid: Uuid,
+    /// Start of the session, as a UTC date and time.
+    pub session_start: String,
+    /// UUID of the entry.
+    pub entry_uuid: Uuid,
+}
+
+/// Column headers of CSV and Markdown exports, in the order of the fields of ExportRecord.
+///
+/// **NOT PUBLIC.**
+///
+const EXPORT_HEADERS: [&str; 6] = [
+    "expression",
+    "result",
+    "fraction",
+    "session_uuid",
+    "session_start",
+    "entry_uuid",
+];
+
+/// Gets a record for every entry of an initialized session, oldest first.
+///
+/// Entries whose history file was removed since the session started are left out, since their session can't be found.
+///
+/// Returns an i/o error if the history files can't be read to find the sessions of previous entries.
+///
+pub fn get_records(session: &Session) -> Result<Vec<ExportRecord>, Box<dyn Error>> {
+    let mut entry_sessions = HashMap::<Uuid, (Uuid, u64)>::new();
+
+    for history_file in session.get_history_files()? {
+        for entry in &history_file.bincode.entries {
+            entry_sessions.insert(
+                entry.entry_uuid,
+                (
+                    history_file.bincode.session_uuid,
+                    history_file.bincode.session_start,
+                ),
+            );
+        }
+    }
+
+    // The file of the current session may not have been written yet
+    for entry in &session.entries {
+        entry_sessions.insert(
+            entry.entry_uuid,
+            (session.session_uuid, session.session_start),
+        );
+    }
+
+    Ok(session
+        .get_entries()
+        .iter()
+        .filter_map(|entry| {
+            let (session_uuid, session_start) = *entry_sessions.get(&entry.entry_uuid)?;
+            let result = entry.only_equality();
+
+            Some(ExportRecord {
+                expression: entry.render_without_equality(session),
+                result: result.to_string(session),
+                fraction: match result {
+                    Token::Number(number) => number.to_exact_fraction(),
+                    _ => None,
+                },
+                session_uuid,
+                session_start: history::format_timestamp(session_start),
+                entry_uuid: entry.entry_uuid,
+            })
+        })
+        .collect())
+}
+
+/// Exports every entry of an initialized session in the given format, ending with a newline.
+///
+/// Returns an i/o error if the history files can't be read.
+///
+pub fn export(session: &Session, format: ExportFormat) -> Result<String, Box<dyn Error>> {
+    if format == ExportFormat::Text {
+        let mut text = String::new();
+
+        for entry in session.get_entries() {
+            text.push_str(&format!("{}\n", entry));
+        }
+
+        return Ok(text);
+    }
+
+    let records = get_records(session)?;
+
+    Ok(match format {
+        ExportFormat::Csv => {
+            let mut csv = format!("{}\n", EXPORT_HEADERS.join(","));
+
+            for record in &records {
+                let fields: Vec<String> = record_fields(record)
+                    .iter()
+                    .map(|field| csv_field(field))
+                    .collect();
+
+                csv.push_str(&format!("{}\n", fields.join(",")));
+            }
+
+            csv
+        }
+        ExportFormat::Json => format!("{}\n", serde_json::to_string_pretty(&records)?),
+        ExportFormat::Markdown => {
+            let mut markdown = format!("| {} |\n", EXPORT_HEADERS.join(" | "));
+
+            markdown.push_str(&format!("|{}\n", " --- |".repeat(EXPORT_HEADERS.len())));
+
+            for record in &records {
+                let cells: Vec<String> = record_fields(record)
+                    .iter()
+                    .map(|field| markdown_cell(field))
+                    .collect();
+
+                markdown.push_str(&format!("| {} |\n", cells.join(" | ")));
+            }
+
+            markdown
+        }
+        ExportFormat::Text => unreachable!(), // Handled above, since it doesn't need the records
+    })
+}
+
+/// Gets the fields of a record as strings, in the order of EXPORT_HEADERS.
+///
+/// Fractions are written as "numerator/denominator", or just the numerator for whole numbers.
+///
+/// **PRIVATE FUNCTION**
+///
+fn record_fields(record: &ExportRecord) -> [String; 6] {
+    let fraction = match &record.fraction {
+        Some(fraction) if fraction.denominator == "1" => fraction.numerator.clone(),
+        Some(fraction) => format!("{}/{}", fraction.numerator, fraction.denominator),
+        None => String::new(),
+    };
+
+    [
+        record.expression.clone(),
+        record.result.clone(),
+        fraction,
+        record.session_uuid.to_string(),
+        record.session_start.clone(),
+        record.entry_uuid.to_string(),
+    ]
+}
+
+/// Quotes a CSV field if it holds a comma, quote or line break, doubling any quotes inside it.
+///
+/// **PRIVATE FUNCTION**
+///
+fn csv_field(field: &str) -> String {
+    match field.contains(&[',', '"', '\n', '\r'][..]) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+/// Escapes the characters that would break a Markdown table cell or be read as formatting.
+///
+/// **PRIVATE FUNCTION**
+///
+fn markdown_cell(field: &str) -> String {
+    let mut cell = String::new();
+
+    for c in field.chars() {
+        match c {
+            '|' | '\\' | '*' | '_' | '`' => {
+                cell.push('\\');
+                cell.push(c);
+            }
+            '\n' => cell.push_str("<br>"),
+            _ => cell.push(c),
+        }
+    }
+
+    cell
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::*;
+
+    #[test]
+    #[serial]
+    fn test_export_formats() {
+        let mut previous = Session::_new_test().unwrap();
+
+        previous.init().unwrap();
+        previous.session_start = 1_664_546_700;
+        previous.evaluate_line("1/4 -> a_b").unwrap();
+        previous.update_file().unwrap();
+
+        let mut session = Session::_new_test().unwrap();
+
+        session.init().unwrap();
+        session.session_start = 1_664_550_300;
+        session.evaluate_line("[1, 2] * a_b; 1/3").unwrap();
+
+        let records = get_records(&session).unwrap();
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].session_uuid, previous.session_uuid);
+        assert_eq!(records[0].session_start, "2022-09-30 14:05:00 UTC");
+        assert_eq!(records[2].session_uuid, session.session_uuid);
+        assert_eq!(records[2].session_start, "2022-09-30 15:05:00 UTC");
+        assert_eq!(records[1].fraction, None);
+
+        let ids = |i: usize| {
+            format!(
+                "{},{},{}",
+                records[i].session_uuid, records[i].session_start, records[i].entry_uuid
+            )
+        };
+
+        assert_eq!(
+            export(&session, ExportFormat::Csv).unwrap(),
+            format!(
+                "expression,result,fraction,session_uuid,session_start,entry_uuid\n\
+                 1 / 4 -> a_b,0.25,1/4,{}\n\
+                 \"[[1, 2]] * a_b\",\"[[0.25, 0.5]]\",,{}\n\
+                 1 / 3,0.333333...,1/3,{}\n",
+                ids(0),
+                ids(1),
+                ids(2)
+            )
+        );
+
+        let markdown = export(&session, ExportFormat::Markdown).unwrap();
+        let rows: Vec<&str> = markdown.lines().collect();
+
+        assert_eq!(rows.len(), 5);
+        assert_eq!(rows[1], "| --- | --- | --- | --- | --- | --- |");
+        assert!(rows[2].starts_with("| 1 / 4 -> a\\_b | 0.25 | 1/4 | "));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&export(&session, ExportFormat::Json).unwrap()).unwrap();
+
+        assert_eq!(json[2]["expression"], "1 / 3");
+        assert_eq!(json[2]["fraction"]["denominator"], "3");
+        assert_eq!(json[2]["entry_uuid"], records[2].entry_uuid.to_string());
+
+        assert_eq!(
+            export(&session, ExportFormat::Text).unwrap(),
+            "1 / 4 -> a_b = 0.25\n[ 1  2 ] * a_b = [ 0.25  0.5 ]\n1 / 3 = 0.333333...\n"
+        );
+
+        // Entries from a history file removed after startup are left out instead of panicking
+        std::fs::remove_file(&previous.history_file_path).unwrap();
+
+        let records = get_records(&session).unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].expression, "[[1, 2]] * a_b");
+
+        session._test_purge().unwrap();
+    }
+}
@@ -1,5 +1,5 @@
 //! Built-in functions and function-table type.
-//! 
+//!
 
 // Copyright (c) 2022 Charles M. Thompson
 //
@@ -16,58 +16,718 @@
 //
 // You should have received a copy of the GNU General Public License along with
 // ApeCrunch(in a file named COPYING).
-// If not, see <https://www.gnu.org/licenses/>. 
+// If not, see <https://www.gnu.org/licenses/>.
 
-use std::error::Error;
+use crate::algebra;
+use crate::calculus;
+use crate::complex::Complex;
+use crate::matrix::Matrix;
 use crate::number::Number;
+use crate::op_engine;
+use crate::parser::Token;
+use crate::polynomial;
+use crate::polynomial::Polynomial;
 use crate::session::Session;
+use lazy_static::*;
 use simple_error::*;
+use std::error::Error;
 
-pub enum Function {
-    OneArgN(Box<dyn Fn(Session, Number) -> Number>)
-}
+/// Signature of every built-in function.
+///
+/// Arguments are handed over unsimplified so each function can decide how and when they get evaluated.
+///
+pub type Function = fn(&[Token], &mut Session) -> Result<Token, Box<dyn Error>>;
 
-impl Function {
-    pub fn new_one_n(function: impl Fn(Session, Number) -> Number + 'static) -> Self {
-        Self::OneArgN(Box::new(function))
-    }
-}
+/// Signature of the part of an elementary function that works on a single number, given the session for the decimal places to work to
+/// and to note any approximation in.
+///
+type NumberFunction = fn(&Number, &mut Session) -> Result<Token, Box<dyn Error>>;
 
+/// Struct for a single entry in the function table
+///
+#[derive(Debug, Clone)]
 pub struct FunctionEntry {
+    /// ID, the name the function is called by
     pub id: String,
-    pub function: Function
+    /// Minimum number of arguments accepted
+    pub min_args: usize,
+    /// Maximum number of arguments accepted
+    pub max_args: usize,
+    /// Index of the argument naming a symbol bound in the other arguments, like the x in roots(x^2 - 1, x)
+    pub bound_arg: Option<usize>,
+    /// The function itself
+    pub function: Function,
 }
 
 impl FunctionEntry {
-    pub fn new_one_n(id: String, function: impl Fn(Session, Number) -> Number + 'static) -> Self {
+    pub fn new(id: &str, min_args: usize, max_args: usize, function: Function) -> Self {
         Self {
-            id: id,
-            function: Function::new_one_n(function)
+            id: id.to_owned(),
+            min_args,
+            max_args,
+            bound_arg: None,
+            function,
         }
     }
+
+    /// Creates a function entry where one of the arguments names a symbol bound in the other arguments
+    ///
+    pub fn new_binding(
+        id: &str,
+        min_args: usize,
+        max_args: usize,
+        bound_arg: usize,
+        function: Function,
+    ) -> Self {
+        Self {
+            bound_arg: Some(bound_arg),
+            ..Self::new(id, min_args, max_args, function)
+        }
+    }
+
+    /// Makes sure the function accepts the given number of arguments
+    ///
+    pub fn check_arg_count(&self, count: usize) -> Result<(), Box<dyn Error>> {
+        if count < self.min_args || count > self.max_args {
+            match self.min_args == self.max_args {
+                true => bail!(
+                    "Function \"{}\" takes {} argument(s), {} given!",
+                    self.id,
+                    self.min_args,
+                    count
+                ),
+                false => bail!(
+                    "Function \"{}\" takes {} to {} arguments, {} given!",
+                    self.id,
+                    self.min_args,
+                    self.max_args,
+                    count
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Calls the function, failing if the wrong number of arguments were given
+    ///
+    pub fn call(&self, args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+        self.check_arg_count(args.len())?;
+
+        (self.function)(args, session)
+    }
 }
 
+/// Struct for the function table, kept sorted by id
+///
+#[derive(Debug, Clone)]
 pub struct FunctionTable {
-    functionTable: Vec::<FunctionEntry>,
+    /// Vector of functions
+    pub functions: Vec<FunctionEntry>,
+}
+
+impl Default for FunctionTable {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl FunctionTable {
+    /// Create an empty FunctionTable
+    ///
     pub fn new() -> Self {
         Self {
-            functionTable: Vec::<FunctionEntry>::new()
+            functions: Vec::<FunctionEntry>::new(),
         }
     }
 
     /// Add function to the function table, fail if it already exists
-    pub fn add_one_n(&mut self, id: String, function: impl Fn(Session, Number) -> Number + 'static) -> Result<(), Box<dyn Error>> {
-        match self.functionTable.binary_search_by(|i| i.id.cmp(&id)) {
+    ///
+    pub fn add(&mut self, entry: FunctionEntry) -> Result<(), Box<dyn Error>> {
+        match self.functions.binary_search_by(|i| i.id.cmp(&entry.id)) {
             Ok(_) => {
-                bail!("Function \"{}\" already exists!");
+                bail!("Function \"{}\" already exists!", entry.id);
             }
             Err(i) => {
-                self.functionTable.insert(i, FunctionEntry::new_one_n(id, function))
+                self.functions.insert(i, entry);
             }
         }
+
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Get a function from the FunctionTable given just the id
+    ///
+    pub fn get(&self, id: &str) -> Option<&FunctionEntry> {
+        match self.functions.binary_search_by(|i| i.id.as_str().cmp(id)) {
+            Ok(i) => self.functions.get(i),
+            Err(_) => None,
+        }
+    }
+}
+
+/// Most arguments roots() takes, enough for the coefficients of the highest degree polynomial it can solve
+///
+const MAX_ROOTS_ARGS: usize = polynomial::MAX_DEGREE + 1;
+
+/// Most arguments if() takes, enough for 32 conditions and a fallback
+///
+const MAX_IF_ARGS: usize = 65;
+
+/// Most terms sum() and prod() will evaluate, so a typo in a bound doesn't hang the calculator
+///
+const MAX_SERIES_TERMS: i64 = 100_000;
+
+/// Gets a built-in function given just the id
+///
+pub fn get_builtin(id: &str) -> Option<&'static FunctionEntry> {
+    lazy_static! {
+        static ref BUILTINS: FunctionTable = builtin_table();
+    }
+
+    BUILTINS.get(id)
+}
+
+/// Creates the table of every built-in function.
+///
+/// **PRIVATE FUNCTION**
+///
+fn builtin_table() -> FunctionTable {
+    let mut table = FunctionTable::new();
+
+    let entries = [
+        FunctionEntry::new("cos", 1, 1, cos),
+        FunctionEntry::new("det", 1, 1, det),
+        FunctionEntry::new_binding("diff", 2, 3, 1, diff),
+        FunctionEntry::new("exp", 1, 1, exp),
+        FunctionEntry::new("expand", 1, 1, expand),
+        FunctionEntry::new("factor", 1, 1, factor),
+        FunctionEntry::new("identity", 1, 1, identity),
+        FunctionEntry::new("if", 3, MAX_IF_ARGS, if_),
+        FunctionEntry::new_binding("integrate", 4, 4, 1, integrate),
+        FunctionEntry::new("inv", 1, 1, inv),
+        FunctionEntry::new("ln", 1, 1, ln),
+        FunctionEntry::new_binding("nderiv", 3, 3, 1, nderiv),
+        FunctionEntry::new_binding("prod", 4, 4, 0, prod),
+        FunctionEntry::new_binding("roots", 1, MAX_ROOTS_ARGS, 1, roots),
+        FunctionEntry::new("sin", 1, 1, sin),
+        FunctionEntry::new_binding("solve", 2, 3, 1, solve),
+        FunctionEntry::new("sqrt", 1, 1, sqrt),
+        FunctionEntry::new_binding("sum", 4, 4, 0, sum),
+        FunctionEntry::new("tan", 1, 1, tan),
+        FunctionEntry::new("transpose", 1, 1, transpose),
+    ];
+
+    for entry in entries {
+        table.add(entry).unwrap(); // Only fails if a built-in is defined twice, which is a bug
+    }
+
+    table
+}
+
+/// Applies a function of a single number to a simplified argument, leaving the call as it is if the argument has symbols in it.
+///
+/// **PRIVATE FUNCTION**
+///
+fn elementary(
+    id: &str,
+    args: &[Token],
+    session: &mut Session,
+    function: NumberFunction,
+) -> Result<Token, Box<dyn Error>> {
+    match op_engine::simplify(&args[0], session)? {
+        Token::Number(number) if number.is_finite() => {
+            match function(&number, session)? {
+                // Results like exp(10^7) are too big to work out, and sin(10^200) needs far too many digits of pi
+                Token::Number(result) if !result.is_finite() => {
+                    bail!("The argument of function \"{}\" is too big!", id)
+                }
+                result => Ok(result),
+            }
+        }
+        arg if arg.is_symbolic() => Ok(Token::Function(id.to_string(), vec![arg])),
+        arg => bail!(
+            "Function \"{}\" only works on numbers, not {}!",
+            id,
+            arg.to_string(session)
+        ),
+    }
+}
+
+/// Evaluates a term for every whole number value of a bound index between two bounds, folding the terms together.
+///
+/// The term is simplified symbolically first, then again for each value with the index locally bound, so rational terms stay exact. If
+/// there are no terms, the result is the given empty value.
+///
+/// **PRIVATE FUNCTION**
+///
+fn series(
+    id: &str,
+    args: &[Token],
+    session: &mut Session,
+    empty: Token,
+    combine: fn(Box<Token>, Box<Token>) -> Token,
+) -> Result<Token, Box<dyn Error>> {
+    let index = match &args[0] {
+        Token::Symbol(index) => index,
+        _ => bail!("The first argument of {} must be the index symbol!", id),
+    };
+
+    let bound = |arg: &Token, session: &mut Session| -> Result<i64, Box<dyn Error>> {
+        match op_engine::simplify(arg, session)? {
+            Token::Number(number) if number.is_integer() => match number.to_i64() {
+                Some(bound) => Ok(bound),
+                None => bail!("The bounds of {} are too large!", id),
+            },
+            arg => bail!(
+                "The bounds of {} must be whole numbers, not {}!",
+                id,
+                arg.to_string(session)
+            ),
+        }
+    };
+
+    let lower = bound(&args[1], session)?;
+    let upper = bound(&args[2], session)?;
+
+    if upper.saturating_sub(lower) >= MAX_SERIES_TERMS {
+        bail!("{} can only take up to {} terms!", id, MAX_SERIES_TERMS);
+    }
+
+    // Work the term out with the index as a symbol first, so functions like diff see the index before it's given values. Terms that only
+    // work with numbers, like if(i > 1, ...), are left as they are
+    let term = op_engine::simplify(&args[3], session).unwrap_or_else(|_| args[3].clone());
+
+    let mut result = empty;
+
+    for i in lower..=upper {
+        session
+            .local_bindings
+            .push((index.to_string(), Token::Number(Number::from_i64(i))));
+
+        let term = op_engine::simplify(&term, session);
+
+        // Unbind the index even if the term failed
+        session.local_bindings.pop();
+
+        result = op_engine::simplify(&combine(Box::new(result), Box::new(term?)), session)?;
+    }
+
+    Ok(result)
+}
+
+/// Simplifies an argument and converts it into a matrix.
+///
+/// **PRIVATE FUNCTION**
+///
+fn matrix_arg(arg: &Token, session: &mut Session) -> Result<Matrix, Box<dyn Error>> {
+    Matrix::from_token(&op_engine::simplify(arg, session)?)
+}
+
+/// Simplifies an argument that has to be a finite number.
+///
+/// **PRIVATE FUNCTION**
+///
+fn number_arg(id: &str, arg: &Token, session: &mut Session) -> Result<Number, Box<dyn Error>> {
+    match op_engine::simplify(arg, session)? {
+        Token::Number(number) if number.is_finite() => Ok(number),
+        arg => bail!(
+            "Function \"{}\" needs a number here, not {}!",
+            id,
+            arg.to_string(session)
+        ),
+    }
+}
+
+/// cos(x), the cosine of x in radians.
+///
+/// **PRIVATE FUNCTION**
+///
+fn cos(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    elementary("cos", args, session, |x, session| {
+        session.approximated |= !x.is_zero();
+
+        Ok(Token::Number(x.cos(session.decimal_places)))
+    })
+}
+
+/// det(M), the determinant of a square matrix.
+///
+/// **PRIVATE FUNCTION**
+///
+fn det(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    Ok(Token::Number(matrix_arg(&args[0], session)?.determinant()?))
+}
+
+/// diff(expr, x) or diff(expr, x, at), the derivative of an expression with respect to x, optionally evaluated at a point.
+///
+/// **PRIVATE FUNCTION**
+///
+fn diff(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    let symbol = match &args[1] {
+        Token::Symbol(symbol) => symbol,
+        _ => bail!(
+            "The second argument of diff must be the symbol to differentiate with respect to!"
+        ),
+    };
+
+    let expression = op_engine::simplify(&args[0], session)?;
+    let derivative = op_engine::simplify(&calculus::derivative(&expression, symbol)?, session)?;
+
+    match args.get(2) {
+        Some(at) => {
+            let at = op_engine::simplify(at, session)?;
+
+            op_engine::simplify(&derivative.substitute(symbol, &at), session)
+        }
+        None => Ok(derivative),
+    }
+}
+
+/// if(cond, a, b) or if(cond1, a1, cond2, a2, ..., b), the value for the first true condition, or the last value if none are.
+///
+/// Only the conditions up to the first true one and the value chosen get evaluated. If a condition has symbols in it, the rest is left as it is.
+///
+/// **PRIVATE FUNCTION**
+///
+fn if_(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    if args.len() % 2 != 1 {
+        bail!("Function \"if\" takes pairs of conditions and values, followed by a value for when no condition is true!");
+    }
+
+    for (i, pair) in args.chunks_exact(2).enumerate() {
+        let condition = op_engine::simplify(&pair[0], session)?;
+
+        match op_engine::truth(&condition, session)? {
+            Some(true) => return op_engine::simplify(&pair[1], session),
+            Some(false) => {}
+            None => {
+                let mut rest = vec![condition];
+                rest.extend_from_slice(&args[i * 2 + 1..]);
+
+                return Ok(Token::Function("if".to_string(), rest));
+            }
+        }
+    }
+
+    op_engine::simplify(&args[args.len() - 1], session)
+}
+
+/// integrate(expr, x, a, b), the numeric integral of an expression with respect to x from a to b.
+///
+/// **PRIVATE FUNCTION**
+///
+fn integrate(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    let symbol = match &args[1] {
+        Token::Symbol(symbol) => symbol,
+        _ => bail!("The second argument of integrate must be the symbol to integrate over!"),
+    };
+
+    let expression = op_engine::simplify(&args[0], session)?;
+    let a = number_arg("integrate", &args[2], session)?;
+    let b = number_arg("integrate", &args[3], session)?;
+
+    session.approximated = true;
+
+    Ok(Token::Number(calculus::integrate(
+        &expression,
+        symbol,
+        &a,
+        &b,
+        session,
+    )?))
+}
+
+/// nderiv(expr, x, at), the numeric derivative of an expression with respect to x at a point.
+///
+/// **PRIVATE FUNCTION**
+///
+fn nderiv(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    let symbol = match &args[1] {
+        Token::Symbol(symbol) => symbol,
+        _ => bail!(
+            "The second argument of nderiv must be the symbol to differentiate with respect to!"
+        ),
+    };
+
+    let expression = op_engine::simplify(&args[0], session)?;
+    let at = number_arg("nderiv", &args[2], session)?;
+
+    session.approximated = true;
+
+    Ok(Token::Number(calculus::nderiv(
+        &expression,
+        symbol,
+        &at,
+        session,
+    )?))
+}
+
+/// exp(x), e raised to the power of x.
+///
+/// **PRIVATE FUNCTION**
+///
+fn exp(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    elementary("exp", args, session, |x, session| {
+        session.approximated |= !x.is_zero();
+
+        Ok(Token::Number(x.exp(session.decimal_places)))
+    })
+}
+
+/// expand(expr), the expression with every product and whole number power of sums multiplied out.
+///
+/// **PRIVATE FUNCTION**
+///
+fn expand(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    let expression = op_engine::simplify(&args[0], session)?;
+
+    match expression.is_symbolic() {
+        true => Ok(algebra::expand(&expression)),
+        false => Ok(expression),
+    }
+}
+
+/// factor(expr), the expression with common factors and the rational roots of polynomials pulled out.
+///
+/// **PRIVATE FUNCTION**
+///
+fn factor(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    let expression = op_engine::simplify(&args[0], session)?;
+
+    match expression.is_symbolic() {
+        true => Ok(algebra::factor(&expression, session.decimal_places)),
+        false => Ok(expression),
+    }
+}
+
+/// identity(n), the n by n identity matrix.
+///
+/// **PRIVATE FUNCTION**
+///
+fn identity(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    let size = match op_engine::simplify(&args[0], session)? {
+        Token::Number(number) => number.to_i64(),
+        _ => None,
+    };
+
+    match size {
+        Some(size) if size > 0 => Ok(Matrix::identity(size as usize)?.to_token()),
+        _ => bail!("The size of an identity matrix must be a positive whole number!"),
+    }
+}
+
+/// inv(M), the inverse of a square matrix.
+///
+/// **PRIVATE FUNCTION**
+///
+fn inv(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    Ok(matrix_arg(&args[0], session)?.inverse()?.to_token())
+}
+
+/// ln(x), the natural logarithm of x.
+///
+/// **PRIVATE FUNCTION**
+///
+fn ln(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    elementary("ln", args, session, |x, session| {
+        session.approximated |= *x != Number::one();
+
+        match x.is_negative() || x.is_zero() {
+            true => bail!("The logarithm is only defined for positive numbers!"),
+            false => Ok(Token::Number(x.ln(session.decimal_places))),
+        }
+    })
+}
+
+/// prod(i, a, b, expr), the product of an expression for every whole number i from a to b.
+///
+/// **PRIVATE FUNCTION**
+///
+fn prod(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    series(
+        "prod",
+        args,
+        session,
+        Token::Number(Number::one()),
+        Token::Multiply,
+    )
+}
+
+/// roots(p, x) or roots(a, b, c, ...), every root of a polynomial, real and complex.
+///
+/// The polynomial is either an expression in the symbol x, or its coefficients starting from the highest power. Roots are returned as a single
+/// row matrix.
+///
+/// **PRIVATE FUNCTION**
+///
+fn roots(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    let polynomial = match args {
+        [expression, Token::Symbol(symbol)] => Polynomial::from_token(expression, symbol, session)?,
+        _ => {
+            let mut coefficients = Vec::<Number>::new();
+
+            for arg in args.iter().rev() {
+                match op_engine::simplify(arg, session)? {
+                    Token::Number(number) => coefficients.push(number),
+                    _ => bail!(
+                        "Coefficients of a polynomial must be numbers: {}",
+                        arg.to_string(session)
+                    ),
+                }
+            }
+
+            Polynomial::new(coefficients)
+        }
+    };
+
+    let roots = polynomial.roots(session.decimal_places)?;
+
+    // Roots found numerically are only exact if the polynomial comes out exactly zero at them
+    session.approximated |= roots
+        .iter()
+        .any(|root| !polynomial.evaluate(root).is_zero());
+
+    let roots = roots
+        .into_iter()
+        .map(|root| match root.is_real() {
+            true => Token::Number(root.real),
+            false => Token::Complex(root),
+        })
+        .collect();
+
+    Ok(Token::Matrix(vec![roots]))
+}
+
+/// sin(x), the sine of x in radians.
+///
+/// **PRIVATE FUNCTION**
+///
+fn sin(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    elementary("sin", args, session, |x, session| {
+        session.approximated |= !x.is_zero();
+
+        Ok(Token::Number(x.sin(session.decimal_places)))
+    })
+}
+
+/// solve(A, b) or solve(lhs = rhs, x, guess), either the exact solution x of the linear system Ax = b, or a numeric root of an equation.
+///
+/// Equations are solved for x starting from the guess, or zero if there's no guess.
+///
+/// **PRIVATE FUNCTION**
+///
+fn solve(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    if let Token::Symbol(symbol) = &args[1] {
+        if args[0].contains_symbol(symbol) {
+            let guess = match args.get(2) {
+                Some(guess) => match op_engine::simplify(guess, session)? {
+                    Token::Number(number) => number,
+                    _ => bail!("The starting guess for solve must be a number!"),
+                },
+                None => Number::zero(),
+            };
+
+            return Ok(Token::Number(op_engine::find_root(
+                &args[0], symbol, &guess, session,
+            )?));
+        }
+    }
+
+    if args.len() > 2 {
+        bail!("Linear systems are solved with just solve(A, b)!");
+    }
+
+    // The parser binds b as a symbol in case it was an equation's unknown, but here it's just a variable
+    let b = match &args[1] {
+        Token::Symbol(id) => Token::Variable(Box::new(session.vartable.get(id)?)),
+        b => b.clone(),
+    };
+
+    let a = matrix_arg(&args[0], session)?;
+    let b = matrix_arg(&b, session)?;
+
+    Ok(a.solve(&b)?.to_token())
+}
+
+/// sqrt(x), the square root of x, exact when x is a perfect square and imaginary when x is negative.
+///
+/// **PRIVATE FUNCTION**
+///
+fn sqrt(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    elementary("sqrt", args, session, |x, session| {
+        let root = match x.abs().exact_sqrt() {
+            Some(root) => root,
+            None => {
+                session.approximated = true;
+
+                x.abs().root(&Number::from_i64(2), session.decimal_places)
+            }
+        };
+
+        match x.is_negative() {
+            true => Ok(Token::Complex(Complex::new(Number::zero(), root))),
+            false => Ok(Token::Number(root)),
+        }
+    })
+}
+
+/// sum(i, a, b, expr), the sum of an expression for every whole number i from a to b.
+///
+/// **PRIVATE FUNCTION**
+///
+fn sum(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    series(
+        "sum",
+        args,
+        session,
+        Token::Number(Number::zero()),
+        Token::Add,
+    )
+}
+
+/// tan(x), the tangent of x in radians.
+///
+/// **PRIVATE FUNCTION**
+///
+fn tan(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    elementary("tan", args, session, |x, session| {
+        session.approximated |= !x.is_zero();
+
+        Ok(Token::Number(x.tan(session.decimal_places)))
+    })
+}
+
+/// transpose(M), the matrix with its rows and columns swapped.
+///
+/// **PRIVATE FUNCTION**
+///
+fn transpose(args: &[Token], session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    Ok(matrix_arg(&args[0], session)?.transpose().to_token())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_function_table_add_get() {
+        let mut table = FunctionTable::new();
+
+        table.add(FunctionEntry::new("det", 1, 1, det)).unwrap();
+
+        // Adding the same function twice should fail
+        table.add(FunctionEntry::new("det", 1, 1, det)).unwrap_err();
+
+        assert!(table.get("det").is_some());
+        assert!(table.get("foo").is_none());
+    }
+
+    #[test]
+    fn test_function_arg_count() {
+        let mut session = Session::_new_test().unwrap();
+        let det = get_builtin("det").unwrap();
+
+        det.call(&[], &mut session).unwrap_err();
+    }
+}
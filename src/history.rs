@@ -0,0 +1,403 @@
+//! Management of the history files in the data directory, for the history and vars subcommands.
+//!
+//! Every function works on the files of an initialized session, so answers are numbered the same way they are in the TUI.
+//!
+
+// Copyright (c) 2022 Charles M. Thompson
+//
+// This file is part of ApeCrunch.
+//
+// ApeCrunch is free software: you can redistribute it and/or modify it under
+// the terms only of version 3 of the GNU General Public License as published
+// by the Free Software Foundation
+//
+// ApeCrunch is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License
+// for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// ApeCrunch(in a file named COPYING).
+// If not, see <https://www.gnu.org/licenses/>.
+
+use crate::parser::Token;
+use crate::session::HistoryEntry;
+use crate::session::HistoryFile;
+use crate::session::Session;
+use simple_error::*;
+use std::error::Error;
+use std::fs;
+use uuid::Uuid;
+
+/// Units ages can be given in, along with how many seconds each one is.
+///
+/// **NOT PUBLIC.**
+///
+const AGE_UNITS: [(&str, u64); 5] = [
+    ("s", 1),
+    ("m", 60),
+    ("h", 60 * 60),
+    ("d", 24 * 60 * 60),
+    ("w", 7 * 24 * 60 * 60),
+];
+
+/// Lists the entries of every session, or only of the session with the given UUID, under a line describing their session.
+///
+/// Returns an error if there's no session with the given UUID.
+///
+pub fn list(session: &Session, session_uuid: Option<Uuid>) -> Result<Vec<String>, Box<dyn Error>> {
+    let history_files: Vec<HistoryFile> = session
+        .get_history_files()?
+        .into_iter()
+        .filter(|history_file| match session_uuid {
+            Some(uuid) => history_file.bincode.session_uuid == uuid,
+            None => true,
+        })
+        .collect();
+
+    if let Some(uuid) = session_uuid {
+        if history_files.is_empty() {
+            bail!("Session {} not found!", uuid);
+        }
+    }
+
+    let mut lines = Vec::<String>::new();
+
+    for history_file in history_files {
+        lines.push(format!(
+            "Session {} started {}, {} entries",
+            history_file.bincode.session_uuid,
+            format_timestamp(history_file.bincode.session_start),
+            history_file.bincode.entries.len()
+        ));
+
+        for entry in &history_file.bincode.entries {
+            lines.push(render_indexed(session, entry));
+        }
+    }
+
+    Ok(lines)
+}
+
+/// Shows the entry that would be grabbed with @N in full, along with its UUID and the session it's from.
+///
+/// Returns an error if there's no entry with that index.
+///
+pub fn show(session: &Session, inverse_index: usize) -> Result<Vec<String>, Box<dyn Error>> {
+    let entry = match session.get_entry_inv_index(inverse_index) {
+        Some(entry) => entry,
+        None => bail!("Entry @{} not found!", inverse_index),
+    };
+
+    let mut lines = vec![entry.to_string(), format!("Entry:   {}", entry.entry_uuid)];
+
+    let history_file = session
+        .get_history_files()?
+        .into_iter()
+        .find(|history_file| {
+            history_file
+                .bincode
+                .entries
+                .iter()
+                .any(|other| other.entry_uuid == entry.entry_uuid)
+        });
+
+    if let Some(history_file) = history_file {
+        lines.push(format!(
+            "Session: {} started {}",
+            history_file.bincode.session_uuid,
+            format_timestamp(history_file.bincode.session_start)
+        ));
+    }
+
+    Ok(lines)
+}
+
+/// Deletes every history file, including ones that are corrupt or from a newer version.
+///
+/// Returns how many files were deleted, or an i/o error if one couldn't be.
+///
+pub fn clear(session: &Session) -> Result<usize, Box<dyn Error>> {
+    let paths = session.get_history_file_paths()?;
+
+    for path in &paths {
+        fs::remove_file(path)?;
+    }
+
+    Ok(paths.len())
+}
+
+/// Deletes the history files of sessions that started longer ago than the given age, in seconds, before the session did.
+///
+/// Returns how many files were deleted, or an i/o error if one couldn't be.
+///
+pub fn prune(session: &Session, older_than: u64) -> Result<usize, Box<dyn Error>> {
+    let cutoff = session.session_start.saturating_sub(older_than);
+    let mut count = 0;
+
+    for history_file in session.get_history_files()? {
+        if history_file.bincode.session_start < cutoff {
+            fs::remove_file(&history_file.path)?;
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+/// Parses an age like 90s, 30m, 12h, 7d or 2w into seconds.
+///
+/// Returns a simple error if the age is missing its unit or isn't a whole number.
+///
+pub fn parse_age(age: &str) -> Result<u64, Box<dyn Error>> {
+    let age = age.trim();
+
+    for (unit, seconds) in AGE_UNITS {
+        if let Some(count) = age.strip_suffix(unit) {
+            match count.parse::<u64>() {
+                Ok(count) => return Ok(count.saturating_mul(seconds)),
+                Err(_) => bail!("Invalid age {}, expected a whole number!", age),
+            }
+        }
+    }
+
+    bail!(
+        "Invalid age {}, expected a number followed by one of s, m, h, d or w!",
+        age
+    );
+}
+
+/// Formats seconds since unix epoch as a UTC date and time, like 2022-09-30 14:05:00 UTC.
+///
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let seconds = timestamp % 86400;
+
+    // Convert days since the epoch to a civil date, shifting the year to start in March so leap days fall at the end of it
+    let shifted = days + 719468;
+    let era = shifted.div_euclid(146097);
+    let day_of_era = shifted.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+/// Lists every variable as "name = value", or "name := formula" for formulas, in a form that can be typed back in.
+///
+pub fn list_variables(session: &Session) -> Vec<String> {
+    session
+        .vartable
+        .variables
+        .iter()
+        .map(|variable| match &variable.tokens {
+            Token::Formula(..) => variable.tokens.to_source_string(session),
+            value => format!("{} = {}", variable.id, value.to_source_string(session)),
+        })
+        .collect()
+}
+
+/// Gets the value of a variable, or the expression of a formula, in a form that can be typed back in.
+///
+/// Returns a simple error if the variable doesn't exist.
+///
+pub fn get_variable(session: &Session, name: &str) -> Result<String, Box<dyn Error>> {
+    match session.vartable.get(name)?.tokens {
+        Token::Formula(_id, expression) => Ok(expression.to_source_string(session)),
+        value => Ok(value.to_source_string(session)),
+    }
+}
+
+/// Stores the value of an expression in a variable with Session::set_variable(), and saves it to a new history file.
+///
+/// Returns the rendered entry, or a simple error if the name can't be stored to, or the value isn't a single expression or can't be evaluated.
+///
+pub fn set_variable(
+    session: &mut Session,
+    name: &str,
+    value: &str,
+) -> Result<String, Box<dyn Error>> {
+    let entry = session.set_variable(name, value)?;
+
+    session.update_file()?;
+
+    Ok(entry.to_string())
+}
+
+/// Removes a variable from the session and from every history file it's stored in.
+///
+/// Returns how many files were changed, or a simple error if the variable doesn't exist or formulas still use it.
+///
+pub fn remove_variable(session: &mut Session, name: &str) -> Result<usize, Box<dyn Error>> {
+    session.vartable.get(name)?;
+
+    let dependents = session.vartable.dependents(name);
+
+    if !dependents.is_empty() {
+        bail!(
+            "Variable \"{}\" is used by {}, remove them first!",
+            name,
+            dependents.join(", ")
+        );
+    }
+
+    session.vartable.remove(name)?;
+
+    let mut count = 0;
+
+    for mut history_file in session.get_history_files()? {
+        if history_file.bincode.session_vartable.remove(name).is_ok() {
+            history_file.bincode.to_file(&history_file.path)?;
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+/// Renders an entry with its answer index in front of it, lining up any lines after the first.
+///
+/// **PRIVATE FUNCTION**
+///
+fn render_indexed(session: &Session, entry: &HistoryEntry) -> String {
+    let index = match session.get_inv_index_from_uuid(&entry.entry_uuid) {
+        Some(index) => format!("@{}", index),
+        None => "@?".to_string(),
+    };
+
+    let padding = format!("\n  {}  ", " ".repeat(index.len()));
+
+    format!("  {}  {}", index, entry.to_string().replace('\n', &padding))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::*;
+
+    /// Creates a session in the test directory, evaluates a line in it and saves it.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn record_session(line: &str, session_start: u64) -> Session {
+        let mut session = Session::_new_test().unwrap();
+
+        session.init().unwrap();
+        session.session_start = session_start;
+        session.evaluate_line(line).unwrap();
+        session.update_file().unwrap();
+
+        session
+    }
+
+    #[test]
+    #[serial]
+    fn test_history_list_show_prune() {
+        let old = record_session("2 + 2", 1000);
+        let new = record_session("@0 * 3; 5 -> a", 1_000_000);
+
+        let mut session = Session::_new_test().unwrap();
+
+        session.init().unwrap();
+        session.session_start = 1_000_000 + 60;
+
+        assert_eq!(
+            list(&session, None).unwrap(),
+            vec![
+                format!(
+                    "Session {} started 1970-01-01 00:16:40 UTC, 1 entries",
+                    old.session_uuid
+                ),
+                "  @2  2 + 2 = 4".to_string(),
+                format!(
+                    "Session {} started 1970-01-12 13:46:40 UTC, 2 entries",
+                    new.session_uuid
+                ),
+                "  @1  @0 * 3 = 12".to_string(),
+                "  @0  5 -> a = 5".to_string(),
+            ]
+        );
+
+        assert_eq!(list(&session, Some(old.session_uuid)).unwrap().len(), 2);
+        list(&session, Some(session.session_uuid)).unwrap_err();
+
+        let shown = show(&session, 2).unwrap();
+
+        assert_eq!(shown[0], "2 + 2 = 4");
+        assert!(shown[2].contains(&old.session_uuid.to_string()));
+        show(&session, 3).unwrap_err();
+
+        // Only the old session started more than a day before this one
+        assert_eq!(prune(&session, parse_age("1d").unwrap()).unwrap(), 1);
+        assert!(!old.history_file_path.exists());
+        assert!(new.history_file_path.exists());
+
+        assert_eq!(clear(&session).unwrap(), 1);
+        assert!(!new.history_file_path.exists());
+
+        session._test_purge().unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn test_history_vars() {
+        record_session("3 -> a; a * 2 -> b", 1000);
+        record_session("a * 3 -> b; b := a + 1", 2000);
+
+        let mut session = Session::_new_test().unwrap();
+
+        session.init().unwrap();
+
+        assert_eq!(list_variables(&session), vec!["a = 3", "b := a + 1"]);
+        assert_eq!(get_variable(&session, "b").unwrap(), "a + 1");
+        get_variable(&session, "c").unwrap_err();
+
+        // Formulas still using a variable keep it from being removed
+        remove_variable(&mut session, "a").unwrap_err();
+        assert_eq!(remove_variable(&mut session, "b").unwrap(), 2);
+
+        assert_eq!(
+            set_variable(&mut session, "c", "a^2").unwrap(),
+            "( a^2 ) -> c = 9"
+        );
+        set_variable(&mut session, "d; e", "1").unwrap_err();
+        set_variable(&mut session, "d", "1; 2").unwrap_err();
+
+        let mut session = Session::_new_test().unwrap();
+
+        session.init().unwrap();
+
+        assert_eq!(list_variables(&session), vec!["a = 3", "c = 9"]);
+
+        session._test_purge().unwrap();
+    }
+
+    #[test]
+    fn test_history_age_timestamp() {
+        assert_eq!(parse_age("90s").unwrap(), 90);
+        assert_eq!(parse_age("2w").unwrap(), 1_209_600);
+        parse_age("12").unwrap_err();
+        parse_age("1.5d").unwrap_err();
+
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_timestamp(1_664_546_700), "2022-09-30 14:05:00 UTC");
+    }
+}
@@ -0,0 +1,367 @@
+//! Importer for SpeedCrunch session files, so years of SpeedCrunch history aren't left behind.
+//!
+//! SpeedCrunch saves sessions as JSON with the history, user variables and user functions. Expressions are translated to ApeCrunch syntax and
+//! evaluated again rather than trusting SpeedCrunch's results, so the imported history is exact wherever ApeCrunch can be.
+//!
+
+// Copyright (c) 2022 Charles M. Thompson
+//
+// This file is part of ApeCrunch.
+//
+// ApeCrunch is free software: you can redistribute it and/or modify it under
+// the terms only of version 3 of the GNU General Public License as published
+// by the Free Software Foundation
+//
+// ApeCrunch is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License
+// for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// ApeCrunch(in a file named COPYING).
+// If not, see <https://www.gnu.org/licenses/>.
+
+use crate::session::Session;
+use lazy_static::*;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use simple_error::*;
+use std::error::Error;
+use uuid::Uuid;
+
+/// Layout of SpeedCrunch session files, only the parts that are imported.
+///
+/// **NOT PUBLIC.**
+///
+#[derive(Debug, Deserialize)]
+struct SpeedCrunchSession {
+    #[serde(default)]
+    history: Vec<SpeedCrunchEntry>,
+    #[serde(default)]
+    variables: Vec<SpeedCrunchVariable>,
+    #[serde(default)]
+    functions: Vec<SpeedCrunchFunction>,
+}
+
+/// SpeedCrunch history entry, the result is worked out again so it's left out.
+///
+/// **NOT PUBLIC.**
+///
+#[derive(Debug, Deserialize)]
+struct SpeedCrunchEntry {
+    expression: String,
+}
+
+/// SpeedCrunch user variable, the value is a number nested in one or more objects depending on the SpeedCrunch version.
+///
+/// **NOT PUBLIC.**
+///
+#[derive(Debug, Deserialize)]
+struct SpeedCrunchVariable {
+    identifier: String,
+    value: Value,
+}
+
+/// SpeedCrunch user function, which ApeCrunch has no equivalent for.
+///
+/// **NOT PUBLIC.**
+///
+#[derive(Debug, Deserialize)]
+struct SpeedCrunchFunction {
+    identifier: String,
+    #[serde(default)]
+    arguments: Vec<String>,
+    #[serde(default)]
+    expression: String,
+}
+
+/// Something from a SpeedCrunch session that couldn't be imported, and why.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportFailure {
+    /// The expression, variable or function as it was in SpeedCrunch.
+    pub source: String,
+    /// Why it couldn't be imported.
+    pub reason: String,
+}
+
+/// Report of what was imported from a SpeedCrunch session.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// Number of history entries imported.
+    pub entries: usize,
+    /// Number of variables imported.
+    pub variables: usize,
+    /// Everything that couldn't be imported, in the order it was in the file.
+    pub failures: Vec<ImportFailure>,
+}
+
+/// Imports a SpeedCrunch session into a session, variables first so the history can use the ones defined before it was recorded.
+///
+/// The session isn't saved, call Session::update_file() afterwards to write it as a history file.
+///
+/// Returns a report of what was imported, or an error if the file isn't a SpeedCrunch session.
+///
+pub fn import_speedcrunch(
+    session: &mut Session,
+    json: &str,
+) -> Result<ImportReport, Box<dyn Error>> {
+    let speedcrunch_session: SpeedCrunchSession = match serde_json::from_str(json) {
+        Ok(speedcrunch_session) => speedcrunch_session,
+        Err(error) => bail!("Not a SpeedCrunch session file: {}", error),
+    };
+
+    let mut report = ImportReport::default();
+
+    for variable in &speedcrunch_session.variables {
+        match import_variable(session, variable) {
+            Ok(_) => report.variables += 1,
+            Err(error) => report.failures.push(ImportFailure {
+                source: format!("{} = {}", variable.identifier, variable.value),
+                reason: error.to_string(),
+            }),
+        }
+    }
+
+    for function in &speedcrunch_session.functions {
+        report.failures.push(ImportFailure {
+            source: format!(
+                "{}({}) = {}",
+                function.identifier,
+                function.arguments.join("; "),
+                function.expression
+            ),
+            reason:
+                "User functions aren't supported, store the expression as a formula with := instead"
+                    .to_string(),
+        });
+    }
+
+    // Variables get entries too, so ans is the last imported history result rather than the latest entry
+    let mut last_answer = None::<Uuid>;
+
+    for entry in &speedcrunch_session.history {
+        match import_entry(session, &entry.expression, last_answer) {
+            Ok(answer) => {
+                report.entries += 1;
+                last_answer = Some(answer);
+            }
+            Err(error) => report.failures.push(ImportFailure {
+                source: entry.expression.clone(),
+                reason: error.to_string(),
+            }),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Translates a SpeedCrunch expression into ApeCrunch syntax.
+///
+/// Assignments like "a = 5" become stores like "(5) -> a", comparisons like "a == b" become "(a = b)", "ans" becomes the given answer
+/// like "@2", semicolons between function arguments become commas, "**" becomes "^" and numbers in scientific notation become powers of
+/// ten.
+///
+/// Returns a simple error for user function definitions, which have no translation, and for "ans" when there's no answer to use.
+///
+pub fn translate_expression(expression: &str, ans: Option<&str>) -> Result<String, Box<dyn Error>> {
+    lazy_static! {
+        static ref FUNCTION_DEFINITION_RE: Regex = Regex::new(r"^\s*[[:alpha:]_][[:alnum:]_]*\s*\([^)]*\)\s*=[^=]").unwrap(); // Matches "f(x) = ..."
+        static ref ASSIGNMENT_RE: Regex = Regex::new(r"^\s*([[:alpha:]_][[:alnum:]_]*)\s*=([^=].*)$").unwrap(); // Matches "a = ..."
+        static ref ANS_RE: Regex = Regex::new(r"\bans\b").unwrap(); // Matches the previous answer
+        static ref RADIX_RE: Regex = Regex::new(r"(^|[^[:alnum:]_.])0[xXbBoO][[:xdigit:]]").unwrap(); // Matches hexadecimal, binary and octal numbers
+        static ref SCIENTIFIC_RE: Regex = Regex::new(r"(^|[^[:alnum:]_.])(\d+\.?\d*|\.\d+)[eE]([+-]?\d+)\b").unwrap(); // Matches 1.5e-3, but not names like x1e3
+    }
+
+    if FUNCTION_DEFINITION_RE.is_match(expression) {
+        bail!("User functions aren't supported, store the expression as a formula with := instead");
+    }
+
+    // These would otherwise be read as zero times a name
+    if RADIX_RE.is_match(expression) {
+        bail!("Hexadecimal, binary and octal numbers aren't supported");
+    }
+
+    let (expression, store_to) = match ASSIGNMENT_RE.captures(expression) {
+        Some(captures) => (captures[2].to_string(), Some(captures[1].to_string())),
+        None => (expression.to_string(), None),
+    };
+
+    let expression = expression.replace("**", "^").replace(';', ",");
+    let expression = match (ANS_RE.is_match(&expression), ans) {
+        (true, Some(ans)) => ANS_RE.replace_all(&expression, ans).to_string(),
+        (true, None) => bail!("There's no previous result for ans to refer to"),
+        (false, _) => expression,
+    };
+    let expression = SCIENTIFIC_RE.replace_all(&expression, "$1($2 * 10^($3))");
+    let expression = expression.trim();
+
+    // A statement like a = b stores to a, so comparisons get parenthesis to stay comparisons
+    let expression = match expression.contains("==") {
+        true => format!("({})", expression.replace("==", "=")),
+        false => expression.to_string(),
+    };
+
+    Ok(match store_to {
+        Some(id) => format!("({}) -> {}", expression, id),
+        None => expression,
+    })
+}
+
+/// Translates, evaluates and adds a SpeedCrunch history entry to the session, with "ans" referring to the entry with the given UUID.
+///
+/// Returns the UUID of the entry holding the result, for the next entry's "ans".
+///
+/// **PRIVATE FUNCTION**
+///
+fn import_entry(
+    session: &mut Session,
+    expression: &str,
+    last_answer: Option<Uuid>,
+) -> Result<Uuid, Box<dyn Error>> {
+    let ans = last_answer
+        .and_then(|uuid| session.get_inv_index_from_uuid(&uuid))
+        .map(|index| format!("@{}", index));
+    let translated = translate_expression(expression, ans.as_deref())?;
+    let entries = session.evaluate_line(&translated)?;
+
+    Ok(entries.last().unwrap().entry_uuid) // Lines that evaluate have at least one statement
+}
+
+/// Evaluates the value of a SpeedCrunch variable and stores it with Session::set_variable(), which adds an entry for it.
+///
+/// **PRIVATE FUNCTION**
+///
+fn import_variable(
+    session: &mut Session,
+    variable: &SpeedCrunchVariable,
+) -> Result<(), Box<dyn Error>> {
+    let value = match find_number(&variable.value) {
+        Some(value) => translate_expression(value, None)?,
+        None => bail!("No value found for variable {}!", variable.identifier),
+    };
+
+    session.set_variable(&variable.identifier, &value)?;
+
+    Ok(())
+}
+
+/// Finds the number in the value of a SpeedCrunch variable, either the value itself or a "value" or "numeric_value" field nested inside it.
+///
+/// **PRIVATE FUNCTION**
+///
+fn find_number(value: &Value) -> Option<&str> {
+    match value {
+        Value::String(number) => Some(number),
+        Value::Object(fields) => ["value", "numeric_value"]
+            .iter()
+            .find_map(|key| fields.get(*key).and_then(find_number)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_translate() {
+        assert_eq!(
+            translate_expression("a = 2 ** 3", None).unwrap(),
+            "(2 ^ 3) -> a"
+        );
+        assert_eq!(
+            translate_expression("ans * 2", Some("@3")).unwrap(),
+            "@3 * 2"
+        );
+        assert_eq!(
+            translate_expression("max(1; 2)", None).unwrap(),
+            "max(1, 2)"
+        );
+        assert_eq!(
+            translate_expression("1.5e3 + x1e3 - 2E-2", None).unwrap(),
+            "(1.5 * 10^(3)) + x1e3 - (2 * 10^(-2))"
+        );
+        assert_eq!(translate_expression("a == b", None).unwrap(), "(a = b)");
+        translate_expression("ans * 2", None).unwrap_err();
+        translate_expression("f(x) = x^2", None).unwrap_err();
+        translate_expression("0xFF + 1", None).unwrap_err();
+    }
+
+    #[test]
+    fn test_import_speedcrunch() {
+        let mut session = Session::_new_test().unwrap();
+
+        let json = r#"{
+            "history": [
+                {"expression": "ans + 1"},
+                {"expression": "r = 2", "result": {"numeric_value": {"value": "2"}}},
+                {"expression": "pi * r ** 2 + offset"},
+                {"expression": "ans / 2"},
+                {"expression": "0x1F + 1"},
+                {"expression": "sqrt(16)"},
+                {"expression": "ans == 4"}
+            ],
+            "variables": [
+                {"identifier": "offset", "value": {"numeric_value": {"value": "2.5e-1"}}},
+                {"identifier": "half", "value": "0.5"},
+                {"identifier": "broken", "value": {}}
+            ],
+            "functions": [
+                {"identifier": "f", "arguments": ["x"], "expression": "x^2"}
+            ]
+        }"#;
+
+        let report = import_speedcrunch(&mut session, json).unwrap();
+
+        assert_eq!(report.entries, 5);
+        assert_eq!(report.variables, 2);
+        assert_eq!(
+            report
+                .failures
+                .iter()
+                .map(|failure| failure.source.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["broken = {}", "f(x) = x^2", "ans + 1", "0x1F + 1"]
+        );
+
+        let entries = session.get_entries();
+
+        // Variables are stored with entries of their own, before the history
+        assert_eq!(entries[1].to_string(), "( 0.5 ) -> half = 0.5");
+        assert_eq!(entries[2].to_string(), "( 2 ) -> r = 2");
+        assert_eq!(entries[4].render_without_equality(&session), "@3 / 2");
+        assert_eq!(
+            entries[4].only_equality().to_string(&session),
+            "6.408185..."
+        );
+        assert_eq!(entries[5].to_string(), "sqrt(16) = 4");
+
+        // ans skips over failed entries, and == compares instead of storing
+        assert_eq!(entries[6].render_without_equality(&session), "( @1 = 4 )");
+        assert_eq!(entries[6].only_equality().to_string(&session), "true");
+
+        assert_eq!(
+            session
+                .vartable
+                .get("offset")
+                .unwrap()
+                .tokens
+                .to_source_string(&session),
+            "0.25"
+        );
+        assert_eq!(
+            session
+                .vartable
+                .get("r")
+                .unwrap()
+                .tokens
+                .to_source_string(&session),
+            "2"
+        );
+
+        import_speedcrunch(&mut session, "[1, 2]").unwrap_err();
+    }
+}
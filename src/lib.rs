@@ -18,13 +18,24 @@
 // ApeCrunch(in a file named COPYING).
 // If not, see <https://www.gnu.org/licenses/>.
 
+pub mod algebra;
+pub mod calculus;
+pub mod complex;
+pub mod export;
+pub mod functions;
+pub mod history;
+pub mod import;
+pub mod matrix;
 pub mod number;
 pub mod op_engine;
 pub mod parser;
+pub mod polynomial;
+pub mod render;
+pub mod repl;
+pub mod server;
 pub mod session;
 pub mod tui;
 pub mod variable;
-//pub mod functions;
 
 /// Version of apecrunch, derived from the Cargo.toml version.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -18,23 +18,168 @@
 // ApeCrunch(in a file named COPYING).
 // If not, see <https://www.gnu.org/licenses/>.
 
+use apecrunch::export;
+use apecrunch::export::ExportFormat;
+use apecrunch::history;
+use apecrunch::import;
+use apecrunch::parser;
+use apecrunch::repl::Repl;
+use apecrunch::server::Server;
 use apecrunch::session::Session;
 use apecrunch::tui::*;
+use clap::ArgEnum;
 use clap::Parser;
+use clap::Subcommand;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::io::BufRead;
 use std::io::Write;
+use std::path::PathBuf;
+use std::process;
 use termcolor::Color;
 use termcolor::ColorChoice;
 use termcolor::ColorSpec;
 use termcolor::StandardStream;
 use termcolor::WriteColor;
+use uuid::Uuid;
 
 // Command line arguments parsed through clap
 #[derive(Parser, Debug)]
-#[clap(author, version, about)]
+#[clap(author, version, about, args_conflicts_with_subcommands = true)]
 struct Args {
     /// Print paths to the config and session/history files
     #[clap(short, long)]
     print_file_paths: bool,
+
+    /// Evaluate an expression, print the result and exit instead of starting the TUI
+    #[clap(short, long, value_name = "EXPRESSION", conflicts_with = "expression")]
+    evaluate: Option<String>,
+
+    /// Same as --evaluate
+    #[clap(value_name = "EXPRESSION")]
+    expression: Option<String>,
+
+    /// Evaluate every line read from stdin in one session, print the results and exit instead of starting the TUI
+    #[clap(short, long, conflicts_with_all = &["evaluate", "expression"])]
+    batch: bool,
+
+    /// Only print the results in batch mode, without the expressions
+    #[clap(short, long, requires = "batch")]
+    results_only: bool,
+
+    /// Record the batch in the history
+    #[clap(long, requires = "batch")]
+    record: bool,
+
+    /// Answer JSON-RPC requests on stdin, one per line, instead of starting the TUI
+    #[clap(long, conflicts_with_all = &["evaluate", "expression", "batch", "repl"])]
+    server: bool,
+
+    /// Format to print results in when evaluating from the command line or in batch mode
+    #[clap(long, arg_enum, value_name = "FORMAT", default_value = "text")]
+    format: OutputFormat,
+
+    /// Start a plain line by line prompt instead of the TUI, for terminals the TUI doesn't work on and screen readers
+    #[clap(long, conflicts_with_all = &["evaluate", "expression", "batch"])]
+    repl: bool,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+/// Subcommands for managing the history files instead of starting the TUI.
+///
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Manage the calculations saved in the history files
+    #[clap(subcommand)]
+    History(HistoryCommand),
+    /// Manage the variables saved in the history files
+    #[clap(subcommand)]
+    Vars(VarsCommand),
+    /// Import sessions from other calculators into a new history file
+    #[clap(subcommand)]
+    Import(ImportCommand),
+}
+
+/// Subcommands of the history subcommand.
+///
+#[derive(Subcommand, Debug)]
+enum HistoryCommand {
+    /// List the entries of every session, along with the index to grab their answer with
+    List {
+        /// Only list the entries of the session with this UUID
+        #[clap(long, value_name = "UUID")]
+        session: Option<Uuid>,
+    },
+    /// Show the entry grabbed with @N, along with its UUID and session
+    Show {
+        /// Index of the entry, counting back from the latest one
+        #[clap(value_name = "N")]
+        index: usize,
+    },
+    /// Print every entry, oldest first, along with its session when exporting to a table
+    Export {
+        /// Format to print the entries in
+        #[clap(long, arg_enum, value_name = "FORMAT", default_value = "text")]
+        format: ExportFormat,
+    },
+    /// Delete every history file
+    Clear,
+    /// Delete the history files of sessions older than a given age
+    Prune {
+        /// Age like 90s, 30m, 12h, 7d or 2w
+        #[clap(long, value_name = "AGE")]
+        older_than: String,
+    },
+}
+
+/// Subcommands of the vars subcommand.
+///
+#[derive(Subcommand, Debug)]
+enum VarsCommand {
+    /// List every variable
+    List,
+    /// Print the value of a variable
+    Get {
+        /// Name of the variable
+        name: String,
+    },
+    /// Store the value of an expression in a variable
+    Set {
+        /// Name of the variable
+        name: String,
+        /// Expression to evaluate
+        value: String,
+    },
+    /// Remove a variable from every history file
+    Rm {
+        /// Name of the variable
+        name: String,
+    },
+}
+
+/// Subcommands of the import subcommand.
+///
+#[derive(Subcommand, Debug)]
+enum ImportCommand {
+    /// Import the history and variables of a SpeedCrunch session file, evaluating every expression again
+    Speedcrunch {
+        /// SpeedCrunch session file, saved as JSON
+        #[clap(value_name = "FILE")]
+        file: PathBuf,
+    },
+}
+
+/// Formats results can be printed in.
+///
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    /// Results as they're shown in the TUI
+    Text,
+    /// One JSON object per statement, with the exact fraction and any error
+    Json,
 }
 
 /// Placeholder main function.
@@ -51,6 +196,102 @@ fn main() {
 
     green.set_fg(Some(Color::Green));
 
+    if let Some(command) = args.command {
+        match run_command(&mut session, command) {
+            Ok(lines) => {
+                for line in lines {
+                    println!("{}", line);
+                }
+            }
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                process::exit(1);
+            }
+        }
+
+        return;
+    }
+
+    if let Some(expression) = args.evaluate.or(args.expression) {
+        if args.format == OutputFormat::Json {
+            let (results, succeeded) = evaluate_json(&mut session, &expression);
+
+            for result in results {
+                println!("{}", result);
+            }
+
+            if !succeeded {
+                process::exit(1);
+            }
+
+            return;
+        }
+
+        match evaluate(&mut session, &expression, true) {
+            Ok(results) => {
+                for result in results {
+                    println!("{}", result);
+                }
+            }
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                process::exit(1);
+            }
+        }
+
+        return;
+    }
+
+    if args.batch {
+        let stdin = io::stdin();
+
+        let succeeded = match batch(
+            &mut session,
+            stdin.lock(),
+            &mut io::stdout(),
+            &mut io::stderr(),
+            args.format,
+            args.results_only,
+        ) {
+            Ok(succeeded) => succeeded,
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                process::exit(1);
+            }
+        };
+
+        if args.record {
+            if let Err(error) = session.update_file() {
+                eprintln!("Error: {}", error);
+                process::exit(1);
+            }
+        }
+
+        if !succeeded {
+            process::exit(1);
+        }
+
+        return;
+    }
+
+    if args.server {
+        let stdin = io::stdin();
+
+        Server::new(session)
+            .serve(stdin.lock(), &mut io::stdout())
+            .unwrap();
+
+        return;
+    }
+
+    if args.repl {
+        let mut repl = Repl::new(session).unwrap();
+
+        repl.run().unwrap();
+
+        return;
+    }
+
     if args.print_file_paths {
         stdout.set_color(&green).unwrap();
         write!(&mut stdout, "\n\n\tConfig Directory:\t").unwrap();
@@ -72,6 +313,162 @@ fn main() {
     tui.run();
 }
 
+/// Runs a history, vars or import subcommand on the history files of an initialized session.
+///
+/// Returns the lines to print, or the error the subcommand failed with.
+///
+fn run_command(session: &mut Session, command: Command) -> Result<Vec<String>, Box<dyn Error>> {
+    match command {
+        Command::History(HistoryCommand::List { session: uuid }) => history::list(session, uuid),
+        Command::History(HistoryCommand::Show { index }) => history::show(session, index),
+        Command::History(HistoryCommand::Export { format }) => {
+            Ok(vec![export::export(session, format)?
+                .trim_end()
+                .to_string()])
+        }
+        Command::History(HistoryCommand::Clear) => {
+            let count = history::clear(session)?;
+
+            Ok(vec![format!("Deleted {} history files", count)])
+        }
+        Command::History(HistoryCommand::Prune { older_than }) => {
+            let count = history::prune(session, history::parse_age(&older_than)?)?;
+
+            Ok(vec![format!("Deleted {} history files", count)])
+        }
+        Command::Vars(VarsCommand::List) => Ok(history::list_variables(session)),
+        Command::Vars(VarsCommand::Get { name }) => {
+            Ok(vec![history::get_variable(session, &name)?])
+        }
+        Command::Vars(VarsCommand::Set { name, value }) => {
+            Ok(vec![history::set_variable(session, &name, &value)?])
+        }
+        Command::Vars(VarsCommand::Rm { name }) => {
+            let count = history::remove_variable(session, &name)?;
+
+            Ok(vec![format!(
+                "Removed {} from {} history files",
+                name, count
+            )])
+        }
+        Command::Import(ImportCommand::Speedcrunch { file }) => {
+            let report = import::import_speedcrunch(session, &fs::read_to_string(&file)?)?;
+
+            // Nothing is written if nothing was imported
+            if report.entries + report.variables > 0 {
+                session.update_file()?;
+            }
+
+            let mut lines = vec![format!(
+                "Imported {} entries and {} variables from {}",
+                report.entries,
+                report.variables,
+                file.display()
+            )];
+
+            for failure in report.failures {
+                lines.push(format!(
+                    "Couldn't import {}: {}",
+                    failure.source, failure.reason
+                ));
+            }
+
+            Ok(lines)
+        }
+    }
+}
+
+/// Evaluates a line of statements without writing them to the history file, returning each one rendered as "expression = result" or just
+/// the result.
+///
+/// Returns a simple error if any of the statements can't be parsed or evaluated.
+///
+fn evaluate(
+    session: &mut Session,
+    line: &str,
+    results_only: bool,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let entries = session.evaluate_line(line)?;
+
+    Ok(entries
+        .iter()
+        .map(|entry| match results_only {
+            true => entry.only_equality().to_multiline_string(session),
+            false => entry.to_string(),
+        })
+        .collect())
+}
+
+/// Evaluates a line of statements without writing them to the history file, returning each statement as a JSON object.
+///
+/// Errors are written as part of the object of the statement that failed, and the statements after it aren't evaluated.
+///
+/// Returns the objects and whether every statement was evaluated.
+///
+fn evaluate_json(session: &mut Session, line: &str) -> (Vec<String>, bool) {
+    let results = session.evaluate_line_results(line);
+    let succeeded = results.iter().all(|result| result.error.is_none());
+
+    let objects = results
+        .iter()
+        .map(|result| serde_json::to_string(result).unwrap()) // Results only hold strings, so they always serialize
+        .collect();
+
+    (objects, succeeded)
+}
+
+/// Evaluates every line of the input in the same session, so later lines can use the variables and answers of earlier ones.
+///
+/// Errors are written next to the line number they happened on and evaluation carries on with the next line. Blank lines and comments are
+/// skipped. In JSON format errors are written to the output as part of the objects instead, and results_only is ignored.
+///
+/// Returns whether every line was evaluated, or an i/o error if the input can't be read or the output can't be written.
+///
+fn batch(
+    session: &mut Session,
+    input: impl BufRead,
+    output: &mut impl Write,
+    errors: &mut impl Write,
+    format: OutputFormat,
+    results_only: bool,
+) -> Result<bool, Box<dyn Error>> {
+    let mut succeeded = true;
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+
+        if parser::split_statements(&line).is_ok_and(|statements| statements.is_empty()) {
+            continue;
+        }
+
+        if format == OutputFormat::Json {
+            let (results, line_succeeded) = evaluate_json(session, &line);
+
+            for result in results {
+                writeln!(output, "{}", result)?;
+            }
+
+            succeeded &= line_succeeded;
+
+            continue;
+        }
+
+        match evaluate(session, &line, results_only) {
+            Ok(results) => {
+                for result in results {
+                    writeln!(output, "{}", result)?;
+                }
+            }
+            Err(error) => {
+                writeln!(errors, "Error on line {}: {}", i + 1, error)?;
+                succeeded = false;
+            }
+        }
+    }
+
+    Ok(succeeded)
+}
+
 // Test general user things
 #[cfg(test)]
 mod tests {
@@ -109,6 +506,109 @@ mod tests {
         assert_eq!(result.to_string(&session), expected_result);
     }
 
+    #[test]
+    fn test_evaluate() {
+        let mut session = Session::_new_test().unwrap();
+
+        assert_eq!(
+            super::evaluate(&mut session, "3 -> a; a^2", true).unwrap(),
+            vec!["3", "9"]
+        );
+
+        assert_eq!(
+            super::evaluate(&mut session, "a + 1", false).unwrap(),
+            vec!["a + 1 = 4"]
+        );
+
+        super::evaluate(&mut session, "2 +", true).unwrap_err();
+        super::evaluate(&mut session, "[1, 2] * [3, 4]", true).unwrap_err();
+    }
+
+    #[test]
+    fn test_batch() {
+        let mut session = Session::_new_test().unwrap();
+        let input = "3 -> a\n\n# Comment\na * 2\n2 +\n@0 + a\n";
+        let mut output = Vec::<u8>::new();
+        let mut errors = Vec::<u8>::new();
+
+        let succeeded = super::batch(
+            &mut session,
+            input.as_bytes(),
+            &mut output,
+            &mut errors,
+            super::OutputFormat::Text,
+            false,
+        )
+        .unwrap();
+
+        assert!(!succeeded);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "3 -> a = 3\na * 2 = 6\n@0 + a = 9\n"
+        );
+        assert_eq!(
+            String::from_utf8(errors).unwrap(),
+            "Error on line 5: Incomplete Expression: 2+\n"
+        );
+    }
+
+    #[test]
+    fn test_evaluate_json() {
+        let mut session = Session::_new_test().unwrap();
+
+        let (results, succeeded) = super::evaluate_json(&mut session, "1/3; 2 +; 5");
+
+        assert!(!succeeded);
+        assert_eq!(
+            results,
+            vec![
+                concat!(
+                    r#"{"input":"1/3","canonical":"1/3","fraction":{"numerator":"1","denominator":"3"},"#,
+                    r#""decimal":"0.333333...","exact":true,"complete":false,"error":null}"#
+                ),
+                concat!(
+                    r#"{"input":"2 +","canonical":null,"fraction":null,"decimal":null,"exact":null,"complete":null,"#,
+                    r#""error":{"code":"parse_error","message":"Incomplete Expression: 2+"}}"#
+                ),
+            ]
+        );
+
+        let (results, succeeded) = super::evaluate_json(&mut session, "[1, 2] * 2.5");
+
+        assert!(succeeded);
+        assert_eq!(
+            results,
+            vec![concat!(
+                r#"{"input":"[1, 2] * 2.5","canonical":"[[2.5, 5]]","fraction":null,"#,
+                r#""decimal":"[[2.5, 5]]","exact":true,"complete":true,"error":null}"#
+            )]
+        );
+
+        // Roots that come out even are exact, others are approximations even once they're stored
+        let (results, succeeded) =
+            super::evaluate_json(&mut session, "sqrt(-4) / 8; sqrt(2) -> r; r * 0");
+
+        assert!(succeeded);
+        assert_eq!(
+            results,
+            vec![
+                concat!(
+                    r#"{"input":"sqrt(-4) / 8","canonical":"0.25 * sqrt(-1)","fraction":null,"#,
+                    r#""decimal":"0.25i","exact":true,"complete":true,"error":null}"#
+                ),
+                concat!(
+                    r#"{"input":"sqrt(2) -> r","canonical":"1.4142135","#,
+                    r#""fraction":{"numerator":"2828427","denominator":"2000000"},"#,
+                    r#""decimal":"1.414213...","exact":false,"complete":false,"error":null}"#
+                ),
+                concat!(
+                    r#"{"input":"r * 0","canonical":"0","fraction":{"numerator":"0","denominator":"1"},"#,
+                    r#""decimal":"0","exact":false,"complete":true,"error":null}"#
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn test_roots() {
         let user_string1 = "16^0.5";
@@ -0,0 +1,881 @@
+//! Built-in matrix type and exact matrix arithmetic.
+//!
+
+// Copyright (c) 2022 Charles M. Thompson
+//
+// This file is part of ApeCrunch.
+//
+// ApeCrunch is free software: you can redistribute it and/or modify it under
+// the terms only of version 3 of the GNU General Public License as published
+// by the Free Software Foundation
+//
+// ApeCrunch is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License
+// for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// ApeCrunch(in a file named COPYING).
+// If not, see <https://www.gnu.org/licenses/>.
+
+use crate::complex::Complex;
+use crate::number::Number;
+use crate::parser::Token;
+use simple_error::*;
+use std::error::Error;
+
+/// Largest number of entries a matrix can have, so a typo like identity(100000000) is an error instead of running out of memory.
+///
+/// **NOT PUBLIC.**
+///
+const MAX_ENTRIES: usize = 1 << 16;
+
+/// Largest power a matrix can be raised to, since the entries of the result grow with the power.
+///
+/// **NOT PUBLIC.**
+///
+const MAX_POWER: u64 = 1 << 12;
+
+/// Matrix of numbers, stored row by row.
+///
+/// All of the arithmetic is done on the exact fractions inside of Number, so nothing here ever rounds.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    /// Number of rows.
+    pub rows: usize,
+    /// Number of columns.
+    pub cols: usize,
+    /// Entries of the matrix, row-major.
+    entries: Vec<Number>,
+}
+
+impl Matrix {
+    /// Creates a matrix from row-major entries.
+    ///
+    /// Returns a simple error if the number of entries doesn't match the dimensions.
+    ///
+    pub fn new(rows: usize, cols: usize, entries: Vec<Number>) -> Result<Self, Box<dyn Error>> {
+        if rows == 0 || cols == 0 {
+            bail!("Matrices must have at least one row and one column!");
+        }
+
+        Self::check_size(rows, cols)?;
+
+        if entries.len() != rows * cols {
+            bail!(
+                "{} entries cannot fill a {}x{} matrix!",
+                entries.len(),
+                rows,
+                cols
+            );
+        }
+
+        Ok(Self {
+            rows,
+            cols,
+            entries,
+        })
+    }
+
+    /// Creates an n by n identity matrix.
+    ///
+    pub fn identity(n: usize) -> Result<Self, Box<dyn Error>> {
+        Self::check_size(n, n)?;
+
+        let mut entries = Vec::<Number>::with_capacity(n * n);
+
+        for row in 0..n {
+            for col in 0..n {
+                entries.push(match row == col {
+                    true => Number::one(),
+                    false => Number::zero(),
+                });
+            }
+        }
+
+        Self::new(n, n, entries)
+    }
+
+    /// Converts a simplified matrix token into a matrix.
+    ///
+    /// Returns a simple error if the token isn't a matrix or if any of the entries aren't numbers.
+    ///
+    pub fn from_token(token: &Token) -> Result<Self, Box<dyn Error>> {
+        let rows = match token {
+            Token::Matrix(rows) => rows,
+            _ => bail!("Expected a matrix!"),
+        };
+
+        let cols = rows.first().map(|row| row.len()).unwrap_or(0);
+        let mut entries = Vec::<Number>::with_capacity(rows.len() * cols);
+
+        for row in rows {
+            if row.len() != cols {
+                bail!("Every row of a matrix must be the same length!");
+            }
+
+            for entry in row {
+                match entry {
+                    Token::Number(number) => entries.push(number.clone()),
+                    _ => bail!("Matrix entries must be numbers!"),
+                }
+            }
+        }
+
+        Self::new(rows.len(), cols, entries)
+    }
+
+    /// Converts the matrix back into a matrix token.
+    ///
+    pub fn to_token(&self) -> Token {
+        Token::Matrix(
+            self.entries
+                .chunks(self.cols)
+                .map(|row| row.iter().cloned().map(Token::Number).collect())
+                .collect(),
+        )
+    }
+
+    /// Gets the entry at the given row and column.
+    ///
+    pub fn get(&self, row: usize, col: usize) -> &Number {
+        &self.entries[row * self.cols + col]
+    }
+
+    /// Returns true if the matrix has as many rows as it does columns.
+    ///
+    pub fn is_square(&self) -> bool {
+        self.rows == self.cols
+    }
+
+    /// Adds this matrix to another matrix of the same size.
+    ///
+    pub fn add(&self, other: &Matrix) -> Result<Matrix, Box<dyn Error>> {
+        self.check_same_size(other, "add")?;
+
+        let entries = self
+            .entries
+            .iter()
+            .zip(&other.entries)
+            .map(|(left, right)| left.add(right))
+            .collect();
+
+        Self::new(self.rows, self.cols, entries)
+    }
+
+    /// Subtracts a matrix of the same size from this matrix.
+    ///
+    pub fn subtract(&self, other: &Matrix) -> Result<Matrix, Box<dyn Error>> {
+        self.check_same_size(other, "subtract")?;
+
+        let entries = self
+            .entries
+            .iter()
+            .zip(&other.entries)
+            .map(|(left, right)| left.subtract(right))
+            .collect();
+
+        Self::new(self.rows, self.cols, entries)
+    }
+
+    /// Multiplies every entry of the matrix by a number.
+    ///
+    pub fn scale(&self, scalar: &Number) -> Matrix {
+        Self {
+            rows: self.rows,
+            cols: self.cols,
+            entries: self
+                .entries
+                .iter()
+                .map(|entry| entry.multiply(scalar))
+                .collect(),
+        }
+    }
+
+    /// Multiplies this matrix by another matrix.
+    ///
+    /// Returns a simple error if the columns of this matrix don't match the rows of the other.
+    ///
+    pub fn multiply(&self, other: &Matrix) -> Result<Matrix, Box<dyn Error>> {
+        if self.cols != other.rows {
+            bail!(
+                "Cannot multiply a {}x{} matrix by a {}x{} matrix!",
+                self.rows,
+                self.cols,
+                other.rows,
+                other.cols
+            );
+        }
+
+        let mut entries = Vec::<Number>::with_capacity(self.rows * other.cols);
+
+        for row in 0..self.rows {
+            for col in 0..other.cols {
+                let mut sum = Number::zero();
+
+                for i in 0..self.cols {
+                    sum = sum.add(&self.get(row, i).multiply(other.get(i, col)));
+                }
+
+                entries.push(sum);
+            }
+        }
+
+        Self::new(self.rows, other.cols, entries)
+    }
+
+    /// Swaps the rows and columns of the matrix.
+    ///
+    pub fn transpose(&self) -> Matrix {
+        let mut entries = Vec::<Number>::with_capacity(self.entries.len());
+
+        for col in 0..self.cols {
+            for row in 0..self.rows {
+                entries.push(self.get(row, col).clone());
+            }
+        }
+
+        Self {
+            rows: self.cols,
+            cols: self.rows,
+            entries,
+        }
+    }
+
+    /// Calculates the determinant of a square matrix through gaussian elimination.
+    ///
+    pub fn determinant(&self) -> Result<Number, Box<dyn Error>> {
+        self.check_square("determinant")?;
+
+        let mut work = self.clone();
+        let mut determinant = Number::one();
+
+        for pivot in 0..work.rows {
+            // Find a row with a non-zero entry in the pivot column, the determinant is zero if there isn't one
+            let pivot_row = match (pivot..work.rows).find(|row| !work.get(*row, pivot).is_zero()) {
+                Some(row) => row,
+                None => return Ok(Number::zero()),
+            };
+
+            // Swapping rows flips the sign of the determinant
+            if pivot_row != pivot {
+                work.swap_rows(pivot, pivot_row);
+                determinant = determinant.negative();
+            }
+
+            let pivot_value = work.get(pivot, pivot).clone();
+
+            determinant = determinant.multiply(&pivot_value);
+
+            // Eliminate everything below the pivot
+            for row in pivot + 1..work.rows {
+                let factor = work.get(row, pivot).divide(&pivot_value);
+
+                work.subtract_row_multiple(row, pivot, &factor);
+            }
+        }
+
+        Ok(determinant)
+    }
+
+    /// Calculates the inverse of a square matrix through gauss-jordan elimination.
+    ///
+    /// Returns a simple error if the matrix is singular.
+    ///
+    pub fn inverse(&self) -> Result<Matrix, Box<dyn Error>> {
+        self.check_square("inverse")?;
+
+        let mut work = self.clone();
+        let mut inverse = Self::identity(self.rows)?;
+
+        for pivot in 0..work.rows {
+            let pivot_row = match (pivot..work.rows).find(|row| !work.get(*row, pivot).is_zero()) {
+                Some(row) => row,
+                None => bail!("Matrix is singular and has no inverse!"),
+            };
+
+            work.swap_rows(pivot, pivot_row);
+            inverse.swap_rows(pivot, pivot_row);
+
+            // Scale the pivot row so the pivot becomes one...
+            let pivot_value = work.get(pivot, pivot).clone();
+
+            work.divide_row(pivot, &pivot_value);
+            inverse.divide_row(pivot, &pivot_value);
+
+            // And clear out the pivot column in every other row
+            for row in 0..work.rows {
+                if row == pivot {
+                    continue;
+                }
+
+                let factor = work.get(row, pivot).clone();
+
+                if factor.is_zero() {
+                    continue;
+                }
+
+                work.subtract_row_multiple(row, pivot, &factor);
+                inverse.subtract_row_multiple(row, pivot, &factor);
+            }
+        }
+
+        Ok(inverse)
+    }
+
+    /// Solves the linear system Ax = b through gaussian elimination, where this matrix is A.
+    ///
+    /// b can be given as either a row or a column vector, and x is returned the same way. Everything is exact, nothing is rounded.
+    ///
+    /// Returns a simple error if the system is inconsistent or doesn't have a single unique solution.
+    ///
+    pub fn solve(&self, b: &Matrix) -> Result<Matrix, Box<dyn Error>> {
+        let is_row = b.rows == 1 && b.cols != 1;
+        let b = match is_row {
+            true => b.transpose(),
+            false => b.clone(),
+        };
+
+        if b.cols != 1 || b.rows != self.rows {
+            bail!(
+                "A system with {} equations needs a vector of {} constants!",
+                self.rows,
+                self.rows
+            );
+        }
+
+        // Build the augmented matrix [A | b]
+        let unknowns = self.cols;
+        let mut entries = Vec::<Number>::with_capacity(self.rows * (unknowns + 1));
+
+        for row in 0..self.rows {
+            for col in 0..unknowns {
+                entries.push(self.get(row, col).clone());
+            }
+
+            entries.push(b.get(row, 0).clone());
+        }
+
+        let mut work = Self::new(self.rows, unknowns + 1, entries)?;
+        let mut rank = 0;
+
+        // Reduce the augmented matrix to reduced row echelon form
+        for col in 0..unknowns {
+            let pivot_row = match (rank..work.rows).find(|row| !work.get(*row, col).is_zero()) {
+                Some(row) => row,
+                None => continue,
+            };
+
+            work.swap_rows(rank, pivot_row);
+
+            let pivot_value = work.get(rank, col).clone();
+
+            work.divide_row(rank, &pivot_value);
+
+            for row in 0..work.rows {
+                if row == rank {
+                    continue;
+                }
+
+                let factor = work.get(row, col).clone();
+
+                if !factor.is_zero() {
+                    work.subtract_row_multiple(row, rank, &factor);
+                }
+            }
+
+            rank += 1;
+        }
+
+        // Any leftover row reading 0 = c, where c isn't zero, can never be satisfied
+        if (rank..work.rows).any(|row| !work.get(row, unknowns).is_zero()) {
+            bail!("System is inconsistent and has no solution!");
+        }
+
+        if rank < unknowns {
+            bail!("System is singular and has infinitely many solutions!");
+        }
+
+        // With full rank the first rows of the last column hold the solution, in order
+        let solution: Vec<Number> = (0..unknowns)
+            .map(|row| work.get(row, unknowns).clone())
+            .collect();
+
+        match is_row {
+            true => Self::new(1, unknowns, solution),
+            false => Self::new(unknowns, 1, solution),
+        }
+    }
+
+    /// Raises a square matrix to an integer power, using the inverse for negative powers.
+    ///
+    pub fn power(&self, exponent: i64) -> Result<Matrix, Box<dyn Error>> {
+        self.check_square("power")?;
+
+        // Check the power before working out an inverse that would go to waste
+        if exponent.unsigned_abs() > MAX_POWER {
+            bail!("Matrices can only be raised to powers up to {}!", MAX_POWER);
+        }
+
+        let base = match exponent < 0 {
+            true => self.inverse()?,
+            false => self.clone(),
+        };
+
+        // Exponentiation by squaring, going through the bits of the power from the lowest
+        let mut result = Self::identity(self.rows)?;
+        let mut square = base;
+        let mut bits = exponent.unsigned_abs();
+
+        while bits > 0 {
+            if bits & 1 == 1 {
+                result = result.multiply(&square)?;
+            }
+
+            bits >>= 1;
+
+            if bits > 0 {
+                square = square.multiply(&square)?;
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Checks that a matrix with the given dimensions isn't too big to create.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn check_size(rows: usize, cols: usize) -> Result<(), Box<dyn Error>> {
+        match rows.checked_mul(cols) {
+            Some(size) if size <= MAX_ENTRIES => Ok(()),
+            _ => bail!(
+                "A {}x{} matrix is too big, matrices can have at most {} entries!",
+                rows,
+                cols,
+                MAX_ENTRIES
+            ),
+        }
+    }
+
+    /// Swaps two rows in place.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        if a == b {
+            return;
+        }
+
+        for col in 0..self.cols {
+            self.entries.swap(a * self.cols + col, b * self.cols + col);
+        }
+    }
+
+    /// Divides every entry in a row by a number, in place.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn divide_row(&mut self, row: usize, divisor: &Number) {
+        for col in 0..self.cols {
+            let index = row * self.cols + col;
+            self.entries[index] = self.entries[index].divide(divisor);
+        }
+    }
+
+    /// Subtracts a multiple of the source row from the target row, in place.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn subtract_row_multiple(&mut self, target: usize, source: usize, factor: &Number) {
+        for col in 0..self.cols {
+            let product = self.get(source, col).multiply(factor);
+            let index = target * self.cols + col;
+            self.entries[index] = self.entries[index].subtract(&product);
+        }
+    }
+
+    /// Makes sure two matrices are the same size before an elementwise operation.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn check_same_size(&self, other: &Matrix, operation: &str) -> Result<(), Box<dyn Error>> {
+        if self.rows != other.rows || self.cols != other.cols {
+            bail!(
+                "Cannot {} a {}x{} matrix and a {}x{} matrix!",
+                operation,
+                self.rows,
+                self.cols,
+                other.rows,
+                other.cols
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Makes sure the matrix is square before an operation that requires it.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn check_square(&self, operation: &str) -> Result<(), Box<dyn Error>> {
+        if !self.is_square() {
+            bail!(
+                "Cannot take the {} of a non-square {}x{} matrix!",
+                operation,
+                self.rows,
+                self.cols
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Matrix of complex numbers, kept as one matrix of the real parts and another of the imaginary parts so the arithmetic stays exact.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComplexMatrix {
+    /// Real parts of the entries.
+    pub real: Matrix,
+    /// Imaginary parts of the entries.
+    pub imaginary: Matrix,
+}
+
+impl ComplexMatrix {
+    /// Creates a complex matrix with no imaginary parts.
+    ///
+    pub fn from_real(real: Matrix) -> Self {
+        let imaginary = real.scale(&Number::zero());
+
+        Self { real, imaginary }
+    }
+
+    /// Converts a simplified matrix token with number or complex entries into a complex matrix.
+    ///
+    /// Returns a simple error if the token isn't a matrix or if any of the entries aren't numbers.
+    ///
+    pub fn from_token(token: &Token) -> Result<Self, Box<dyn Error>> {
+        let rows = match token {
+            Token::Matrix(rows) => rows,
+            _ => bail!("Expected a matrix!"),
+        };
+
+        // Split the entries into a matrix token of the real parts and another of the imaginary parts
+        let mut real_rows = Vec::<Vec<Token>>::with_capacity(rows.len());
+        let mut imaginary_rows = Vec::<Vec<Token>>::with_capacity(rows.len());
+
+        for row in rows {
+            let mut real_row = Vec::<Token>::with_capacity(row.len());
+            let mut imaginary_row = Vec::<Token>::with_capacity(row.len());
+
+            for entry in row {
+                let complex = match entry {
+                    Token::Number(number) => Complex::from_real(number.clone()),
+                    Token::Complex(complex) => complex.clone(),
+                    _ => bail!("Matrix entries must be numbers!"),
+                };
+
+                real_row.push(Token::Number(complex.real));
+                imaginary_row.push(Token::Number(complex.imaginary));
+            }
+
+            real_rows.push(real_row);
+            imaginary_rows.push(imaginary_row);
+        }
+
+        Ok(Self {
+            real: Matrix::from_token(&Token::Matrix(real_rows))?,
+            imaginary: Matrix::from_token(&Token::Matrix(imaginary_rows))?,
+        })
+    }
+
+    /// Converts the matrix back into a matrix token, with entries that have no imaginary part as plain numbers.
+    ///
+    pub fn to_token(&self) -> Token {
+        let entries: Vec<Token> = self
+            .real
+            .entries
+            .iter()
+            .zip(&self.imaginary.entries)
+            .map(|(real, imaginary)| match imaginary.is_zero() {
+                true => Token::Number(real.clone()),
+                false => Token::Complex(Complex::new(real.clone(), imaginary.clone())),
+            })
+            .collect();
+
+        Token::Matrix(
+            entries
+                .chunks(self.real.cols)
+                .map(|row| row.to_vec())
+                .collect(),
+        )
+    }
+
+    /// Adds this matrix to another matrix of the same size.
+    ///
+    pub fn add(&self, other: &ComplexMatrix) -> Result<ComplexMatrix, Box<dyn Error>> {
+        Ok(Self {
+            real: self.real.add(&other.real)?,
+            imaginary: self.imaginary.add(&other.imaginary)?,
+        })
+    }
+
+    /// Subtracts a matrix of the same size from this matrix.
+    ///
+    pub fn subtract(&self, other: &ComplexMatrix) -> Result<ComplexMatrix, Box<dyn Error>> {
+        Ok(Self {
+            real: self.real.subtract(&other.real)?,
+            imaginary: self.imaginary.subtract(&other.imaginary)?,
+        })
+    }
+
+    /// Multiplies every entry of the matrix by a complex number.
+    ///
+    pub fn scale(&self, scalar: &Complex) -> ComplexMatrix {
+        // (A + Bi)(a + bi) = (aA - bB) + (bA + aB)i
+        Self {
+            real: self
+                .real
+                .scale(&scalar.real)
+                .subtract(&self.imaginary.scale(&scalar.imaginary))
+                .unwrap(), // Both parts are always the same size
+            imaginary: self
+                .real
+                .scale(&scalar.imaginary)
+                .add(&self.imaginary.scale(&scalar.real))
+                .unwrap(),
+        }
+    }
+
+    /// Multiplies this matrix by another matrix.
+    ///
+    /// Returns a simple error if the columns of this matrix don't match the rows of the other.
+    ///
+    pub fn multiply(&self, other: &ComplexMatrix) -> Result<ComplexMatrix, Box<dyn Error>> {
+        // (A + Bi)(C + Di) = (AC - BD) + (AD + BC)i
+        Ok(Self {
+            real: self
+                .real
+                .multiply(&other.real)?
+                .subtract(&self.imaginary.multiply(&other.imaginary)?)?,
+            imaginary: self
+                .real
+                .multiply(&other.imaginary)?
+                .add(&self.imaginary.multiply(&other.real)?)?,
+        })
+    }
+
+    /// Calculates the inverse of a square matrix.
+    ///
+    /// A + Bi behaves just like the real block matrix [[A, -B], [B, A]], whose inverse has the same layout with the parts of the inverse
+    /// in it. Returns a simple error if the matrix is singular.
+    ///
+    pub fn inverse(&self) -> Result<ComplexMatrix, Box<dyn Error>> {
+        self.real.check_square("inverse")?;
+
+        let n = self.real.rows;
+        let mut entries = Vec::<Number>::with_capacity(4 * n * n);
+
+        for row in 0..2 * n {
+            for col in 0..2 * n {
+                entries.push(match (row < n, col < n) {
+                    (true, true) => self.real.get(row, col).clone(),
+                    (true, false) => self.imaginary.get(row, col - n).negative(),
+                    (false, true) => self.imaginary.get(row - n, col).clone(),
+                    (false, false) => self.real.get(row - n, col - n).clone(),
+                });
+            }
+        }
+
+        let inverse = Matrix::new(2 * n, 2 * n, entries)?.inverse()?;
+        let part = |offset: usize| {
+            let entries = (0..n * n)
+                .map(|i| inverse.get(offset + i / n, i % n).clone())
+                .collect();
+
+            Matrix::new(n, n, entries)
+        };
+
+        Ok(Self {
+            real: part(0)?,
+            imaginary: part(n)?,
+        })
+    }
+
+    /// Raises a square matrix to an integer power, using the inverse for negative powers.
+    ///
+    pub fn power(&self, exponent: i64) -> Result<ComplexMatrix, Box<dyn Error>> {
+        self.real.check_square("power")?;
+
+        if exponent.unsigned_abs() > MAX_POWER {
+            bail!("Matrices can only be raised to powers up to {}!", MAX_POWER);
+        }
+
+        let base = match exponent < 0 {
+            true => self.inverse()?,
+            false => self.clone(),
+        };
+
+        // Exponentiation by squaring, going through the bits of the power from the lowest
+        let mut result = Self::from_real(Matrix::identity(self.real.rows)?);
+        let mut square = base;
+        let mut bits = exponent.unsigned_abs();
+
+        while bits > 0 {
+            if bits & 1 == 1 {
+                result = result.multiply(&square)?;
+            }
+
+            bits >>= 1;
+
+            if bits > 0 {
+                square = square.multiply(&square)?;
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(rows: usize, cols: usize, entries: &[i64]) -> Matrix {
+        Matrix::new(
+            rows,
+            cols,
+            entries
+                .iter()
+                .map(|entry| Number::from_i64(*entry))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_matrix_multiply() {
+        let a = matrix(2, 3, &[1, 2, 3, 4, 5, 6]);
+        let b = matrix(3, 2, &[7, 8, 9, 10, 11, 12]);
+
+        assert_eq!(a.multiply(&b).unwrap(), matrix(2, 2, &[58, 64, 139, 154]));
+
+        // Columns of the left don't match the rows of the right
+        a.multiply(&a).unwrap_err();
+    }
+
+    #[test]
+    fn test_matrix_transpose() {
+        let a = matrix(2, 3, &[1, 2, 3, 4, 5, 6]);
+
+        assert_eq!(a.transpose(), matrix(3, 2, &[1, 4, 2, 5, 3, 6]));
+    }
+
+    #[test]
+    fn test_matrix_determinant() {
+        let a = matrix(3, 3, &[2, -3, 1, 2, 0, -1, 1, 4, 5]);
+        let singular = matrix(2, 2, &[1, 2, 2, 4]);
+
+        assert_eq!(a.determinant().unwrap(), Number::from_i64(49));
+        assert_eq!(singular.determinant().unwrap(), Number::zero());
+    }
+
+    #[test]
+    fn test_matrix_inverse() {
+        let a = matrix(3, 3, &[0, 1, 2, 1, 0, 3, 4, -3, 8]);
+        let singular = matrix(2, 2, &[1, 2, 2, 4]);
+
+        let inverse = a.inverse().unwrap();
+
+        // The inverse must be exact, so multiplying by it must give the identity exactly
+        assert_eq!(a.multiply(&inverse).unwrap(), Matrix::identity(3).unwrap());
+        assert_eq!(inverse.get(0, 0), &Number::from_str("-4.5").unwrap());
+
+        singular.inverse().unwrap_err();
+    }
+
+    #[test]
+    fn test_matrix_power() {
+        let a = matrix(2, 2, &[1, 1, 1, 0]);
+
+        // Powers of the Fibonacci matrix hold Fibonacci numbers
+        assert_eq!(a.power(10).unwrap(), matrix(2, 2, &[89, 55, 55, 34]));
+        assert_eq!(a.power(0).unwrap(), Matrix::identity(2).unwrap());
+        assert_eq!(
+            a.power(-3).unwrap().multiply(&a.power(3).unwrap()).unwrap(),
+            Matrix::identity(2).unwrap()
+        );
+
+        // Huge powers and sizes are errors rather than hanging or running out of memory
+        a.power(100_000).unwrap_err();
+        assert!(matrix(2, 2, &[1, 2, 2, 4])
+            .power(-100_000)
+            .unwrap_err()
+            .to_string()
+            .contains("powers up to"));
+        Matrix::identity(100_000_000).unwrap_err();
+    }
+
+    #[test]
+    fn test_complex_matrix() {
+        let i = Complex::new(Number::zero(), Number::one());
+        let a = ComplexMatrix {
+            real: matrix(2, 2, &[1, 0, 2, 1]),
+            imaginary: matrix(2, 2, &[1, 1, 0, -1]),
+        };
+        let identity = ComplexMatrix::from_real(Matrix::identity(2).unwrap());
+
+        assert_eq!(a.multiply(&a.inverse().unwrap()).unwrap(), identity);
+        assert_eq!(
+            a.power(-2).unwrap().multiply(&a.power(2).unwrap()).unwrap(),
+            identity
+        );
+        assert_eq!(
+            identity.scale(&i).scale(&i),
+            identity.scale(&Complex::from_real(Number::from_i64(-1)))
+        );
+        assert_eq!(ComplexMatrix::from_token(&a.to_token()).unwrap(), a);
+
+        // Singular matrices have no inverse
+        let singular = ComplexMatrix {
+            real: matrix(2, 2, &[1, 0, 0, 0]),
+            imaginary: matrix(2, 2, &[0, 1, 0, 0]),
+        };
+
+        singular.inverse().unwrap_err();
+    }
+
+    #[test]
+    fn test_matrix_solve() {
+        let a = matrix(2, 2, &[2, 1, 1, 3]);
+        let b = matrix(1, 2, &[3, 5]);
+
+        // 2x + y = 3, x + 3y = 5 gives x = 4/5, y = 7/5 exactly
+        let x = a.solve(&b).unwrap();
+
+        assert_eq!(x.rows, 1);
+        assert_eq!(x.get(0, 0), &Number::from_str("0.8").unwrap());
+        assert_eq!(x.get(0, 1), &Number::from_str("1.4").unwrap());
+
+        // Column vectors come back as column vectors
+        let x = a.solve(&b.transpose()).unwrap();
+
+        assert_eq!(x.cols, 1);
+
+        // x + 2y = 1, 2x + 4y = 2 has infinitely many solutions...
+        let singular = matrix(2, 2, &[1, 2, 2, 4]);
+        let error = singular.solve(&matrix(1, 2, &[1, 2])).unwrap_err();
+
+        assert!(error.to_string().contains("singular"));
+
+        // And x + 2y = 1, 2x + 4y = 3 has none
+        let error = singular.solve(&matrix(1, 2, &[1, 3])).unwrap_err();
+
+        assert!(error.to_string().contains("inconsistent"));
+
+        // The wrong number of constants
+        a.solve(&matrix(1, 3, &[1, 2, 3])).unwrap_err();
+    }
+}
@@ -23,6 +23,7 @@ use fraction::BigUint;
 use fraction::One;
 use fraction::Sign;
 use fraction::Signed;
+use fraction::ToPrimitive;
 use fraction::Zero;
 use lazy_static::*;
 use serde::Deserialize;
@@ -31,19 +32,84 @@ use std::error::Error;
 use std::str::FromStr;
 
 /// Type used to represent and operate on all numerical values, currently just a Big Fraction.
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
 pub struct Number {
     /// Fractional representation of the number.
     fraction: BigFraction,
 }
 
+/// Exact fraction of a number, with the numerator and denominator written out as strings so no digits are lost in formats like JSON.
+///
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ExactFraction {
+    /// Numerator in lowest terms, with the sign of the number.
+    pub numerator: String,
+    /// Denominator in lowest terms, always positive.
+    pub denominator: String,
+}
+
+/// Extra decimal places carried through exponentials, logarithms and trigonometric functions, so rounding doesn't creep into the result.
+///
+/// **NOT PUBLIC.**
+///
+const TRANSCENDENTAL_GUARD_DIGITS: u32 = 5;
+
+/// Largest numerator and denominator of a fractional power that is worked out with a power and a root rather than logarithms.
+///
+/// **NOT PUBLIC.**
+///
+const MAX_ROOT_POWER: u32 = 64;
+
+/// Largest power e is raised to before exp gives up and returns infinity, e^10000 already has over four thousand digits.
+///
+/// **NOT PUBLIC.**
+///
+const MAX_EXP_POWER: i64 = 10_000;
+
+/// Most bits the numerator or denominator of an exact power can take, powers bigger than this go through logarithms instead.
+///
+/// **NOT PUBLIC.**
+///
+const MAX_EXACT_POWER_BITS: u64 = 1 << 17;
+
+/// Most digits before the decimal point an angle can have, since reducing it needs pi to that many more digits.
+///
+/// **NOT PUBLIC.**
+///
+const MAX_ANGLE_DIGITS: u32 = 100;
+
 impl Number {
     /// Converts a string to a number.
     ///
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(string: &str) -> Result<Self, Box<dyn Error>> {
         let fraction = BigFraction::from_str(string)?;
 
-        Ok(Number { fraction: fraction })
+        Ok(Number { fraction })
+    }
+
+    /// Returns 0 as a number.
+    ///
+    pub fn zero() -> Self {
+        Self {
+            fraction: BigFraction::zero(),
+        }
+    }
+
+    /// Returns 1 as a number.
+    ///
+    pub fn one() -> Self {
+        Self {
+            fraction: BigFraction::one(),
+        }
+    }
+
+    /// Converts an integer to a number.
+    ///
+    pub fn from_i64(int: i64) -> Self {
+        Self {
+            fraction: BigFraction::from(int),
+        }
     }
 
     /// Returns -1 as a number.
@@ -59,18 +125,212 @@ impl Number {
     /// Postfixes three dots to indicate there's a loss of precision when rendering.
     ///
     pub fn to_string(&self, prec: u32) -> String {
+        // Infinity and NaN can't be rounded to a precision
+        if !self.is_finite() {
+            return format!("{}", self.fraction);
+        }
+
         // Tell the user that there is more precision than displayed
         // I'll eventually think of a better way to see if we should print three dots...
-        let base_str = format!("{num:.prec$}", num = self.fraction, prec = prec as usize);
-        let printed_value = BigFraction::from_str(&base_str).unwrap(); // This should always work, right?
+        // Cutting the digits off with whole numbers first is much faster than formatting a fraction with a huge denominator, like 1.0000001^5000
+        let (numer, denom) = (
+            self.fraction.numer().unwrap(),
+            self.fraction.denom().unwrap(),
+        );
+        let scale = BigUint::from(10u8).pow(prec);
+        let truncated = BigFraction::new(numer * &scale / denom, scale);
+        let truncated = match self.is_negative() {
+            true => -truncated,
+            false => truncated,
+        };
+        let base_str = format!("{num:.prec$}", num = truncated, prec = prec as usize);
 
-        if printed_value != self.fraction {
+        if truncated != self.fraction {
             return format!("{}...", base_str);
         }
 
         base_str
     }
 
+    /// Renders the number without losing any precision, as a decimal if it has one that ends and as a fraction like "1/3" otherwise.
+    ///
+    pub fn to_exact_string(&self) -> String {
+        let (numer, denom) = match (self.fraction.numer(), self.fraction.denom()) {
+            (Some(numer), Some(denom)) => (numer, denom),
+            _ => return format!("{}", self.fraction), // Infinity and NaN
+        };
+
+        // A fraction only has a decimal that ends if its denominator is made of twos and fives, with one place per factor of the more common one
+        let mut remaining = denom.clone();
+        let mut places = [0usize; 2];
+
+        for (i, factor) in [2u8, 5u8].iter().enumerate() {
+            while (&remaining % *factor).is_zero() {
+                remaining /= *factor;
+                places[i] += 1;
+            }
+        }
+
+        if remaining.is_one() {
+            return format!(
+                "{num:.prec$}",
+                num = self.fraction,
+                prec = places[0].max(places[1])
+            );
+        }
+
+        match self.is_negative() {
+            true => format!("-{}/{}", numer, denom),
+            false => format!("{}/{}", numer, denom),
+        }
+    }
+
+    /// Gets the exact fraction of the number as strings, None if the number is infinite or NaN.
+    ///
+    pub fn to_exact_fraction(&self) -> Option<ExactFraction> {
+        if !self.is_finite() {
+            return None;
+        }
+
+        Some(ExactFraction {
+            numerator: self.numerator().to_exact_string(),
+            denominator: self.denominator().to_exact_string(),
+        })
+    }
+
+    /// Returns true if the number is equal to zero.
+    ///
+    pub fn is_zero(&self) -> bool {
+        self.fraction.is_zero()
+    }
+
+    /// Returns true if the number is neither infinite nor NaN, like the result of dividing by zero.
+    ///
+    pub fn is_finite(&self) -> bool {
+        matches!(self.fraction, BigFraction::Rational(..))
+    }
+
+    /// Returns true if the number is finite and has no more decimal places than the given precision, so rendering it loses nothing.
+    ///
+    pub fn is_exact_to(&self, prec: u32) -> bool {
+        self.is_finite() && self.round(prec) == *self
+    }
+
+    /// Returns the number as an i64 if it is a whole number small enough to fit in one.
+    ///
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.fraction.denom()? != &BigUint::one() {
+            return None;
+        }
+
+        let magnitude = self.fraction.numer()?.to_i64()?;
+
+        match self.fraction.is_negative() {
+            true => Some(-magnitude),
+            false => Some(magnitude),
+        }
+    }
+
+    /// Gets the numerator of the number as a fraction in lowest terms, keeping the sign.
+    ///
+    pub fn numerator(&self) -> Number {
+        match self.fraction.numer() {
+            Some(numer) => Number {
+                fraction: match self.is_negative() {
+                    true => -BigFraction::new(numer.clone(), 1u8),
+                    false => BigFraction::new(numer.clone(), 1u8),
+                },
+            },
+            None => self.clone(),
+        }
+    }
+
+    /// Gets the denominator of the number as a fraction in lowest terms, always positive.
+    ///
+    pub fn denominator(&self) -> Number {
+        match self.fraction.denom() {
+            Some(denom) => Number {
+                fraction: BigFraction::new(denom.clone(), 1u8),
+            },
+            None => Number::one(),
+        }
+    }
+
+    /// Returns true if the number is less than zero.
+    ///
+    pub fn is_negative(&self) -> bool {
+        self.fraction < BigFraction::zero()
+    }
+
+    /// Returns the absolute value of the number.
+    ///
+    pub fn abs(&self) -> Number {
+        Number {
+            fraction: self.fraction.abs(),
+        }
+    }
+
+    /// Approximates the number with a denominator of 10^decimal_places, for calculations that don't need perfect accuracy.
+    ///
+    /// Keeps numbers from growing absurdly large denominators during iterative algorithms.
+    ///
+    pub fn approximate(&self, decimal_places: u32) -> Number {
+        lazy_static! {
+            static ref TEN_BIGINT: BigUint = 10u8.into();
+        }
+
+        match &self.fraction {
+            BigFraction::Infinity(_) | BigFraction::NaN => self.clone(),
+            _ => Number {
+                fraction: Self::round_denom(self.fraction.clone(), &TEN_BIGINT.pow(decimal_places)),
+            },
+        }
+    }
+
+    /// Rounds the number to the nearest multiple of 10^-decimal_places, halves rounding away from zero.
+    ///
+    pub fn round(&self, decimal_places: u32) -> Number {
+        let (numer, denom) = match (self.fraction.numer(), self.fraction.denom()) {
+            (Some(numer), Some(denom)) => (numer, denom),
+            _ => return self.clone(),
+        };
+
+        let scale = BigUint::from(10u8).pow(decimal_places);
+        let two = BigUint::from(2u8);
+        let rounded = (numer * &scale * &two + denom) / (denom * &two);
+
+        let magnitude = Number {
+            fraction: BigFraction::new(rounded, scale),
+        };
+
+        match self.is_negative() {
+            true => magnitude.negative(),
+            false => magnitude,
+        }
+    }
+
+    /// Gets the exact square root of the number, if the square root is rational.
+    ///
+    pub fn exact_sqrt(&self) -> Option<Number> {
+        if self.is_negative() {
+            return None;
+        }
+
+        let numer = self.fraction.numer()?;
+        let denom = self.fraction.denom()?;
+
+        let numer_sqrt = numer.sqrt();
+        let denom_sqrt = denom.sqrt();
+
+        if &(&numer_sqrt * &numer_sqrt) != numer || &(&denom_sqrt * &denom_sqrt) != denom {
+            return None;
+        }
+
+        Some(Number {
+            fraction: BigFraction::new(numer_sqrt, denom_sqrt),
+        })
+    }
+
     /// Makes this number negative
     ///
     pub fn negative(&self) -> Number {
@@ -119,6 +379,28 @@ impl Number {
             _ => {}
         }
 
+        // Repeated multiplication and roots get very slow for powers like 2^1.41421, and exact results like 3^4000000000 are far too big to
+        // work out, so go through logarithms instead
+        if (!exp.is_integer() && !exp.is_small_fraction())
+            || self.exact_power_bits(exp) > MAX_EXACT_POWER_BITS
+        {
+            return match (self.is_zero(), self.is_negative(), exp.is_integer()) {
+                (true, _, _) if exp.is_negative() => Number::infinity(),
+                (true, _, _) => Number::zero(),
+                (false, false, _) => self.exponent_by_logarithm(exp, prec),
+                (false, true, true) => {
+                    let magnitude = self.abs().exponent_by_logarithm(exp, prec);
+
+                    match exp.numerator().fraction.numer().unwrap().bit(0) {
+                        true => magnitude.negative(),
+                        false => magnitude,
+                    }
+                }
+                // Roots of negative numbers aren't real
+                (false, true, false) => Number::nan(),
+            };
+        }
+
         let result = Self {
             fraction: Self::pow(&self.fraction, &exp.fraction),
         };
@@ -136,6 +418,70 @@ impl Number {
         }
     }
 
+    /// Returns true if the result of raising this number to a power with exponent() is exact rather than an approximation.
+    ///
+    /// Whole number powers that aren't too big to work out are always exact, and roots are exact when they come out even, like 8^(2/3).
+    ///
+    pub fn is_exact_power(&self, exp: &Number, result: &Number) -> bool {
+        if !self.is_finite() || !exp.is_finite() || !result.is_finite() {
+            return true;
+        }
+
+        match (
+            self.exact_power_bits(exp) <= MAX_EXACT_POWER_BITS,
+            exp.is_integer(),
+            exp.is_small_fraction(),
+        ) {
+            (true, true, _) => true,
+            // Undoing the root has to give back the exact power
+            (true, false, true) => {
+                result.exponent(&exp.denominator(), 0) == self.exponent(&exp.numerator(), 0)
+            }
+            _ => false,
+        }
+    }
+
+    /// Estimates how many bits the numerator or denominator of this number raised to a power would take, working it out exactly.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn exact_power_bits(&self, exp: &Number) -> u64 {
+        let bits = match (self.fraction.numer(), self.fraction.denom()) {
+            (Some(numer), Some(denom)) => numer.bits().max(denom.bits()),
+            _ => 0,
+        };
+
+        match exp.fraction.numer().and_then(|numer| numer.to_u64()) {
+            Some(power) => bits.saturating_mul(power),
+            None if bits == 0 => 0,
+            None => u64::MAX,
+        }
+    }
+
+    /// Raises this positive number to a power through logarithms, as e^(power * ln(self)).
+    ///
+    /// The error in the logarithm is multiplied by the result, so it's worked out to as many more digits as the result has before the
+    /// decimal point.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn exponent_by_logarithm(&self, exp: &Number, prec: u32) -> Number {
+        let estimate = exp.multiply(&self.ln(TRANSCENDENTAL_GUARD_DIGITS));
+
+        // Past this exp gives up anyway
+        if estimate.abs() > Number::from_i64(MAX_EXP_POWER + 1) {
+            return match estimate.is_negative() {
+                true => Number::zero(),
+                false => Number::infinity(),
+            };
+        }
+
+        exp.multiply(
+            &self.ln(prec + TRANSCENDENTAL_GUARD_DIGITS + estimate.integer_digits_of_exp()),
+        )
+        .exp(prec)
+    }
+
     /// Raises a BigFraction to the power of another BigFraction, ignoring the denominator of the power
     ///
     /// **PRIVATE FUNCTION**
@@ -146,14 +492,36 @@ impl Number {
             _ => {}
         }
 
-        let mut i = pow.numer().unwrap().clone();
-        let mut result = num.clone();
-
-        while i > BigUint::one() {
-            result = num * &result;
-            i -= BigUint::one();
+        // Anything to the power of zero is one
+        if pow.is_zero() {
+            return BigFraction::one();
         }
 
+        // Powers of a fraction in lowest terms are still in lowest terms, so the numerator and denominator are raised separately
+        let result = match pow.numer().unwrap().to_u32() {
+            Some(i) => {
+                let magnitude =
+                    BigFraction::new_raw(num.numer().unwrap().pow(i), num.denom().unwrap().pow(i));
+
+                match num.is_negative() && i % 2 == 1 {
+                    true => -magnitude,
+                    false => magnitude,
+                }
+            }
+            // Powers this big only have room for a result when the base is one or minus one
+            None if num.numer() == num.denom() => {
+                match num.is_negative() && pow.numer().unwrap().bit(0) {
+                    true => -BigFraction::one(),
+                    false => BigFraction::one(),
+                }
+            }
+            None if num.abs() < BigFraction::one() => BigFraction::zero(),
+            None => match num.is_negative() && pow.numer().unwrap().bit(0) {
+                true => BigFraction::neg_infinity(),
+                false => BigFraction::infinity(),
+            },
+        };
+
         if pow.is_negative() {
             BigFraction::one() / result
         } else {
@@ -215,7 +583,7 @@ impl Number {
 
         // Will compute until every digit up to the precision is 100% accurate, in theory
         //
-        while &last_move >= &min_move {
+        while last_move >= min_move {
             // Keep iterating until the iterations result in inconsequential changes
             x = Self::round_denom(x, &prec_working); // Round the denominator to the working precision to make sure our BigFraction doesn't get too big...
             last_move = x.clone(); // Store our current x value into last_move to see the difference between the previous iteration and the current iteration
@@ -235,7 +603,7 @@ impl Number {
         let exp = root.fraction.denom().unwrap().clone(); // Get the possible exponent we need to raise the rooted number to from the denominator of the root
 
         // If the root is a whole number, then we're done!
-        if &exp == &BigUint::one() {
+        if exp == BigUint::one() {
             result
         }
         // Otherwise raise the number to the power of the root's denominator
@@ -248,14 +616,350 @@ impl Number {
         }
     }
 
+    /// Gets pi to the given precision.
+    ///
+    pub fn pi(prec: u32) -> Number {
+        let working = prec + TRANSCENDENTAL_GUARD_DIGITS;
+
+        // Machin's formula, pi = 16 atan(1/5) - 4 atan(1/239)
+        let fifth = Self::atan_series(&Number::from_i64(5), working);
+        let two_hundred_thirty_ninth = Self::atan_series(&Number::from_i64(239), working);
+
+        Number::from_i64(16)
+            .multiply(&fifth)
+            .subtract(&Number::from_i64(4).multiply(&two_hundred_thirty_ninth))
+            .round(prec + 3)
+    }
+
+    /// Gets e to the given precision.
+    ///
+    pub fn e(prec: u32) -> Number {
+        Number::one().exp(prec)
+    }
+
+    /// Raises e to the power of this number.
+    ///
+    pub fn exp(&self, prec: u32) -> Number {
+        if !self.is_finite() {
+            return self.clone();
+        }
+
+        // Past this the result is too big to write out, or too small to show at any sensible precision
+        if self.abs() > Number::from_i64(MAX_EXP_POWER) {
+            return match self.is_negative() {
+                true => Number::zero(),
+                false => Number::infinity(),
+            };
+        }
+
+        // Halve the power until the series converges quickly, then square the result back up
+        let half = Number::from_str("0.5").unwrap();
+        let mut reduced = self.clone();
+        let mut halvings = 0;
+
+        while reduced.abs() > half {
+            reduced = reduced.multiply(&half);
+            halvings += 1;
+        }
+
+        // Squaring the result back up multiplies its error too, so every digit before the decimal point needs one more digit of working
+        let working = prec + TRANSCENDENTAL_GUARD_DIGITS + halvings + self.integer_digits_of_exp();
+
+        let mut result = Self::sum_series(working, |i, term| match i {
+            0 => Number::one(),
+            _ => term.multiply(&reduced).divide(&Number::from_i64(i as i64)),
+        });
+
+        for _ in 0..halvings {
+            result = result.multiply(&result).approximate(working);
+        }
+
+        result.round(prec + 3)
+    }
+
+    /// Gets the natural logarithm of this number, NaN if the number isn't positive.
+    ///
+    pub fn ln(&self, prec: u32) -> Number {
+        if !self.is_finite() || self.is_negative() || self.is_zero() {
+            return Number::nan();
+        }
+
+        let two = Number::from_i64(2);
+
+        // Scale the number into [1, 2] by powers of two, since ln(m * 2^k) = ln(m) + k ln(2)
+        // The difference in bit lengths gets within one power of two in a single step, even for numbers like 10^(10^5)
+        let mut twos = self.bit_length_difference();
+
+        // The error in ln(2) is multiplied by the number of twos, so get it to that many more digits
+        let working =
+            prec + TRANSCENDENTAL_GUARD_DIGITS + twos.unsigned_abs().to_string().len() as u32 + 1;
+
+        // Divide with whole numbers and round straight away, reducing a fraction with thousands of digits takes far too long
+        let numer = self.fraction.numer().unwrap();
+        let denom = self.fraction.denom().unwrap();
+        let (numer, denom) = match twos < 0 {
+            true => (numer << twos.unsigned_abs(), denom.clone()),
+            false => (numer.clone(), denom << twos.unsigned_abs()),
+        };
+
+        let scale = BigUint::from(10u8).pow(working + 2);
+        let mut mantissa = Number {
+            fraction: BigFraction::new(numer * &scale / denom, scale),
+        };
+
+        while mantissa > two {
+            mantissa = mantissa.divide(&two);
+            twos += 1;
+        }
+
+        while mantissa < Number::one() {
+            mantissa = mantissa.multiply(&two);
+            twos -= 1;
+        }
+
+        // ln(m) = 2 atanh((m - 1) / (m + 1)), which converges quickly since (m - 1) / (m + 1) is at most 1/3
+        let ln_mantissa = Self::atanh_series(
+            &mantissa
+                .subtract(&Number::one())
+                .divide(&mantissa.add(&Number::one())),
+            working,
+        );
+
+        let ln_two = Self::atanh_series(&Number::one().divide(&Number::from_i64(3)), working);
+
+        two.multiply(&ln_mantissa)
+            .add(&Number::from_i64(2 * twos).multiply(&ln_two))
+            .round(prec + 3)
+    }
+
+    /// Gets the sine of this number, in radians.
+    ///
+    pub fn sin(&self, prec: u32) -> Number {
+        if !self.is_finite() {
+            return Number::nan();
+        }
+
+        let working = prec + TRANSCENDENTAL_GUARD_DIGITS;
+        let x = match self.reduce_angle(working) {
+            Some(x) => x,
+            None => return Number::nan(),
+        };
+        let x_squared = x.multiply(&x);
+
+        Self::sum_series(working, |i, term| match i {
+            0 => x.clone(),
+            _ => term
+                .multiply(&x_squared)
+                .divide(&Number::from_i64(-((2 * i) as i64) * (2 * i + 1) as i64)),
+        })
+        .round(prec + 3)
+    }
+
+    /// Gets the cosine of this number, in radians.
+    ///
+    pub fn cos(&self, prec: u32) -> Number {
+        if !self.is_finite() {
+            return Number::nan();
+        }
+
+        let working = prec + TRANSCENDENTAL_GUARD_DIGITS;
+        let x = match self.reduce_angle(working) {
+            Some(x) => x,
+            None => return Number::nan(),
+        };
+        let x_squared = x.multiply(&x);
+
+        Self::sum_series(working, |i, term| match i {
+            0 => Number::one(),
+            _ => term
+                .multiply(&x_squared)
+                .divide(&Number::from_i64(-((2 * i - 1) as i64) * (2 * i) as i64)),
+        })
+        .round(prec + 3)
+    }
+
+    /// Gets the tangent of this number, in radians.
+    ///
+    pub fn tan(&self, prec: u32) -> Number {
+        let working = prec + TRANSCENDENTAL_GUARD_DIGITS;
+
+        self.sin(working).divide(&self.cos(working)).round(prec + 3)
+    }
+
+    /// Returns true if the number is a whole number.
+    ///
+    pub fn is_integer(&self) -> bool {
+        self.fraction.denom() == Some(&BigUint::one())
+    }
+
+    /// Returns NaN as a number.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn nan() -> Self {
+        Self {
+            fraction: BigFraction::NaN,
+        }
+    }
+
+    /// Returns positive infinity as a number.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn infinity() -> Self {
+        Self {
+            fraction: BigFraction::infinity(),
+        }
+    }
+
+    /// Returns true if the number is a fraction simple enough to raise numbers to with a power and a root.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn is_small_fraction(&self) -> bool {
+        match (self.fraction.numer(), self.fraction.denom()) {
+            (Some(numer), Some(denom)) => {
+                numer <= &BigUint::from(MAX_ROOT_POWER) && denom <= &BigUint::from(MAX_ROOT_POWER)
+            }
+            _ => false,
+        }
+    }
+
+    /// Brings an angle into [-pi, pi], so the sine and cosine series converge quickly.
+    ///
+    /// Returns None if the angle has more than MAX_ANGLE_DIGITS digits before the decimal point.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn reduce_angle(&self, working: u32) -> Option<Number> {
+        // Every digit of whole turns costs a digit of pi's accuracy, so get pi to that many more digits before counting the turns
+        let digits = (self.bit_length_difference().max(0) as u64 * 30_103 / 100_000 + 1) as u32;
+
+        if digits > MAX_ANGLE_DIGITS {
+            return None;
+        }
+
+        let two_pi = Number::from_i64(2).multiply(&Number::pi(working + digits));
+        let turns = self.divide(&two_pi).round(0);
+
+        Some(self.subtract(&turns.multiply(&two_pi)).approximate(working))
+    }
+
+    /// Gets the bit length of the numerator minus the bit length of the denominator, which is within one of log2 of the number.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn bit_length_difference(&self) -> i64 {
+        match (self.fraction.numer(), self.fraction.denom()) {
+            (Some(numer), Some(denom)) => numer.bits() as i64 - denom.bits() as i64,
+            _ => 0,
+        }
+    }
+
+    /// Gets about how many digits e raised to this number has before the decimal point, x / ln(10) rounded up, and zero for negative
+    /// numbers. Only meant for numbers up to MAX_EXP_POWER.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn integer_digits_of_exp(&self) -> u32 {
+        match self.is_negative() {
+            true => 0,
+            // 10/23 is just over 1/ln(10)
+            false => {
+                let digits = self
+                    .multiply(&Number::from_i64(10))
+                    .divide(&Number::from_i64(23));
+
+                digits.round(0).to_i64().map_or(0, |digits| digits as u32) + 1
+            }
+        }
+    }
+
+    /// Sum of atanh(z) = z + z^3/3 + z^5/5 + ..., for small z.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn atanh_series(z: &Number, working: u32) -> Number {
+        let z_squared = z.multiply(z);
+        let mut power = z.clone();
+
+        Self::sum_series(working, |i, _| {
+            if i > 0 {
+                power = power.multiply(&z_squared).approximate(working + 2);
+            }
+
+            power.divide(&Number::from_i64(2 * i as i64 + 1))
+        })
+    }
+
+    /// Sum of atan(1/n) = 1/n - 1/(3n^3) + 1/(5n^5) - ..., for whole numbers n above one.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn atan_series(n: &Number, working: u32) -> Number {
+        let z = Number::one().divide(n);
+        let z_squared = z.multiply(&z);
+        let mut power = z;
+
+        Self::sum_series(working, |i, _| {
+            if i > 0 {
+                power = power
+                    .multiply(&z_squared)
+                    .negative()
+                    .approximate(working + 2);
+            }
+
+            power.divide(&Number::from_i64(2 * i as i64 + 1))
+        })
+    }
+
+    /// Sums the terms of a series until they're too small to matter at the working precision.
+    ///
+    /// The closure is handed the index and the previous term, and returns the next term.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn sum_series<F>(working: u32, mut next_term: F) -> Number
+    where
+        F: FnMut(usize, &Number) -> Number,
+    {
+        let tolerance = Number {
+            fraction: BigFraction::new(1u8, BigUint::from(10u8).pow(working)),
+        };
+
+        let mut sum = Number::zero();
+        let mut term = Number::zero();
+        let mut i = 0;
+
+        loop {
+            term = next_term(i, &term).approximate(working + 2);
+            sum = sum.add(&term);
+            i += 1;
+
+            if term.abs() < tolerance {
+                break;
+            }
+        }
+
+        sum.approximate(working)
+    }
+
     /// Rounds the denominator for quicker calculations which don't need perfect accuracy
     ///
     /// **PRIVATE FUNCTIONS**
     fn round_denom(fract: BigFraction, denom: &BigUint) -> BigFraction {
-        let divisor = fract.denom().unwrap() / denom;
+        let current_denom = fract.denom().unwrap();
 
-        if divisor > BigUint::zero() {
-            BigFraction::new(fract.numer().unwrap() / divisor, denom.clone())
+        if current_denom > denom {
+            let rounded = BigFraction::new(
+                fract.numer().unwrap() * denom / current_denom,
+                denom.clone(),
+            );
+
+            match fract.is_negative() {
+                true => -rounded,
+                false => rounded,
+            }
         } else {
             fract
         }
@@ -274,6 +978,119 @@ mod tests {
         let fract3 = Number::round_denom(fract1, &4u8.into());
 
         assert_eq!(fract2, fract3);
+
+        // The sign must survive rounding
+        let fract4 = Number::round_denom(-BigFraction::new(3u8, 8u8), &4u8.into());
+
+        assert_eq!(-fract2, fract4);
+    }
+
+    #[test]
+    fn test_number_round() {
+        let number = Number::from_str("-2.71828").unwrap();
+
+        assert_eq!(number.round(2), Number::from_str("-2.72").unwrap());
+        assert_eq!(number.round(0), Number::from_i64(-3));
+        assert_eq!(
+            Number::from_str("0.125").unwrap().round(2),
+            Number::from_str("0.13").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_number_transcendental() {
+        assert_eq!(Number::pi(20).to_string(20), "3.14159265358979323846...");
+        assert_eq!(Number::e(10).to_string(10), "2.7182818284...");
+        assert_eq!(Number::from_i64(2).ln(10).to_string(10), "0.6931471805...");
+        assert_eq!(Number::one().sin(10).to_string(10), "0.8414709848...");
+        assert_eq!(
+            Number::from_i64(100).cos(10).to_string(10),
+            "0.8623188722..."
+        );
+        assert_eq!(Number::zero().exp(10), Number::one());
+
+        // Huge arguments are reduced or refused rather than taking forever
+        let huge = Number::from_i64(10).exponent(&Number::from_i64(20), 0);
+
+        assert_eq!(huge.sin(10).to_string(10), "-0.6452512852...");
+        assert!(!huge.exponent(&Number::from_i64(10), 0).sin(10).is_finite());
+        assert!(!Number::from_i64(10_000_000).exp(10).is_finite());
+        assert_eq!(
+            Number::from_i64(10)
+                .exponent(&Number::from_i64(30_000), 0)
+                .ln(10)
+                .to_string(10),
+            "69077.5527898213..."
+        );
+
+        // Integer digits of exp are worked out too, not just the decimal places
+        assert_eq!(
+            Number::from_i64(100).exp(6).to_string(6),
+            "26881171418161354484126255515800135873611118.773741..."
+        );
+
+        // Powers too big to work out exactly go through logarithms, and give up like exp does
+        let ten_million = Number::from_i64(10_000_000);
+
+        assert!(!Number::from_i64(3)
+            .exponent(&Number::from_i64(4_000_000_000), 6)
+            .is_finite());
+        assert!(Number::from_i64(10)
+            .exponent(&ten_million.negative(), 6)
+            .is_zero());
+        assert_eq!(
+            Number::from_str("1.0000001")
+                .unwrap()
+                .exponent(&ten_million.multiply(&Number::from_i64(10)), 6)
+                .to_string(6),
+            "22026.454781..."
+        );
+        assert!(Number::from_i64(-2)
+            .exponent(&Number::from_i64(200_001), 6)
+            .is_negative());
+
+        // Powers with complicated fractions go through logarithms instead of taking forever
+        let power = Number::from_i64(2).exponent(&Number::from_str("1.41421").unwrap(), 6);
+
+        assert_eq!(power.to_string(6), "2.665137...");
+    }
+
+    #[test]
+    fn test_number_exact() {
+        let third = Number::one().divide(&Number::from_i64(-3));
+        let eighth = Number::from_str("0.125").unwrap();
+
+        assert_eq!(third.to_exact_string(), "-1/3");
+        assert_eq!(eighth.to_exact_string(), "0.125");
+        assert_eq!(Number::from_i64(40).to_exact_string(), "40");
+
+        let fraction = eighth.to_exact_fraction().unwrap();
+
+        assert_eq!(
+            (fraction.numerator.as_str(), fraction.denominator.as_str()),
+            ("1", "8")
+        );
+        assert_eq!(third.to_exact_fraction().unwrap().numerator, "-1");
+        assert!(Number::one()
+            .divide(&Number::zero())
+            .to_exact_fraction()
+            .is_none());
+
+        // Exact to a precision only if nothing is cut off, and never for infinity
+        assert!(eighth.is_exact_to(3));
+        assert!(!eighth.is_exact_to(2));
+        assert!(!third.is_exact_to(100));
+        assert!(!Number::one().divide(&Number::zero()).is_exact_to(100));
+
+        // Roots are only exact when they come out even
+        let two_thirds = Number::from_i64(2).divide(&Number::from_i64(3));
+        let half = Number::one().divide(&Number::from_i64(2));
+
+        for (base, exp, exact) in [(8, &two_thirds, true), (2, &half, false), (4, &half, true)] {
+            let base = Number::from_i64(base);
+
+            assert_eq!(base.is_exact_power(exp, &base.exponent(exp, 6)), exact);
+        }
     }
 
     #[test]
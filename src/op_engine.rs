@@ -18,6 +18,12 @@
 // ApeCrunch(in a file named COPYING).
 // If not, see <https://www.gnu.org/licenses/>.
 
+use crate::algebra;
+use crate::complex::Complex;
+use crate::functions;
+use crate::matrix::ComplexMatrix;
+use crate::matrix::Matrix;
+use crate::number::Number;
 use crate::parser::Token;
 use crate::session::Session;
 use crate::variable::Variable;
@@ -29,6 +35,8 @@ use std::error::Error;
 /// For example, 2+2 would be equal to 4.
 ///
 pub fn get_equality(tokens: &Token, session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    session.approximated = false;
+
     Ok(Token::Equality(
         Box::new(tokens.clone()),
         Box::new(simplify(tokens, session)?),
@@ -43,89 +51,275 @@ pub fn simplify(token: &Token, session: &mut Session) -> Result<Token, Box<dyn E
     match token {
         // Almost all of these match cases are the same, understand this one and you understand them all...
         Token::Multiply(left, right) => {
-            let left_result = simplify(left, session)?; // Recursively simplify the left side.
-            let right_result = simplify(right, session)?; // Recursively simplify the right side.
+            let left_result = boolean_as_number(simplify(left, session)?); // Recursively simplify the left side, booleans count as 1 or 0.
+            let right_result = boolean_as_number(simplify(right, session)?); // Recursively simplify the right side.
+
+            // If either side is a matrix, leave it to the matrix arithmetic.
+            if let Some(result) = matrix_arithmetic(token, &left_result, &right_result)? {
+                return Ok(result);
+            }
 
             // If both sides are numbers, operate on them and return a number token.
             if let Token::Number(left_number) = &left_result {
                 if let Token::Number(right_number) = &right_result {
-                    return Ok(Token::Number(left_number.multiply(&right_number)));
+                    return Ok(Token::Number(left_number.multiply(right_number)));
                     // In this case we multiply the two.
                 }
             }
 
-            // Otherwise it cannot be further simplified, and we must return a multiply token.
-            Ok(Token::Multiply(
+            // If one side is a complex number, multiply them as complex numbers.
+            if let Some(result) = complex_arithmetic(token, &left_result, &right_result) {
+                return Ok(result);
+            }
+
+            // Otherwise it cannot be further simplified numerically, and we must return a multiply token, collected if it has symbols in it.
+            Ok(collect_symbolic(Token::Multiply(
                 Box::new(left_result),
                 Box::new(right_result),
-            ))
+            )))
         }
 
         Token::Divide(left, right) => {
-            let left_result = simplify(left, session)?;
-            let right_result = simplify(right, session)?;
+            let left_result = boolean_as_number(simplify(left, session)?);
+            let right_result = boolean_as_number(simplify(right, session)?);
+
+            if let Some(result) = matrix_arithmetic(token, &left_result, &right_result)? {
+                return Ok(result);
+            }
 
             if let Token::Number(left_number) = &left_result {
                 if let Token::Number(right_number) = &right_result {
-                    return Ok(Token::Number(left_number.divide(&right_number)));
+                    return Ok(Token::Number(left_number.divide(right_number)));
                 }
             }
 
-            Ok(Token::Divide(Box::new(left_result), Box::new(right_result)))
+            // Only plain numbers can be divided by zero, anything else stays a division instead of collecting into a multiple of infinity
+            if matches!(&right_result, Token::Number(number) if number.is_zero()) {
+                return Ok(Token::Divide(Box::new(left_result), Box::new(right_result)));
+            }
+
+            if let Some(result) = complex_arithmetic(token, &left_result, &right_result) {
+                return Ok(result);
+            }
+
+            Ok(collect_symbolic(Token::Divide(
+                Box::new(left_result),
+                Box::new(right_result),
+            )))
         }
 
         Token::Add(left, right) => {
-            let left_result = simplify(left, session)?;
-            let right_result = simplify(right, session)?;
+            let left_result = boolean_as_number(simplify(left, session)?);
+            let right_result = boolean_as_number(simplify(right, session)?);
+
+            if let Some(result) = matrix_arithmetic(token, &left_result, &right_result)? {
+                return Ok(result);
+            }
 
             if let Token::Number(left_number) = &left_result {
                 if let Token::Number(right_number) = &right_result {
-                    return Ok(Token::Number(left_number.add(&right_number)));
+                    return Ok(Token::Number(left_number.add(right_number)));
                 }
             }
 
-            Ok(Token::Add(Box::new(left_result), Box::new(right_result)))
+            if let Some(result) = complex_arithmetic(token, &left_result, &right_result) {
+                return Ok(result);
+            }
+
+            Ok(collect_symbolic(Token::Add(
+                Box::new(left_result),
+                Box::new(right_result),
+            )))
         }
 
         Token::Subtract(left, right) => {
-            let left_result = simplify(left, session)?;
-            let right_result = simplify(right, session)?;
+            let left_result = boolean_as_number(simplify(left, session)?);
+            let right_result = boolean_as_number(simplify(right, session)?);
+
+            if let Some(result) = matrix_arithmetic(token, &left_result, &right_result)? {
+                return Ok(result);
+            }
 
             if let Token::Number(left_number) = &left_result {
                 if let Token::Number(right_number) = &right_result {
-                    return Ok(Token::Number(left_number.subtract(&right_number)));
+                    return Ok(Token::Number(left_number.subtract(right_number)));
                 }
             }
 
-            Ok(Token::Subtract(
+            if let Some(result) = complex_arithmetic(token, &left_result, &right_result) {
+                return Ok(result);
+            }
+
+            Ok(collect_symbolic(Token::Subtract(
                 Box::new(left_result),
                 Box::new(right_result),
-            ))
+            )))
         }
 
         Token::Exponent(left, right) => {
-            let left_result = simplify(left, session)?;
-            let right_result = simplify(right, session)?;
+            let right_result = boolean_as_number(simplify(right, session)?);
+
+            // Powers of e go through exp, which works e out to as many digits as the power needs and gives up on powers that are too big.
+            // Working e out on its own first would count as an approximation even for e^0
+            if matches!(left.without_parenthesis(), Token::Constant(id) if id == "e")
+                && matches!(right_result, Token::Number(_))
+            {
+                return simplify(
+                    &Token::Function("exp".to_string(), vec![right_result]),
+                    session,
+                );
+            }
+
+            let left_result = boolean_as_number(simplify(left, session)?);
+
+            if let Some(result) = matrix_arithmetic(token, &left_result, &right_result)? {
+                return Ok(result);
+            }
 
             if let Token::Number(left_number) = &left_result {
                 if let Token::Number(right_number) = &right_result {
-                    return Ok(Token::Number(
-                        left_number.exponent(&right_number, session.decimal_places),
-                    ));
+                    let result = left_number.exponent(right_number, session.decimal_places);
+                    session.approximated |= !left_number.is_exact_power(right_number, &result);
+
+                    return Ok(Token::Number(result));
                 }
             }
 
-            Ok(Token::Exponent(
+            // Complex numbers can be raised to whole number powers exactly, other powers stay as they are
+            if let (Token::Complex(base), Token::Number(exponent)) = (&left_result, &right_result) {
+                let result = match exponent.to_i64() {
+                    Some(exponent) => base.power(exponent)?,
+                    None => {
+                        return Ok(Token::Exponent(
+                            Box::new(Token::Parenthesis(Box::new(left_result.clone()))),
+                            Box::new(right_result),
+                        ))
+                    }
+                };
+
+                return Ok(match result.is_real() {
+                    true => Token::Number(result.real),
+                    false => Token::Complex(result),
+                });
+            }
+
+            Ok(collect_symbolic(Token::Exponent(
                 Box::new(left_result),
                 Box::new(right_result),
-            ))
+            )))
+        }
+
+        Token::Equality(left, right) | Token::NotEqual(left, right) => {
+            let left_result = simplify(left, session)?;
+            let right_result = simplify(right, session)?;
+            let equal = values_equal(&left_result, &right_result, session);
+
+            // Sides with symbols in them that simplify to the same thing are equal, but otherwise it depends on the symbols' values
+            if !equal && (left_result.is_symbolic() || right_result.is_symbolic()) {
+                let (left_result, right_result) = (Box::new(left_result), Box::new(right_result));
+
+                return Ok(match token {
+                    Token::Equality(..) => Token::Equality(left_result, right_result),
+                    _ => Token::NotEqual(left_result, right_result),
+                });
+            }
+
+            Ok(Token::Boolean(match token {
+                Token::Equality(..) => equal,
+                _ => !equal,
+            }))
+        }
+
+        Token::Less(left, right)
+        | Token::Greater(left, right)
+        | Token::LessEqual(left, right)
+        | Token::GreaterEqual(left, right) => {
+            let left_result = boolean_as_number(simplify(left, session)?);
+            let right_result = boolean_as_number(simplify(right, session)?);
+
+            if let (Token::Number(left_number), Token::Number(right_number)) =
+                (&left_result, &right_result)
+            {
+                // Numbers within the tolerance are equal, so neither is less than the other
+                let equal = numbers_equal(left_number, right_number, session);
+
+                return Ok(Token::Boolean(match token {
+                    Token::Less(..) => !equal && left_number < right_number,
+                    Token::Greater(..) => !equal && left_number > right_number,
+                    Token::LessEqual(..) => equal || left_number < right_number,
+                    _ => equal || left_number > right_number,
+                }));
+            }
+
+            // Comparisons with symbols in them are left for later
+            if left_result.is_symbolic() || right_result.is_symbolic() {
+                let (left_result, right_result) = (Box::new(left_result), Box::new(right_result));
+
+                return Ok(match token {
+                    Token::Less(..) => Token::Less(left_result, right_result),
+                    Token::Greater(..) => Token::Greater(left_result, right_result),
+                    Token::LessEqual(..) => Token::LessEqual(left_result, right_result),
+                    _ => Token::GreaterEqual(left_result, right_result),
+                });
+            }
+
+            bail!(
+                "Only real numbers can be compared, not {} and {}!",
+                left_result.to_string(session),
+                right_result.to_string(session)
+            );
+        }
+
+        Token::And(left, right) | Token::Or(left, right) => {
+            let left_result = simplify(left, session)?;
+
+            // The right side only gets evaluated if it can change the result
+            match (token, truth(&left_result, session)?) {
+                (Token::And(..), Some(false)) => return Ok(Token::Boolean(false)),
+                (Token::Or(..), Some(true)) => return Ok(Token::Boolean(true)),
+                _ => {}
+            }
+
+            let right_result = simplify(right, session)?;
+
+            // A known right side decides the result too if it's the deciding value, like x and false
+            match (
+                token,
+                truth(&left_result, session)?,
+                truth(&right_result, session)?,
+            ) {
+                (Token::And(..), _, Some(false)) => Ok(Token::Boolean(false)),
+                (Token::Or(..), _, Some(true)) => Ok(Token::Boolean(true)),
+                (_, Some(_), Some(right_truth)) => Ok(Token::Boolean(right_truth)),
+                _ => Ok(match token {
+                    Token::And(..) => Token::And(Box::new(left_result), Box::new(right_result)),
+                    _ => Token::Or(Box::new(left_result), Box::new(right_result)),
+                }),
+            }
         }
 
-        Token::Equality(left, right) => {
+        Token::Xor(left, right) => {
             let left_result = simplify(left, session)?;
             let right_result = simplify(right, session)?;
 
-            Ok(Token::Boolean(left_result == right_result))
+            match (
+                truth(&left_result, session)?,
+                truth(&right_result, session)?,
+            ) {
+                (Some(left_truth), Some(right_truth)) => {
+                    Ok(Token::Boolean(left_truth != right_truth))
+                }
+                _ => Ok(Token::Xor(Box::new(left_result), Box::new(right_result))),
+            }
+        }
+
+        Token::Not(expression) => {
+            let result = simplify(expression, session)?;
+
+            match truth(&result, session)? {
+                Some(truth) => Ok(Token::Boolean(!truth)),
+                None => Ok(Token::Not(Box::new(result))),
+            }
         }
 
         Token::Parenthesis(expression) => simplify(expression, session),
@@ -133,6 +327,8 @@ pub fn simplify(token: &Token, session: &mut Session) -> Result<Token, Box<dyn E
         Token::Answer(uuid) => {
             if let Some(entry) = session.get_entry_from_uuid(uuid) {
                 let entry = entry.clone();
+                session.approximated |= session.is_approximate(uuid);
+
                 return simplify(entry.only_equality(), session);
             }
             bail!("Invalid entry uuid {}!", uuid);
@@ -142,16 +338,36 @@ pub fn simplify(token: &Token, session: &mut Session) -> Result<Token, Box<dyn E
 
         Token::Boolean(_truth) => Ok(token.clone()),
 
-        Token::Variable(variable) => Ok(variable.tokens.clone()),
+        Token::Variable(variable) => variable_value(variable, session),
 
         Token::Negative(expression) => {
-            let result = simplify(expression, session)?;
+            let result = boolean_as_number(simplify(expression, session)?);
 
             if let Token::Number(number) = &result {
                 return Ok(Token::Number(number.negative()));
             }
 
-            Ok(Token::Negative(Box::new(result)))
+            if let Token::Complex(complex) = &result {
+                return Ok(Token::Complex(
+                    Complex::from_real(Number::zero()).subtract(complex),
+                ));
+            }
+
+            if let Token::Matrix(_) = &result {
+                return Ok(Matrix::from_token(&result)?
+                    .scale(&Number::neg_one())
+                    .to_token());
+            }
+
+            Ok(collect_symbolic(Token::Negative(Box::new(result))))
+        }
+
+        Token::Formula(id, _expression) => {
+            let variable = Variable::new(id, token.clone());
+
+            session.vartable.store(variable.clone())?;
+
+            variable_value(&variable, session)
         }
 
         Token::Store(id, tokens) => {
@@ -159,9 +375,732 @@ pub fn simplify(token: &Token, session: &mut Session) -> Result<Token, Box<dyn E
             let variable = Variable::new(id, simplified_tokens.clone());
             session.vartable.store(variable)?;
 
+            // Remember whether the value is an approximation for when it's used later
+            session
+                .approximate_variables
+                .retain(|variable| variable != id);
+
+            if session.approximated {
+                session.approximate_variables.push(id.to_string());
+            }
+
             Ok(simplified_tokens)
         }
+
+        Token::Matrix(rows) => {
+            let mut simplified_rows = Vec::<Vec<Token>>::with_capacity(rows.len());
+
+            for row in rows {
+                let mut simplified_row = Vec::<Token>::with_capacity(row.len());
+
+                for entry in row {
+                    simplified_row.push(simplify(entry, session)?);
+                }
+
+                simplified_rows.push(simplified_row);
+            }
+
+            Ok(Token::Matrix(simplified_rows))
+        }
+
+        Token::Function(id, args) => match functions::get_builtin(id) {
+            Some(function) => function.call(args, session),
+            None => bail!("Unknown function {}!", id),
+        },
+
+        Token::Symbol(id) => {
+            if let Some(value) = session.get_binding(id) {
+                return Ok(value.clone());
+            }
+
+            // Formulas can refer to variables that didn't exist yet when they were defined
+            if !session.evaluating_formulas.is_empty() {
+                if let Ok(variable) = session.vartable.get(id) {
+                    return variable_value(&variable, session);
+                }
+            }
+
+            Ok(token.clone())
+        }
+
+        Token::Complex(_complex) => Ok(token.clone()),
+
+        Token::Constant(id) => match id.as_str() {
+            "e" => {
+                session.approximated = true;
+
+                Ok(Token::Number(Number::e(
+                    session.decimal_places + CONSTANT_GUARD_DIGITS,
+                )))
+            }
+            "pi" => {
+                session.approximated = true;
+
+                Ok(Token::Number(Number::pi(
+                    session.decimal_places + CONSTANT_GUARD_DIGITS,
+                )))
+            }
+            "i" => Ok(Token::Complex(Complex::new(Number::zero(), Number::one()))),
+            _ => bail!("Unknown constant {}!", id),
+        },
+    }
+}
+
+/// Gets the value of a variable, evaluating it if it's a formula.
+///
+/// Formulas, and the variables inside them, are looked up again in the vartable rather than trusting the copy made when the expression was parsed,
+/// so they always reflect the latest values. Returns a simple error if a formula ends up being evaluated inside itself.
+///
+/// **PRIVATE FUNCTION**
+///
+fn variable_value(variable: &Variable, session: &mut Session) -> Result<Token, Box<dyn Error>> {
+    let is_formula = |variable: &Variable| matches!(variable.tokens, Token::Formula(..));
+
+    let variable = match is_formula(variable) || !session.evaluating_formulas.is_empty() {
+        true => session
+            .vartable
+            .get(&variable.id)
+            .unwrap_or_else(|_| variable.clone()),
+        false => variable.clone(),
+    };
+
+    let expression = match &variable.tokens {
+        Token::Formula(_id, expression) => expression,
+        tokens => {
+            session.approximated |= session.approximate_variables.contains(&variable.id);
+
+            return Ok(tokens.clone());
+        }
+    };
+
+    if session.evaluating_formulas.contains(&variable.id) {
+        bail!(
+            "Variable \"{}\" is defined in terms of itself!",
+            variable.id
+        );
+    }
+
+    session.evaluating_formulas.push(variable.id.clone());
+
+    let result = simplify(expression, session);
+
+    // Finish evaluating the formula even if it failed
+    session.evaluating_formulas.pop();
+
+    result
+}
+
+/// Turns a boolean into 1 or 0 so it can be used in arithmetic, leaving anything else alone.
+///
+/// **PRIVATE FUNCTION**
+///
+fn boolean_as_number(token: Token) -> Token {
+    match token {
+        Token::Boolean(true) => Token::Number(Number::one()),
+        Token::Boolean(false) => Token::Number(Number::zero()),
+        _ => token,
+    }
+}
+
+/// Gets the truth of a simplified token, where any number other than zero is true.
+///
+/// Returns None if the token has symbols in it, and a simple error if it can't be true or false at all.
+///
+pub fn truth(token: &Token, session: &Session) -> Result<Option<bool>, Box<dyn Error>> {
+    match token {
+        Token::Boolean(truth) => Ok(Some(*truth)),
+        Token::Number(number) => Ok(Some(!number.is_zero())),
+        _ if token.is_symbolic() => Ok(None),
+        _ => bail!(
+            "Only booleans and numbers can be true or false, not {}!",
+            token.to_string(session)
+        ),
+    }
+}
+
+/// Compares two simplified tokens by value, with numbers counting as equal if they're within the session's equality tolerance.
+///
+/// Anything that isn't a number, complex number or matrix of them has to match exactly, so expressions with symbols in them that don't match
+/// may still be equal for some values of the symbols.
+///
+/// **PRIVATE FUNCTION**
+///
+fn values_equal(left: &Token, right: &Token, session: &Session) -> bool {
+    let as_complex = |token: &Token| match token {
+        Token::Number(number) => Some(Complex::from_real(number.clone())),
+        Token::Complex(complex) => Some(complex.clone()),
+        _ => None,
+    };
+
+    let left = boolean_as_number(left.clone());
+    let right = boolean_as_number(right.clone());
+
+    if let (Some(left), Some(right)) = (as_complex(&left), as_complex(&right)) {
+        return numbers_equal(&left.real, &right.real, session)
+            && numbers_equal(&left.imaginary, &right.imaginary, session);
+    }
+
+    if let (Token::Matrix(left_rows), Token::Matrix(right_rows)) = (&left, &right) {
+        return left_rows.len() == right_rows.len()
+            && left_rows
+                .iter()
+                .zip(right_rows)
+                .all(|(left_row, right_row)| {
+                    left_row.len() == right_row.len()
+                        && left_row
+                            .iter()
+                            .zip(right_row)
+                            .all(|(left, right)| values_equal(left, right, session))
+                });
+    }
+
+    left == right
+}
+
+/// Compares two numbers by value, counting them as equal if they're within the session's equality tolerance.
+///
+/// **PRIVATE FUNCTION**
+///
+fn numbers_equal(left: &Number, right: &Number, session: &Session) -> bool {
+    match &session.equality_tolerance {
+        Some(tolerance) => left.subtract(right).abs() <= *tolerance,
+        None => left == right,
+    }
+}
+
+/// Collects like terms and folds constants in an operation on simplified tokens, if there are symbols in it.
+///
+/// **PRIVATE FUNCTION**
+///
+fn collect_symbolic(token: Token) -> Token {
+    match token.is_symbolic() {
+        true => algebra::collect(&token),
+        false => token,
+    }
+}
+
+/// Performs matrix arithmetic on the simplified sides of an operation.
+///
+/// Returns None if neither side is a matrix, and a simple error if the operation makes no sense for matrices.
+///
+/// **PRIVATE FUNCTION**
+///
+fn matrix_arithmetic(
+    token: &Token,
+    left: &Token,
+    right: &Token,
+) -> Result<Option<Token>, Box<dyn Error>> {
+    if !matches!(left, Token::Matrix(_)) && !matches!(right, Token::Matrix(_)) {
+        return Ok(None);
+    }
+
+    // Complex numbers, like the results of roots, go through complex matrices instead
+    let is_complex = |side: &Token| match side {
+        Token::Complex(_) => true,
+        Token::Matrix(rows) => rows
+            .iter()
+            .flatten()
+            .any(|entry| matches!(entry, Token::Complex(_))),
+        _ => false,
+    };
+
+    if is_complex(left) || is_complex(right) {
+        return complex_matrix_arithmetic(token, left, right).map(Some);
+    }
+
+    let result = match (token, left, right) {
+        (Token::Add(..), Token::Matrix(_), Token::Matrix(_)) => {
+            Matrix::from_token(left)?.add(&Matrix::from_token(right)?)?
+        }
+        (Token::Subtract(..), Token::Matrix(_), Token::Matrix(_)) => {
+            Matrix::from_token(left)?.subtract(&Matrix::from_token(right)?)?
+        }
+        (Token::Multiply(..), Token::Matrix(_), Token::Matrix(_)) => {
+            Matrix::from_token(left)?.multiply(&Matrix::from_token(right)?)?
+        }
+        (Token::Multiply(..), Token::Matrix(_), Token::Number(scalar)) => {
+            Matrix::from_token(left)?.scale(scalar)
+        }
+        (Token::Multiply(..), Token::Number(scalar), Token::Matrix(_)) => {
+            Matrix::from_token(right)?.scale(scalar)
+        }
+        (Token::Divide(..), Token::Matrix(_), Token::Number(scalar)) => {
+            Matrix::from_token(left)?.scale(&Number::one().divide(scalar))
+        }
+        (Token::Exponent(..), Token::Matrix(_), Token::Number(exponent)) => {
+            match exponent.to_i64() {
+                Some(exponent) => Matrix::from_token(left)?.power(exponent)?,
+                None => bail!("Matrices can only be raised to whole number powers!"),
+            }
+        }
+        (Token::Add(..), _, _) | (Token::Subtract(..), _, _) => {
+            bail!("Matrices can only be added to or subtracted from other matrices!")
+        }
+        (Token::Divide(..), _, _) => bail!("Matrices can only be divided by numbers!"),
+        _ => bail!("Invalid matrix operation!"),
+    };
+
+    Ok(Some(result.to_token()))
+}
+
+/// Performs matrix arithmetic on the simplified sides of an operation where a matrix entry or the other side is a complex number.
+///
+/// Returns a simple error if the operation makes no sense for matrices.
+///
+/// **PRIVATE FUNCTION**
+///
+fn complex_matrix_arithmetic(
+    token: &Token,
+    left: &Token,
+    right: &Token,
+) -> Result<Token, Box<dyn Error>> {
+    let scalar = |side: &Token| match side {
+        Token::Number(number) => Some(Complex::from_real(number.clone())),
+        Token::Complex(complex) => Some(complex.clone()),
+        _ => None,
+    };
+
+    let result = match (token, left, right) {
+        (Token::Add(..), Token::Matrix(_), Token::Matrix(_)) => {
+            ComplexMatrix::from_token(left)?.add(&ComplexMatrix::from_token(right)?)?
+        }
+        (Token::Subtract(..), Token::Matrix(_), Token::Matrix(_)) => {
+            ComplexMatrix::from_token(left)?.subtract(&ComplexMatrix::from_token(right)?)?
+        }
+        (Token::Multiply(..), Token::Matrix(_), Token::Matrix(_)) => {
+            ComplexMatrix::from_token(left)?.multiply(&ComplexMatrix::from_token(right)?)?
+        }
+        (Token::Multiply(..), Token::Matrix(_), _) if scalar(right).is_some() => {
+            ComplexMatrix::from_token(left)?.scale(&scalar(right).unwrap())
+        }
+        (Token::Multiply(..), _, Token::Matrix(_)) if scalar(left).is_some() => {
+            ComplexMatrix::from_token(right)?.scale(&scalar(left).unwrap())
+        }
+        (Token::Divide(..), Token::Matrix(_), _) if scalar(right).is_some() => {
+            let one = Complex::from_real(Number::one());
+
+            ComplexMatrix::from_token(left)?.scale(&one.divide(&scalar(right).unwrap()))
+        }
+        (Token::Exponent(..), Token::Matrix(_), Token::Number(exponent)) => {
+            match exponent.to_i64() {
+                Some(exponent) => ComplexMatrix::from_token(left)?.power(exponent)?,
+                None => bail!("Matrices can only be raised to whole number powers!"),
+            }
+        }
+        (Token::Add(..), _, _) | (Token::Subtract(..), _, _) => {
+            bail!("Matrices can only be added to or subtracted from other matrices!")
+        }
+        (Token::Divide(..), _, _) => bail!("Matrices can only be divided by numbers!"),
+        _ => bail!("Invalid matrix operation!"),
+    };
+
+    Ok(result.to_token())
+}
+
+/// Performs complex arithmetic on the simplified sides of an addition, subtraction, multiplication or division.
+///
+/// Returns None unless one side is a complex number and the other is a number or complex number, or when dividing by zero. Results
+/// without an imaginary part come back as plain numbers.
+///
+/// **PRIVATE FUNCTION**
+///
+fn complex_arithmetic(token: &Token, left: &Token, right: &Token) -> Option<Token> {
+    let (left, right) = match (left, right) {
+        (Token::Complex(left), Token::Complex(right)) => (left.clone(), right.clone()),
+        (Token::Complex(left), Token::Number(right)) => {
+            (left.clone(), Complex::from_real(right.clone()))
+        }
+        (Token::Number(left), Token::Complex(right)) => {
+            (Complex::from_real(left.clone()), right.clone())
+        }
+        _ => return None,
+    };
+
+    let result = match token {
+        Token::Add(..) => left.add(&right),
+        Token::Subtract(..) => left.subtract(&right),
+        Token::Multiply(..) => left.multiply(&right),
+        Token::Divide(..) if !right.is_zero() => left.divide(&right),
+        _ => return None,
+    };
+
+    Some(match result.is_real() {
+        true => Token::Number(result.real),
+        false => Token::Complex(result),
+    })
+}
+
+/// Numerically solves an equation for a single symbol, starting the search from a guess.
+///
+/// The solver first looks for a sign change on either side of the guess, then closes in on the root with the Illinois variant of the
+/// secant method. If there's no sign change, like with x^2 = 0, it falls back to the plain secant method. Roots that round to an exact
+/// solution are returned exactly, otherwise the root is accurate to the session's decimal places.
+///
+/// The function is evaluated at more decimal places than the session shows, and a point only counts as a root if the function there is
+/// tiny compared to its size around it, so exp(x) = 0 doesn't find a root just because exp(-32) rounds to zero.
+///
+/// An expression without an equals sign is solved for when it equals zero. Returns a simple error if no root is found.
+///
+pub fn find_root(
+    equation: &Token,
+    symbol: &str,
+    guess: &Number,
+    session: &mut Session,
+) -> Result<Number, Box<dyn Error>> {
+    let decimal_places = session.decimal_places;
+    let approximated = session.approximated;
+
+    session.decimal_places = decimal_places + SOLVE_GUARD_DIGITS;
+
+    let root = search_root(equation, symbol, guess, decimal_places, session);
+
+    // The root is only exact if the equation holds there exactly, without any approximations going into checking it
+    if let Ok(root) = &root {
+        session.approximated = false;
+
+        let difference = match equation {
+            Token::Equality(left, right) => Token::Subtract(left.clone(), right.clone()),
+            _ => equation.clone(),
+        };
+        let difference = difference.substitute(symbol, &Token::Number(root.clone()));
+        let exact =
+            matches!(simplify(&difference, session), Ok(Token::Number(value)) if value.is_zero());
+
+        session.approximated = approximated || !exact || session.approximated;
+    }
+
+    // Put the session's precision back even if the search failed
+    session.decimal_places = decimal_places;
+
+    root
+}
+
+/// Extra decimal places constants are worked out to, so they stay accurate after being multiplied up.
+///
+/// **NOT PUBLIC.**
+///
+const CONSTANT_GUARD_DIGITS: u32 = 10;
+
+/// Extra decimal places carried while solving, so rounding doesn't creep into the displayed digits.
+///
+/// **NOT PUBLIC.**
+///
+const SOLVE_GUARD_DIGITS: u32 = 10;
+
+/// How many times the search for a sign change doubles its distance from the guess before giving up.
+///
+/// **NOT PUBLIC.**
+///
+const MAX_BRACKET_STEPS: usize = 40;
+
+/// Number of digits in the largest distance from zero the solver looks for roots at, so it gives up instead of wandering off forever.
+///
+/// **NOT PUBLIC.**
+///
+const MAX_SOLVE_MAGNITUDE_DIGITS: i64 = 15;
+
+/// Maximum number of iterations when closing in on a root.
+///
+/// **NOT PUBLIC.**
+///
+const MAX_SOLVE_ITERATIONS: usize = 200;
+
+/// Searches for a root of an equation, with the session already set to the working precision.
+///
+/// **PRIVATE FUNCTION**
+///
+fn search_root(
+    equation: &Token,
+    symbol: &str,
+    guess: &Number,
+    decimal_places: u32,
+    session: &mut Session,
+) -> Result<Number, Box<dyn Error>> {
+    let function = match equation {
+        Token::Equality(left, right) => Token::Subtract(left.clone(), right.clone()),
+        _ => equation.clone(),
+    };
+
+    let working_places = session.decimal_places;
+    let tolerance = Number::from_i64(10).exponent(
+        &Number::from_i64(-(decimal_places as i64) - 2),
+        decimal_places,
+    );
+    let max_magnitude =
+        Number::from_i64(10).exponent(&Number::from_i64(MAX_SOLVE_MAGNITUDE_DIGITS), 0);
+
+    let mut evaluate = |x: &Number| -> Result<Option<Number>, Box<dyn Error>> {
+        if x.abs() > max_magnitude {
+            return Ok(None);
+        }
+
+        // Points where evaluating fails or the value isn't a real number, like dividing by zero or the square root of a negative, are treated as
+        // points where the function isn't defined
+        match simplify(
+            &function.substitute(symbol, &Token::Number(x.clone())),
+            session,
+        ) {
+            Ok(Token::Number(number)) if number.is_finite() => {
+                Ok(Some(number.approximate(working_places)))
+            }
+            Ok(value) if value.is_symbolic() => {
+                bail!("Equations can only be solved if both sides are numbers!")
+            }
+            Ok(_) | Err(_) => Ok(None),
+        }
+    };
+
+    let root = match bracket_root(&mut evaluate, guess, &tolerance)? {
+        Some(BracketSearch::Exact(root)) => return Ok(root),
+        Some(BracketSearch::Bracket(bracket)) => {
+            illinois(&mut evaluate, bracket, &tolerance, working_places)?
+        }
+        None => secant(&mut evaluate, guess, &tolerance, working_places)?,
+    };
+
+    let root = match root {
+        Some(root) => root,
+        None => bail!("No root found for {}!", symbol),
+    };
+
+    match evaluate(&root)? {
+        Some(value) if is_root(&mut evaluate, &root, &value, &tolerance)? => {}
+        _ => bail!("No root found for {}!", symbol),
+    }
+
+    // If rounding the root gives something that is exactly a root, it's a much nicer answer
+    for rounded in [root.round(0), root.round(decimal_places)] {
+        if let Some(value) = evaluate(&rounded)? {
+            if value.is_zero() && is_root(&mut evaluate, &rounded, &value, &tolerance)? {
+                return Ok(rounded);
+            }
+        }
+    }
+
+    Ok(root)
+}
+
+/// Checks that the value of the function at a point is small compared to its values one either side, rather than just small.
+///
+/// A function that is flat at zero around the point, like exp(x) far below zero once rounded, has no root there.
+///
+/// **PRIVATE FUNCTION**
+///
+fn is_root<F>(
+    evaluate: &mut F,
+    x: &Number,
+    value: &Number,
+    tolerance: &Number,
+) -> Result<bool, Box<dyn Error>>
+where
+    F: FnMut(&Number) -> Result<Option<Number>, Box<dyn Error>>,
+{
+    let mut scale = Number::zero();
+    let mut neighbours = 0;
+
+    for neighbour in [x.add(&Number::one()), x.subtract(&Number::one())] {
+        if let Some(neighbour_value) = evaluate(&neighbour)? {
+            scale = scale.add(&neighbour_value.abs());
+            neighbours += 1;
+        }
+    }
+
+    if neighbours == 0 || scale.is_zero() {
+        return Ok(false);
+    }
+
+    let scale = scale.divide(&Number::from_i64(neighbours));
+
+    Ok(value.abs() <= tolerance.multiply(&scale))
+}
+
+/// Outcome of searching for a sign change.
+///
+/// **NOT PUBLIC.**
+///
+enum BracketSearch {
+    /// Landed right on a root.
+    Exact(Number),
+    /// Two points with their values, which have opposite signs.
+    Bracket([(Number, Number); 2]),
+}
+
+/// Searches outwards from the guess, doubling the distance each step, for an interval where the function changes sign.
+///
+/// **PRIVATE FUNCTION**
+///
+fn bracket_root<F>(
+    evaluate: &mut F,
+    guess: &Number,
+    tolerance: &Number,
+) -> Result<Option<BracketSearch>, Box<dyn Error>>
+where
+    F: FnMut(&Number) -> Result<Option<Number>, Box<dyn Error>>,
+{
+    let start = match evaluate(guess)? {
+        Some(value) if value.is_zero() && is_root(evaluate, guess, &value, tolerance)? => {
+            return Ok(Some(BracketSearch::Exact(guess.clone())))
+        }
+        Some(value) => Some((guess.clone(), value)),
+        None => None,
+    };
+
+    // The last point checked on the right and on the left of the guess
+    let mut previous = [start.clone(), start];
+    let mut step = Number::one();
+
+    for _ in 0..MAX_BRACKET_STEPS {
+        for (side, direction) in [Number::one(), Number::neg_one()].iter().enumerate() {
+            let x = guess.add(&step.multiply(direction));
+
+            let value = match evaluate(&x)? {
+                Some(value) if value.is_zero() && is_root(evaluate, &x, &value, tolerance)? => {
+                    return Ok(Some(BracketSearch::Exact(x)))
+                }
+                Some(value) => value,
+                None => {
+                    previous[side] = None;
+                    continue;
+                }
+            };
+
+            if let Some((previous_x, previous_value)) = &previous[side] {
+                if previous_value.is_negative() != value.is_negative() {
+                    return Ok(Some(BracketSearch::Bracket([
+                        (previous_x.clone(), previous_value.clone()),
+                        (x, value),
+                    ])));
+                }
+            }
+
+            previous[side] = Some((x, value));
+        }
+
+        step = step.multiply(&Number::from_i64(2));
+    }
+
+    Ok(None)
+}
+
+/// Closes in on a root inside a sign change with the Illinois method, a variant of the secant method that always keeps the root bracketed.
+///
+/// Returns None if the sign change turns out to be a discontinuity rather than a root, like 1/x around zero.
+///
+/// **PRIVATE FUNCTION**
+///
+fn illinois<F>(
+    evaluate: &mut F,
+    bracket: [(Number, Number); 2],
+    tolerance: &Number,
+    working_places: u32,
+) -> Result<Option<Number>, Box<dyn Error>>
+where
+    F: FnMut(&Number) -> Result<Option<Number>, Box<dyn Error>>,
+{
+    let [(mut a, mut fa), (mut b, mut fb)] = bracket;
+
+    // A real root can't leave the function further from zero than where it started
+    let limit = match fa.abs() < fb.abs() {
+        true => fb.abs(),
+        false => fa.abs(),
+    };
+
+    for _ in 0..MAX_SOLVE_ITERATIONS {
+        if b.subtract(&a).abs() < *tolerance {
+            return Ok(match fb.abs() <= limit {
+                true => Some(b),
+                false => None,
+            });
+        }
+
+        let mut c = b
+            .subtract(&fb.multiply(&b.subtract(&a)).divide(&fb.subtract(&fa)))
+            .approximate(working_places);
+
+        // Rounding can push the secant step outside of the bracket, so bisect instead
+        let (low, high) = match a < b {
+            true => (&a, &b),
+            false => (&b, &a),
+        };
+
+        if c <= *low || c >= *high {
+            c = a.add(&b).divide(&Number::from_i64(2));
+        }
+
+        let fc = match evaluate(&c)? {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        if fc.is_zero() {
+            return Ok(Some(c));
+        }
+
+        match fc.is_negative() != fb.is_negative() {
+            true => {
+                a = b;
+                fa = fb;
+            }
+            // Halving the value at the stale end keeps the method from crawling in from one side
+            false => fa = fa.divide(&Number::from_i64(2)),
+        }
+
+        b = c;
+        fb = fc;
     }
+
+    Ok(None)
+}
+
+/// Finds a root with the secant method, for when there's no sign change to bracket.
+///
+/// **PRIVATE FUNCTION**
+///
+fn secant<F>(
+    evaluate: &mut F,
+    guess: &Number,
+    tolerance: &Number,
+    working_places: u32,
+) -> Result<Option<Number>, Box<dyn Error>>
+where
+    F: FnMut(&Number) -> Result<Option<Number>, Box<dyn Error>>,
+{
+    let mut x0 = guess.clone();
+    let mut x1 = guess.add(&Number::one());
+
+    let (mut f0, mut f1) = match (evaluate(&x0)?, evaluate(&x1)?) {
+        (Some(f0), Some(f1)) => (f0, f1),
+        _ => return Ok(None),
+    };
+
+    for _ in 0..MAX_SOLVE_ITERATIONS {
+        if f1 == f0 {
+            return Ok(None);
+        }
+
+        let x2 = x1
+            .subtract(&f1.multiply(&x1.subtract(&x0)).divide(&f1.subtract(&f0)))
+            .approximate(working_places);
+
+        let f2 = match evaluate(&x2)? {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        if x2.subtract(&x1).abs() < *tolerance {
+            return Ok(match f2.abs() < *tolerance {
+                true => Some(x2),
+                false => None,
+            });
+        }
+
+        x0 = x1;
+        f0 = f1;
+        x1 = x2;
+        f1 = f2;
+    }
+
+    Ok(None)
 }
 
 #[cfg(test)]
@@ -287,6 +1226,361 @@ mod tests {
         }
     }
 
+    // Test comparisons and logical operators
+    #[test]
+    fn test_op_engine_logic() {
+        let mut session = Session::_new_test().unwrap();
+
+        let expressions = [
+            ("1 < 2", "true"),
+            ("2 <= 2", "true"),
+            ("2 > 3", "false"),
+            ("3 >= 4", "false"),
+            ("1 != 2", "true"),
+            ("1/2 = 0.5", "true"),
+            ("[1, 2] = [1, 2]", "true"),
+            ("1 < 2 and 3 > 4", "false"),
+            ("1 < 2 or 1 > 2", "true"),
+            ("true xor true", "false"),
+            ("not 1 = 2", "true"),
+            ("true + true", "2"),
+            ("(3 > 2) * 5", "5"),
+            ("x < 2", "x < 2"),
+            ("x = 1", "x = 1"),
+            ("x != 1", "x != 1"),
+            ("x + x = 2x", "true"),
+            ("x > 1 and false", "false"),
+            ("x > 1 or 2 > 1", "true"),
+        ];
+
+        for (expression, expected) in expressions {
+            let tokenized_expression = parser::parse_str(expression, &mut session).unwrap();
+            let result = simplify(&tokenized_expression, &mut session).unwrap();
+
+            assert_eq!(result.to_string(&session), expected, "{}", expression);
+        }
+
+        // Inexact results are only equal with a tolerance
+        let tokenized_expression = parser::parse_str("sqrt(2)^2 = 2", &mut session).unwrap();
+
+        assert_eq!(
+            simplify(&tokenized_expression, &mut session).unwrap(),
+            Token::Boolean(false)
+        );
+
+        session.equality_tolerance = Some(Number::from_str("0.000001").unwrap());
+
+        assert_eq!(
+            simplify(&tokenized_expression, &mut session).unwrap(),
+            Token::Boolean(true)
+        );
+
+        // Matrices can't be compared by size
+        let tokenized_expression = parser::parse_str("[1, 2] < 3", &mut session).unwrap();
+
+        simplify(&tokenized_expression, &mut session).unwrap_err();
+    }
+
+    // Test conditionals
+    #[test]
+    fn test_op_engine_conditional() {
+        let mut session = Session::_new_test().unwrap();
+
+        let expressions = [
+            ("if(2 < 1, 3, 4)", "4"),
+            ("if(0, 1, 0, 2, 3)", "3"),
+            ("if(x > 0, x, -x)", "if(x > 0, x, -x)"),
+            // The branch not taken is never evaluated, so ln(0) doesn't fail
+            ("if(1 < 2, 3, ln(0))", "3"),
+            (
+                "if(30000 < 10000, 0, 30000 < 40000, 0.2(30000 - 10000), 0.4 * 30000)",
+                "4000",
+            ),
+        ];
+
+        for (expression, expected) in expressions {
+            let tokenized_expression = parser::parse_str(expression, &mut session).unwrap();
+            let result = simplify(&tokenized_expression, &mut session).unwrap();
+
+            assert_eq!(result.to_string(&session), expected, "{}", expression);
+        }
+
+        // Every condition needs a value
+        let tokenized_expression = parser::parse_str("if(1, 2, 3, 4)", &mut session).unwrap();
+
+        simplify(&tokenized_expression, &mut session).unwrap_err();
+    }
+
+    // Test formula variables
+    #[test]
+    fn test_op_engine_formula() {
+        let mut session = Session::_new_test().unwrap();
+
+        let expressions = [
+            ("5 -> a", "5"),
+            ("b := a * 2", "10"),
+            ("7 -> a", "7"),
+            ("b + 1", "15"),
+            ("c := d + b", "d + 14"),
+            ("3 -> d", "3"),
+            ("c", "17"),
+        ];
+
+        for (expression, expected) in expressions {
+            let tokenized_expression = parser::parse_str(expression, &mut session).unwrap();
+            let result = simplify(&tokenized_expression, &mut session).unwrap();
+
+            assert_eq!(result.to_string(&session), expected, "{}", expression);
+        }
+
+        // Formulas can't be defined in terms of themselves
+        let tokenized_expression = parser::parse_str("a := c", &mut session).unwrap();
+
+        simplify(&tokenized_expression, &mut session).unwrap_err();
+    }
+
+    // Test matrix arithmetic
+    #[test]
+    fn test_op_engine_matrix() {
+        let mut session = Session::_new_test().unwrap();
+
+        let expressions = [
+            ("[[1,2],[3,4]] + [[4,3],[2,1]]", "[[5, 5], [5, 5]]"),
+            ("[[1,2],[3,4]] - [[1,2],[3,4]]", "[[0, 0], [0, 0]]"),
+            ("2 * [[1,2],[3,4]]", "[[2, 4], [6, 8]]"),
+            ("[[1,2],[3,4]] * [[5,6],[7,8]]", "[[19, 22], [43, 50]]"),
+            ("transpose([[1,2],[3,4]])", "[[1, 3], [2, 4]]"),
+            ("det([[1,2],[3,4]])", "-2"),
+            ("inv([[2,0],[0,4]])", "[[0.5, 0], [0, 0.25]]"),
+            ("[[2,0],[0,4]]^-1", "[[0.5, 0], [0, 0.25]]"),
+            ("identity(2)", "[[1, 0], [0, 1]]"),
+            ("solve([[1,1],[1,-1]], [10,2])", "[[6, 4]]"),
+            ("roots(1, 0, 1) * 2", "[[-2i, 2i]]"),
+            ("roots(1, 0, 1) + [1, 1]", "[[1 - i, 1 + i]]"),
+            ("[[1, i], [0, 1]]^-1", "[[1, -i], [0, 1]]"),
+        ];
+
+        for (expression, expected) in expressions {
+            let tokenized_expression = parser::parse_str(expression, &mut session).unwrap();
+            let result = simplify(&tokenized_expression, &mut session).unwrap();
+
+            assert_eq!(result.to_string(&session), expected);
+        }
+
+        // Mismatched sizes and singular matrices should error instead of panicking
+        for expression in [
+            "[[1,2]] + [[1,2],[3,4]]",
+            "[1,2] + 1",
+            "[i] + 1",
+            "inv([[1,2],[2,4]])",
+            "solve([[1,2],[2,4]], [1,3])",
+        ] {
+            let tokenized_expression = parser::parse_str(expression, &mut session).unwrap();
+
+            simplify(&tokenized_expression, &mut session).unwrap_err();
+        }
+    }
+
+    // Test operators of the same precedence, and implicit multiplication
+    #[test]
+    fn test_op_engine_order() {
+        let mut session = Session::_new_test().unwrap();
+
+        let expressions = [
+            ("1-2+3", "2"),
+            ("1-2-3", "-4"),
+            ("8/4/2", "1"),
+            ("2^3^2", "512"),
+            ("3(2+1)", "9"),
+            ("(1+1)(2+2)", "8"),
+        ];
+
+        for (expression, expected) in expressions {
+            let tokenized_expression = parser::parse_str(expression, &mut session).unwrap();
+            let result = simplify(&tokenized_expression, &mut session).unwrap();
+
+            assert_eq!(result.to_string(&session), expected);
+        }
+    }
+
+    // Test polynomial roots
+    #[test]
+    fn test_op_engine_roots() {
+        let mut session = Session::_new_test().unwrap();
+
+        let expressions = [
+            ("roots(1, -3, 2)", "[[1, 2]]"),
+            ("roots(x^2 - 3x + 2, x)", "[[1, 2]]"),
+            ("roots(x^2 + 2x + 5, x)", "[[-1 - 2i, -1 + 2i]]"),
+            ("roots((x-1)^2 (x+2), x)", "[[-2, 1, 1]]"),
+            ("roots(1, 0, 0, 0, -1)", "[[-1, 1, -i, i]]"),
+        ];
+
+        for (expression, expected) in expressions {
+            let tokenized_expression = parser::parse_str(expression, &mut session).unwrap();
+            let result = simplify(&tokenized_expression, &mut session).unwrap();
+
+            assert_eq!(result.to_string(&session), expected);
+        }
+
+        // Constants have no roots, and x/x isn't a polynomial
+        for expression in ["roots(5)", "roots(x/x, x)"] {
+            let tokenized_expression = parser::parse_str(expression, &mut session).unwrap();
+
+            simplify(&tokenized_expression, &mut session).unwrap_err();
+        }
+    }
+
+    // Test numerically solving equations
+    #[test]
+    fn test_op_engine_solve() {
+        let mut session = Session::_new_test().unwrap();
+
+        let expressions = [
+            ("solve(x^3 + x = 10, x)", "2"),
+            ("solve(x^2 = 2, x)", "1.414213..."),
+            ("solve(x^2 = 2, x, -5)", "-1.414213..."),
+            ("solve(x^2 = 0, x)", "0"),
+            ("solve(3x - 1, x)", "0.333333..."),
+            ("solve(sqrt(x) = 0, x)", "0"),
+            ("solve(exp(x) = 5, x)", "1.609437..."),
+        ];
+
+        for (expression, expected) in expressions {
+            let tokenized_expression = parser::parse_str(expression, &mut session).unwrap();
+            let result = simplify(&tokenized_expression, &mut session).unwrap();
+
+            assert_eq!(result.to_string(&session), expected);
+        }
+
+        // None of these have a real root, 1/x only changes sign across a discontinuity and exp(x) only rounds to zero
+        for expression in [
+            "solve(x^2 = -1, x)",
+            "solve(1/x = 0, x)",
+            "solve(sin(x) = 2, x)",
+            "solve(exp(x) = 0, x)",
+        ] {
+            let tokenized_expression = parser::parse_str(expression, &mut session).unwrap();
+            let error = simplify(&tokenized_expression, &mut session).unwrap_err();
+
+            assert_eq!(error.to_string(), "No root found for x!");
+        }
+
+        // Linear systems still work when b is a stored variable
+        for expression in ["[[1,1],[1,-1]] -> A", "[10,2] -> b"] {
+            let tokenized_expression = parser::parse_str(expression, &mut session).unwrap();
+            simplify(&tokenized_expression, &mut session).unwrap();
+        }
+
+        let tokenized_expression = parser::parse_str("solve(A, b)", &mut session).unwrap();
+        let result = simplify(&tokenized_expression, &mut session).unwrap();
+
+        assert_eq!(result.to_string(&session), "[[6, 4]]");
+    }
+
+    // Test symbolic simplification
+    #[test]
+    fn test_op_engine_symbolic() {
+        let mut session = Session::_new_test().unwrap();
+
+        let expressions = [
+            ("2x + 3x", "5x"),
+            ("2x + 3x = 5x", "true"),
+            ("x/x + 1", "2"),
+            ("2(x + 1) - 2", "2x"),
+            ("x^0", "1"),
+            ("x/0", "x / 0"),
+            ("2x/(x - x) + 1", "( 2x / 0 ) + 1"),
+            ("expand((x+1)(x-1)) + 1", "x^2"),
+            ("factor(x^2 - 3x + 2)", "( x - 1 ) * ( x - 2 )"),
+        ];
+
+        for (expression, expected) in expressions {
+            let tokenized_expression = parser::parse_str(expression, &mut session).unwrap();
+            let result = simplify(&tokenized_expression, &mut session).unwrap();
+
+            assert_eq!(result.to_string(&session), expected);
+        }
+    }
+
+    // Test elementary functions and constants
+    #[test]
+    fn test_op_engine_elementary() {
+        let mut session = Session::_new_test().unwrap();
+
+        let expressions = [
+            ("sin(pi/6)", "0.5"),
+            ("ln(e^2)", "2"),
+            (
+                "e^100",
+                "26881171418161354484126255515800135873611118.773741...",
+            ),
+            ("sqrt(16)", "4"),
+            ("sqrt(-4)", "2i"),
+            ("1 - sqrt(-4)/4", "1 - 0.5i"),
+            ("-sqrt(-1) * sqrt(-9)", "3"),
+            ("3 / (1 + sqrt(-1))", "1.5 - 1.5i"),
+            ("(1 + sqrt(-1))^2", "2i"),
+            ("2i * 2i", "-4"),
+            ("(1 - i)^-2", "0.5i"),
+            ("(2i)^0.5", "( 2i )^0.5"),
+            ("sqrt(2)", "1.414213..."),
+            ("2cos(x) + cos(x)", "3 * cos(x)"),
+        ];
+
+        for (expression, expected) in expressions {
+            let tokenized_expression = parser::parse_str(expression, &mut session).unwrap();
+            let result = simplify(&tokenized_expression, &mut session).unwrap();
+
+            assert_eq!(result.to_string(&session), expected);
+        }
+
+        // Out of the domain, too big to write out, and needing too many digits of pi
+        for expression in ["ln(0)", "exp(10^7)", "e^(10^7)", "sin(10^200)"] {
+            let tokenized_expression = parser::parse_str(expression, &mut session).unwrap();
+
+            simplify(&tokenized_expression, &mut session).unwrap_err();
+        }
+    }
+
+    // Test sums and products with a bound index
+    #[test]
+    fn test_op_engine_series() {
+        let mut session = Session::_new_test().unwrap();
+
+        let expressions = [
+            ("sum(i, 1, 100, i^2)", "338350"),
+            ("prod(k, 1, 10, k)", "3628800"),
+            ("prod(k, 1, 0, k)", "1"),
+            ("sum(i, 1, 3, x^i)", "x^3 + x^2 + x"),
+            ("sum(i, 1, 3, sum(j, 1, i, j))", "10"),
+            ("sum(x, 1, 3, diff(x^2, x))", "12"),
+            ("prod(k, 1, 3, diff(x^k, x))", "6x^3"),
+        ];
+
+        for (expression, expected) in expressions {
+            let tokenized_expression = parser::parse_str(expression, &mut session).unwrap();
+            let result = simplify(&tokenized_expression, &mut session).unwrap();
+
+            assert_eq!(result.to_string(&session), expected);
+        }
+
+        // Rational terms add up exactly
+        let tokenized_expression = parser::parse_str("sum(i, 1, 3, 1/i)", &mut session).unwrap();
+
+        assert_eq!(
+            simplify(&tokenized_expression, &mut session).unwrap(),
+            Token::Number(Number::from_i64(11).divide(&Number::from_i64(6)))
+        );
+
+        // The index is only bound inside the sum
+        assert!(session.local_bindings.is_empty());
+
+        let tokenized_expression = parser::parse_str("sum(i, 1, n, i)", &mut session).unwrap();
+
+        simplify(&tokenized_expression, &mut session).unwrap_err();
+    }
+
     // Test storing
     #[test]
     fn test_op_engine_store_retrieve() {
@@ -18,7 +18,11 @@
 // ApeCrunch(in a file named COPYING).
 // If not, see <https://www.gnu.org/licenses/>.
 
+use crate::complex::Complex;
+use crate::functions;
+use crate::number::ExactFraction;
 use crate::number::Number;
+use crate::session::HistoryEntry;
 use crate::session::Session;
 use crate::variable::Variable;
 use lazy_static::*;
@@ -58,10 +62,140 @@ pub enum Token {
     Variable(Box<Variable>),
     /// Negative token, parsed from any negative sign
     Negative(Box<Token>),
-    /// Boolean token, not currently parsed but can be returned from simplify functions.
+    /// Boolean token, parsed from "true" or "false" and returned from comparisons.
     Boolean(bool),
     /// Store token, used to store values into variables. First string is the id of the variable, second are the tokens to be stored in the variable
     Store(String, Box<Token>),
+    /// Matrix token, parsed from "[[a, b], [c, d]]". Holds a vector of rows, "[a, b]" parses into a single row
+    Matrix(Vec<Vec<Token>>),
+    /// Function token, parsed from an identifier followed by arguments in parenthesis. Holds the id of the function and the arguments
+    Function(String, Vec<Token>),
+    /// Symbol token, an identifier with no value, either bound by a function like the x in roots(x^2 - 1, x) or not stored as a variable
+    Symbol(String),
+    /// Complex token, not currently parsed but can be returned from simplify functions, such as the complex roots of a polynomial
+    Complex(Complex),
+    /// Constant token, parsed from the name of a built-in constant like pi or e when there's no variable by that name
+    Constant(String),
+    /// Less than token, parsed from "<".
+    Less(Box<Token>, Box<Token>),
+    /// Greater than token, parsed from ">".
+    Greater(Box<Token>, Box<Token>),
+    /// Less than or equal token, parsed from "<=".
+    LessEqual(Box<Token>, Box<Token>),
+    /// Greater than or equal token, parsed from ">=".
+    GreaterEqual(Box<Token>, Box<Token>),
+    /// Not equal token, parsed from "!=".
+    NotEqual(Box<Token>, Box<Token>),
+    /// And token, parsed from "and".
+    And(Box<Token>, Box<Token>),
+    /// Or token, parsed from "or".
+    Or(Box<Token>, Box<Token>),
+    /// Exclusive or token, parsed from "xor".
+    Xor(Box<Token>, Box<Token>),
+    /// Not token, parsed from "not".
+    Not(Box<Token>),
+    /// Formula token, parsed from "id := expression". Stored unevaluated in a variable, so the variable is evaluated again every time it's used
+    Formula(String, Box<Token>),
+}
+
+/// Outcome of evaluating a single statement, laid out for machine readable formats like JSON.
+///
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct EvaluationResult {
+    /// Statement as it was entered.
+    pub input: String,
+    /// Result written so it parses back into the same tokens, None if there was an error.
+    pub canonical: Option<String>,
+    /// Exact fraction of the result, None if the result isn't a finite number or there was an error.
+    pub fraction: Option<ExactFraction>,
+    /// Result rendered with the session's decimal places, None if there was an error.
+    pub decimal: Option<String>,
+    /// Whether the result is exact, with no approximation like a root that doesn't come out even or a transcendental function going into
+    /// it. None if there was an error.
+    pub exact: Option<bool>,
+    /// Whether the decimal rendering shows the whole result, without any digits cut off. None if there was an error.
+    pub complete: Option<bool>,
+    /// Error the statement failed with, if it failed.
+    pub error: Option<EvaluationError>,
+}
+
+/// Error a statement failed with, laid out for machine readable formats like JSON.
+///
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct EvaluationError {
+    /// Stage the statement failed at.
+    pub code: EvaluationErrorCode,
+    /// Error message, the same one the TUI shows.
+    pub message: String,
+}
+
+/// Stage a statement failed at.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvaluationErrorCode {
+    /// The statement couldn't be parsed, like "2 +".
+    ParseError,
+    /// The statement parsed but couldn't be evaluated, like multiplying matrices of the wrong sizes.
+    EvaluationError,
+}
+
+impl EvaluationResult {
+    /// Creates a result from the entry the statement added.
+    ///
+    pub fn new(input: &str, entry: &HistoryEntry, session: &Session) -> Self {
+        let result = entry.only_equality();
+
+        let decimal = result.to_string(session);
+
+        Self {
+            input: input.trim().to_string(),
+            canonical: Some(result.to_source_string(session)),
+            fraction: match result {
+                Token::Number(number) => number.to_exact_fraction(),
+                _ => None,
+            },
+            exact: Some(!session.is_approximate(&entry.entry_uuid)),
+            complete: Some(is_exact_to(result, session.decimal_places)),
+            decimal: Some(decimal),
+            error: None,
+        }
+    }
+
+    /// Creates a result for a statement that failed.
+    ///
+    pub fn from_error(input: &str, code: EvaluationErrorCode, error: &dyn Error) -> Self {
+        Self {
+            input: input.trim().to_string(),
+            canonical: None,
+            fraction: None,
+            decimal: None,
+            exact: None,
+            complete: None,
+            error: Some(EvaluationError {
+                code,
+                message: error.to_string(),
+            }),
+        }
+    }
+}
+
+/// Returns true if every number in the token is finite and rendered without losing any decimal places.
+///
+/// **PRIVATE FUNCTION**
+///
+fn is_exact_to(token: &Token, decimal_places: u32) -> bool {
+    match token {
+        Token::Number(number) => number.is_exact_to(decimal_places),
+        Token::Complex(complex) => {
+            complex.real.is_exact_to(decimal_places)
+                && complex.imaginary.is_exact_to(decimal_places)
+        }
+        _ => token
+            .children()
+            .iter()
+            .all(|child| is_exact_to(child, decimal_places)),
+    }
 }
 
 impl Token {
@@ -69,21 +203,47 @@ impl Token {
     ///
     pub fn to_string(&self, session: &Session) -> String {
         match self {
+            // Whole number powers can be written as superscripts, like x²
+            Token::Exponent(left, right)
+                if session.unicode_output
+                    && matches!(&**right, Token::Number(power) if power.is_integer() && !power.is_negative()) =>
+            {
+                let power: String = right
+                    .to_string(session)
+                    .chars()
+                    .map(|digit| SUPERSCRIPT_DIGITS[digit.to_digit(10).unwrap() as usize]) // Whole numbers only have digits
+                    .collect();
+
+                format!("{}{}", left.to_string(session), power)
+            }
             Token::Exponent(left, right) => {
                 format!("{}^{}", left.to_string(session), right.to_string(session))
             }
-            Token::Multiply(left, right) => {
-                format!("{} * {}", left.to_string(session), right.to_string(session))
-            }
-            Token::Divide(left, right) => {
-                format!("{} / {}", left.to_string(session), right.to_string(session))
+            // A number times a symbol is written the short way, like 5x or 3x^2
+            Token::Multiply(left, right) if left.is_coefficient_of(right) => {
+                format!("{}{}", left.to_string(session), right.to_string(session))
             }
+            Token::Multiply(left, right) => format!(
+                "{} {} {}",
+                left.to_string(session),
+                operator_symbol("*", session),
+                right.to_string(session)
+            ),
+            Token::Divide(left, right) => format!(
+                "{} {} {}",
+                left.to_string(session),
+                operator_symbol("/", session),
+                right.to_string(session)
+            ),
             Token::Add(left, right) => {
                 format!("{} + {}", left.to_string(session), right.to_string(session))
             }
-            Token::Subtract(left, right) => {
-                format!("{} - {}", left.to_string(session), right.to_string(session))
-            }
+            Token::Subtract(left, right) => format!(
+                "{} {} {}",
+                left.to_string(session),
+                operator_symbol("-", session),
+                right.to_string(session)
+            ),
             Token::Equality(left, right) => {
                 format!("{} = {}", left.to_string(session), right.to_string(session))
             }
@@ -97,26 +257,495 @@ impl Token {
 
                 "@!".to_string()
             }
+            Token::Number(number) if session.unicode_output && number.is_negative() => {
+                format!("−{}", number.abs().to_string(session.decimal_places))
+            }
             Token::Number(number) => number.to_string(session.decimal_places),
             Token::Negative(expression) => {
-                format!("-{}", expression.to_string(session))
-            }
-            Token::Variable(variable) => {
-                format!("{}", variable.id)
+                format!(
+                    "{}{}",
+                    operator_symbol("-", session),
+                    expression.to_string(session)
+                )
             }
+            Token::Variable(variable) => variable.id.to_string(),
             Token::Boolean(boolean) => boolean.to_string(),
             Token::Store(id, expression) => {
                 format!("{} -> {}", expression.to_string(session), id)
             }
+            Token::Matrix(rows) => {
+                let rows: Vec<String> = rows
+                    .iter()
+                    .map(|row| {
+                        let entries: Vec<String> =
+                            row.iter().map(|entry| entry.to_string(session)).collect();
+
+                        format!("[{}]", entries.join(", "))
+                    })
+                    .collect();
+
+                format!("[{}]", rows.join(", "))
+            }
+            Token::Function(id, args) if session.unicode_output && id == "sqrt" => {
+                format!("√({})", args[0].to_string(session)) // sqrt always has exactly one argument
+            }
+            Token::Function(id, args) => {
+                let args: Vec<String> = args.iter().map(|arg| arg.to_string(session)).collect();
+
+                format!("{}({})", id, args.join(", "))
+            }
+            Token::Symbol(id) => id.to_string(),
+            Token::Complex(complex) => complex.to_string(session.decimal_places),
+            Token::Constant(id) if session.unicode_output && id == "pi" => "π".to_string(),
+            Token::Constant(id) => id.to_string(),
+            Token::Less(left, right) => {
+                format!("{} < {}", left.to_string(session), right.to_string(session))
+            }
+            Token::Greater(left, right) => {
+                format!("{} > {}", left.to_string(session), right.to_string(session))
+            }
+            Token::LessEqual(left, right) => {
+                format!(
+                    "{} <= {}",
+                    left.to_string(session),
+                    right.to_string(session)
+                )
+            }
+            Token::GreaterEqual(left, right) => {
+                format!(
+                    "{} >= {}",
+                    left.to_string(session),
+                    right.to_string(session)
+                )
+            }
+            Token::NotEqual(left, right) => {
+                format!(
+                    "{} != {}",
+                    left.to_string(session),
+                    right.to_string(session)
+                )
+            }
+            Token::And(left, right) => {
+                format!(
+                    "{} and {}",
+                    left.to_string(session),
+                    right.to_string(session)
+                )
+            }
+            Token::Or(left, right) => {
+                format!(
+                    "{} or {}",
+                    left.to_string(session),
+                    right.to_string(session)
+                )
+            }
+            Token::Xor(left, right) => {
+                format!(
+                    "{} xor {}",
+                    left.to_string(session),
+                    right.to_string(session)
+                )
+            }
+            Token::Not(expression) => {
+                format!("not {}", expression.to_string(session))
+            }
+            Token::Formula(id, expression) => {
+                format!("{} := {}", id, expression.to_string(session))
+            }
         }
     }
+
+    /// Converts entire tokenized expressions into strings that parse back into the same tokens, with parenthesis only where the order of
+    /// operations needs them.
+    ///
+    /// Parenthesis tokens aren't kept as they are, they're put back wherever they're needed instead, so only the parenthesis tokens can
+    /// differ after parsing. Numbers are written out in full, ones without an ending decimal are written as a division like 1/3, and complex
+    /// numbers are written with sqrt(-1) for the imaginary unit. Negative numbers, fractions and complex numbers parse back as the
+    /// operations they're written as, which simplify back into the same number.
+    ///
+    pub fn to_source_string(&self, session: &Session) -> String {
+        // Wraps a token in parenthesis if it binds looser than the operator it's an operand of
+        let operand = |token: &Token, precedence: usize| {
+            let string = token.to_source_string(session);
+
+            match token.precedence() < precedence {
+                true => format!("({})", string),
+                false => string,
+            }
+        };
+
+        let binary = |left: &Token, operator: &str, right: &Token| {
+            let (left_precedence, right_precedence) = self.operand_precedences();
+
+            format!(
+                "{}{}{}",
+                operand(left, left_precedence),
+                operator,
+                operand(right, right_precedence)
+            )
+        };
+
+        let list = |tokens: &[Token]| {
+            let strings: Vec<String> = tokens
+                .iter()
+                .map(|token| token.to_source_string(session))
+                .collect();
+
+            strings.join(", ")
+        };
+
+        match self {
+            Token::Exponent(left, right) => binary(left, "^", right),
+            Token::Multiply(left, right) => binary(left, " * ", right),
+            Token::Divide(left, right) => binary(left, " / ", right),
+            Token::Add(left, right) => binary(left, " + ", right),
+            Token::Subtract(left, right) => binary(left, " - ", right),
+            Token::Equality(left, right) => binary(left, " = ", right),
+            Token::Less(left, right) => binary(left, " < ", right),
+            Token::Greater(left, right) => binary(left, " > ", right),
+            Token::LessEqual(left, right) => binary(left, " <= ", right),
+            Token::GreaterEqual(left, right) => binary(left, " >= ", right),
+            Token::NotEqual(left, right) => binary(left, " != ", right),
+            Token::And(left, right) => binary(left, " and ", right),
+            Token::Or(left, right) => binary(left, " or ", right),
+            Token::Xor(left, right) => binary(left, " xor ", right),
+            Token::Parenthesis(expression) => expression.to_source_string(session),
+            Token::Number(number) => number.to_exact_string(),
+            // Two negative signs in a row don't parse, so the operand of a negative has to bind tighter than a negative
+            Token::Negative(expression) => {
+                format!("-{}", operand(expression, self.precedence() + 1))
+            }
+            Token::Not(expression) => format!("not {}", operand(expression, self.precedence())),
+            Token::Store(id, expression) => {
+                format!("{} -> {}", operand(expression, self.precedence() + 1), id)
+            }
+            Token::Formula(id, expression) => {
+                format!("{} := {}", id, expression.to_source_string(session))
+            }
+            // Single rows are written as a matrix of rows too, since a row of matrices would otherwise look like a matrix of rows
+            Token::Matrix(rows) => {
+                let rows: Vec<String> = rows.iter().map(|row| format!("[{}]", list(row))).collect();
+
+                format!("[{}]", rows.join(", "))
+            }
+            Token::Function(id, args) => format!("{}({})", id, list(args)),
+            // There's no imaginary unit to parse, so the imaginary part is written as a multiple of sqrt(-1)
+            Token::Complex(complex) => {
+                let imaginary = match complex.imaginary.abs() == Number::one() {
+                    true => "sqrt(-1)".to_string(),
+                    false => format!(
+                        "{} * sqrt(-1)",
+                        operand(&Token::Number(complex.imaginary.abs()), 7)
+                    ),
+                };
+
+                match (complex.real.is_zero(), complex.imaginary.is_negative()) {
+                    (true, false) => imaginary,
+                    (true, true) => format!("-{}", imaginary),
+                    (false, false) => format!("{} + {}", complex.real.to_exact_string(), imaginary),
+                    (false, true) => format!("{} - {}", complex.real.to_exact_string(), imaginary),
+                }
+            }
+            Token::Answer(_)
+            | Token::Variable(_)
+            | Token::Boolean(_)
+            | Token::Symbol(_)
+            | Token::Constant(_) => self.to_string(session),
+        }
+    }
+
+    /// Gets the lowest precedence the left and right operands of a binary operator can have without parenthesis around them.
+    ///
+    /// Operators of equal precedence group to the left for the left associative operators and to the right for everything else, except for
+    /// comparisons which can't be chained at all.
+    ///
+    pub fn operand_precedences(&self) -> (usize, usize) {
+        let precedence = self.precedence();
+
+        match self {
+            Token::Add(..) | Token::Subtract(..) | Token::Multiply(..) | Token::Divide(..) => {
+                (precedence, precedence + 1)
+            }
+            Token::Equality(..)
+            | Token::Less(..)
+            | Token::Greater(..)
+            | Token::LessEqual(..)
+            | Token::GreaterEqual(..)
+            | Token::NotEqual(..) => (precedence + 1, precedence + 1),
+            _ => (precedence + 1, precedence),
+        }
+    }
+
+    /// Gets how tightly a token binds to its operands when rendered, higher binding tighter, following the order of operations.
+    ///
+    pub fn precedence(&self) -> usize {
+        match self {
+            Token::Formula(..) => 0,
+            Token::Store(..) => 1,
+            Token::Or(..) | Token::Xor(..) => 2,
+            Token::And(..) => 3,
+            Token::Not(..) => 4,
+            Token::Equality(..)
+            | Token::Less(..)
+            | Token::Greater(..)
+            | Token::LessEqual(..)
+            | Token::GreaterEqual(..)
+            | Token::NotEqual(..) => 5,
+            Token::Add(..) | Token::Subtract(..) => 6,
+            // Complex numbers come out as a sum, or as a multiple of sqrt(-1) when there's no real part
+            Token::Complex(complex) => match complex.real.is_zero() {
+                true => 7,
+                false => 6,
+            },
+            Token::Multiply(..) | Token::Divide(..) => 7,
+            Token::Negative(..) => 8,
+            Token::Exponent(..) => 9,
+            Token::Parenthesis(expression) => expression.precedence(),
+            // Numbers can come out as a division or with a negative sign in front
+            Token::Number(number) => match number.to_exact_string() {
+                string if string.contains('/') => 7,
+                string if string.starts_with('-') => 8,
+                _ => 10,
+            },
+            Token::Answer(_)
+            | Token::Variable(_)
+            | Token::Boolean(_)
+            | Token::Matrix(_)
+            | Token::Function(..)
+            | Token::Symbol(_)
+            | Token::Constant(_) => 10,
+        }
+    }
+
+    /// Gets the expression with every parenthesis token taken out, leaving just what they contained.
+    ///
+    pub fn without_parenthesis(&self) -> Token {
+        self.replace(&|token| match token {
+            Token::Parenthesis(expression) => Some(expression.without_parenthesis()),
+            _ => None,
+        })
+    }
+
+    /// Gets every token directly contained in this token.
+    ///
+    pub fn children(&self) -> Vec<&Token> {
+        match self {
+            Token::Exponent(left, right)
+            | Token::Multiply(left, right)
+            | Token::Divide(left, right)
+            | Token::Add(left, right)
+            | Token::Subtract(left, right)
+            | Token::Equality(left, right)
+            | Token::Less(left, right)
+            | Token::Greater(left, right)
+            | Token::LessEqual(left, right)
+            | Token::GreaterEqual(left, right)
+            | Token::NotEqual(left, right)
+            | Token::And(left, right)
+            | Token::Or(left, right)
+            | Token::Xor(left, right) => vec![left, right],
+            Token::Parenthesis(expression)
+            | Token::Negative(expression)
+            | Token::Not(expression)
+            | Token::Store(_, expression)
+            | Token::Formula(_, expression) => vec![expression],
+            Token::Matrix(rows) => rows.iter().flatten().collect(),
+            Token::Function(_, args) => args.iter().collect(),
+            Token::Answer(_)
+            | Token::Number(_)
+            | Token::Variable(_)
+            | Token::Boolean(_)
+            | Token::Symbol(_)
+            | Token::Complex(_)
+            | Token::Constant(_) => Vec::new(),
+        }
+    }
+
+    /// Returns true if there are any symbols anywhere in the expression.
+    ///
+    pub fn is_symbolic(&self) -> bool {
+        match self {
+            Token::Symbol(_) => true,
+            _ => self.children().iter().any(|child| child.is_symbolic()),
+        }
+    }
+
+    /// Returns true if this token is a number that can be written directly in front of the given token, like the 5 in 5x.
+    ///
+    pub fn is_coefficient_of(&self, token: &Token) -> bool {
+        let is_symbol = |token: &Token| matches!(token, Token::Symbol(_));
+
+        matches!(self, Token::Number(_))
+            && match token {
+                Token::Exponent(base, _) => is_symbol(base),
+                _ => is_symbol(token),
+            }
+    }
+
+    /// Returns true if the given symbol appears anywhere in the expression.
+    ///
+    pub fn contains_symbol(&self, id: &str) -> bool {
+        match self {
+            Token::Symbol(symbol) => symbol == id,
+            _ => self
+                .children()
+                .iter()
+                .any(|child| child.contains_symbol(id)),
+        }
+    }
+
+    /// Gets the names of every variable and symbol the expression refers to, sorted and without duplicates.
+    ///
+    pub fn identifiers(&self) -> Vec<String> {
+        let mut identifiers = match self {
+            Token::Variable(variable) => vec![variable.id.clone()],
+            Token::Symbol(id) => vec![id.clone()],
+            _ => self
+                .children()
+                .iter()
+                .flat_map(|child| child.identifiers())
+                .collect(),
+        };
+
+        identifiers.sort();
+        identifiers.dedup();
+
+        identifiers
+    }
+
+    /// Replaces every occurrence of the given symbol with a value.
+    ///
+    pub fn substitute(&self, id: &str, value: &Token) -> Token {
+        self.replace(&|token| match token {
+            Token::Symbol(symbol) if symbol == id => Some(value.clone()),
+            _ => None,
+        })
+    }
+
+    /// Gets the UUIDs of every history entry the expression refers to with an answer, like @1.
+    ///
+    pub fn answers(&self) -> Vec<Uuid> {
+        match self {
+            Token::Answer(uuid) => vec![*uuid],
+            _ => self
+                .children()
+                .iter()
+                .flat_map(|child| child.answers())
+                .collect(),
+        }
+    }
+
+    /// Rebuilds the expression, swapping out every token the function gives a replacement for.
+    ///
+    /// Replacements aren't searched any further, everything else is searched all the way down.
+    ///
+    pub fn replace(&self, function: &dyn Fn(&Token) -> Option<Token>) -> Token {
+        if let Some(replacement) = function(self) {
+            return replacement;
+        }
+
+        let sub = |token: &Token| Box::new(token.replace(function));
+
+        match self {
+            Token::Exponent(left, right) => Token::Exponent(sub(left), sub(right)),
+            Token::Multiply(left, right) => Token::Multiply(sub(left), sub(right)),
+            Token::Divide(left, right) => Token::Divide(sub(left), sub(right)),
+            Token::Add(left, right) => Token::Add(sub(left), sub(right)),
+            Token::Subtract(left, right) => Token::Subtract(sub(left), sub(right)),
+            Token::Equality(left, right) => Token::Equality(sub(left), sub(right)),
+            Token::Less(left, right) => Token::Less(sub(left), sub(right)),
+            Token::Greater(left, right) => Token::Greater(sub(left), sub(right)),
+            Token::LessEqual(left, right) => Token::LessEqual(sub(left), sub(right)),
+            Token::GreaterEqual(left, right) => Token::GreaterEqual(sub(left), sub(right)),
+            Token::NotEqual(left, right) => Token::NotEqual(sub(left), sub(right)),
+            Token::And(left, right) => Token::And(sub(left), sub(right)),
+            Token::Or(left, right) => Token::Or(sub(left), sub(right)),
+            Token::Xor(left, right) => Token::Xor(sub(left), sub(right)),
+            Token::Not(expression) => Token::Not(sub(expression)),
+            Token::Parenthesis(expression) => Token::Parenthesis(sub(expression)),
+            Token::Negative(expression) => Token::Negative(sub(expression)),
+            Token::Store(store_id, expression) => Token::Store(store_id.clone(), sub(expression)),
+            Token::Formula(formula_id, expression) => {
+                Token::Formula(formula_id.clone(), sub(expression))
+            }
+            Token::Matrix(rows) => Token::Matrix(
+                rows.iter()
+                    .map(|row| row.iter().map(|entry| *sub(entry)).collect())
+                    .collect(),
+            ),
+            Token::Function(function_id, args) => Token::Function(
+                function_id.clone(),
+                args.iter().map(|arg| *sub(arg)).collect(),
+            ),
+            _ => self.clone(),
+        }
+    }
+}
+
+/// Reverse order of operations, with operators of equal precedence grouped together.
+///
+/// **NOT PUBLIC.**
+///
+const ORDER_OF_OPS: [&[&str]; 11] = [
+    &[":="],
+    &["->"],
+    &[OR_SYMBOL, XOR_SYMBOL],
+    &[AND_SYMBOL],
+    &[NOT_SYMBOL],
+    &COMPARISON_OPS,
+    &["-", "+"],
+    &["/", "*"],
+    &[NEG_SYMBOL],
+    &["^"],
+    &["@"],
+];
+
+/// Comparison operators, the two character ones first so "<=" isn't mistaken for "<".
+///
+/// Comparisons can't be chained, since 1 < 2 < 3 would compare the truth of 1 < 2 with 3.
+///
+/// **NOT PUBLIC.**
+///
+const COMPARISON_OPS: [&str; 6] = ["<=", ">=", "!=", "=", "<", ">"];
+
+/// Operators that group left to right, so 1-2+3 is (1-2)+3. Everything else groups right to left, so 2^3^2 is 2^(3^2).
+///
+/// **NOT PUBLIC.**
+///
+const LEFT_ASSOCIATIVE_OPS: [&str; 4] = ["-", "+", "/", "*"];
+
+/// Names of the built-in constants, i being the imaginary unit so complex results like "1 + 2i" parse back to themselves.
+///
+pub const CONSTANTS: [&str; 3] = ["e", "pi", "i"];
+
+/// Superscript digits, indexed by the digit they stand for.
+///
+/// **NOT PUBLIC.**
+///
+const SUPERSCRIPT_DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
+/// Unicode symbols accepted in place of ASCII operators, along with their ASCII equivalents.
+///
+/// **NOT PUBLIC.**
+///
+const UNICODE_OPERATORS: [(char, &str); 4] = [('×', "*"), ('·', "*"), ('÷', "/"), ('−', "-")];
+
+/// Gets the symbol an ASCII operator is rendered with, which is its unicode equivalent if the session renders unicode.
+///
+pub fn operator_symbol(operator: &'static str, session: &Session) -> &'static str {
+    match (session.unicode_output, operator) {
+        (true, "*") => "×",
+        (true, "/") => "÷",
+        (true, "-") => "−",
+        _ => operator,
+    }
 }
 
-/// Reverse order of operations.
+/// Largest exponent a number in scientific notation can have, since it gets written out digit by digit.
 ///
 /// **NOT PUBLIC.**
 ///
-const ORDER_OF_OPS: [&str; 9] = ["->", "=", "-", "+", "/", "*", NEG_SYMBOL, "^", "@"];
+const MAX_SCIENTIFIC_EXPONENT: u64 = 10_000;
 
 /// Internal symbol for -1, for parser uses only
 ///
@@ -124,6 +753,30 @@ const ORDER_OF_OPS: [&str; 9] = ["->", "=", "-", "+", "/", "*", NEG_SYMBOL, "^",
 ///
 const NEG_SYMBOL: &str = "\x26";
 
+/// Internal symbol for "and", for parser uses only
+///
+/// **NOT PUBLIC.**
+///
+const AND_SYMBOL: &str = "\x01";
+
+/// Internal symbol for "or", for parser uses only
+///
+/// **NOT PUBLIC.**
+///
+const OR_SYMBOL: &str = "\x02";
+
+/// Internal symbol for "xor", for parser uses only
+///
+/// **NOT PUBLIC.**
+///
+const XOR_SYMBOL: &str = "\x03";
+
+/// Internal symbol for "not", for parser uses only
+///
+/// **NOT PUBLIC.**
+///
+const NOT_SYMBOL: &str = "\x04";
+
 /// Strips a string of whitespace, makes sure it's not empty, and runs the string through parse()!
 ///
 /// Throws a simple error if the expression is empty.
@@ -131,24 +784,294 @@ const NEG_SYMBOL: &str = "\x26";
 pub fn parse_str(string: &str, session: &mut Session) -> Result<Token, Box<dyn Error>> {
     // Regex definitions n stuff
     lazy_static! {
-        static ref NEGATIVE_RE: Regex = Regex::new(r"(?P<a>^|[(=\-\+/\*\^])(?P<b>-)").unwrap(); // Used to see if there are negative numbers in the string
+        static ref NEGATIVE_RE: Regex = Regex::new(r"(?P<a>^|[(\[,=<>\-\+/\*\^\x01-\x04])(?P<b>-)").unwrap(); // Used to see if there are negative numbers in the string
+        static ref LOGIC_WORD_RE: Regex = Regex::new(r"\b(?P<word>and|or|xor|not)\b").unwrap(); // Used to find the logical operators, which are words
         static ref COMMENT_RE: Regex = Regex::new(r"#.*").unwrap();
+        static ref IMPLICIT_NUM_RE: Regex = Regex::new(r"(?P<a>(?:^|[^[:alnum:]_.@])[0-9.]+)(?P<b>[[:alpha:](])").unwrap(); // Used to find numbers directly followed by a symbol or parenthesis, like 3x
+        static ref IMPLICIT_PAREN_RE: Regex = Regex::new(r"\)(?P<b>[[:alnum:](])").unwrap(); // Used to find parenthesis directly followed by something, like (x+1)(x-1)
     }
 
+    let string = replace_unicode(string);
+
+    // Logical operators are words, so they're swapped for symbols before the whitespace separating them from identifiers is lost
+    let string = LOGIC_WORD_RE.replace_all(&string, |captures: &regex::Captures| {
+        match &captures["word"] {
+            "and" => AND_SYMBOL,
+            "or" => OR_SYMBOL,
+            "xor" => XOR_SYMBOL,
+            _ => NOT_SYMBOL,
+        }
+    });
+
     let mut cleaned_string: String = string.chars().filter(|c| !c.is_whitespace()).collect();
 
     cleaned_string = COMMENT_RE.replace_all(&cleaned_string, "").to_string();
 
-    if cleaned_string.len() == 0 {
+    if cleaned_string.is_empty() {
         bail!("Empty Expression!");
     }
 
+    // C style conditionals would otherwise fail somewhere confusing, like trying to read "1?2" as a number
+    if cleaned_string.contains('?') {
+        bail!("Conditionals are written as if(cond, a, b), not cond ? a : b!");
+    }
+
+    // Scientific notation has to be written out before implicit multiplication, or 1e-20 would be 1 * e - 20
+    cleaned_string = expand_scientific(&cleaned_string)?;
+
+    // Implicit multiplication, 3x(x+1) becomes 3*x*(x+1)
+    cleaned_string = IMPLICIT_NUM_RE
+        .replace_all(&cleaned_string, "$a*$b")
+        .to_string();
+
+    cleaned_string = IMPLICIT_PAREN_RE
+        .replace_all(&cleaned_string, ")*$b")
+        .to_string();
+
     // Replace negative sign with ascii 0x26, for parsing reasons
     cleaned_string = NEGATIVE_RE
         .replace_all(&cleaned_string, format!("$a{}", NEG_SYMBOL))
         .to_string();
 
-    Ok(parse(&cleaned_string, session)?)
+    parse(&cleaned_string, session)
+}
+
+/// Writes out numbers in scientific notation as plain decimals, so 1.5e-3 becomes 0.0015 and stays exact.
+///
+/// A number directly followed by an e without an exponent, like 2e, is left alone as a multiple of e.
+///
+/// Returns a simple error if an exponent is too big to write out.
+///
+/// **PRIVATE FUNCTION**
+///
+fn expand_scientific(string: &str) -> Result<String, Box<dyn Error>> {
+    lazy_static! {
+        static ref WORD_RE: Regex = Regex::new(r"[[:alnum:]_.@]+").unwrap(); // Used to find the numbers and names in the string
+        static ref SCIENTIFIC_RE: Regex = Regex::new(r"^(?P<mantissa>[0-9]+\.?[0-9]*|\.[0-9]+)[eE](?P<exponent>[0-9]*)$").unwrap(); // Used to see if a word is a number in scientific notation
+        static ref SIGNED_EXPONENT_RE: Regex = Regex::new(r"^[+\-][0-9]+").unwrap(); // Used to find the exponent of 1e-20, which isn't part of the word 1e
+    }
+
+    let mut expanded = String::with_capacity(string.len());
+    let mut last = 0;
+
+    for word in WORD_RE.find_iter(string) {
+        // The digits of a signed exponent were already written out along with their number
+        if word.start() < last {
+            continue;
+        }
+
+        let captures = match SCIENTIFIC_RE.captures(word.as_str()) {
+            Some(captures) => captures,
+            None => continue,
+        };
+
+        let (exponent, end) = match &captures["exponent"] {
+            "" => match SIGNED_EXPONENT_RE.find(&string[word.end()..]) {
+                Some(signed) => (signed.as_str(), word.end() + signed.end()),
+                None => continue,
+            },
+            exponent => (exponent, word.end()),
+        };
+
+        let exponent = match exponent.parse::<i64>() {
+            Ok(exponent) if exponent.unsigned_abs() <= MAX_SCIENTIFIC_EXPONENT => exponent,
+            _ => bail!(
+                "Exponent of {}{} is too big, it can be at most {}!",
+                word.as_str(),
+                &string[word.end()..end],
+                MAX_SCIENTIFIC_EXPONENT
+            ),
+        };
+
+        let mantissa = &captures["mantissa"];
+        let digits = mantissa.replace('.', "");
+        let point = mantissa.find('.').unwrap_or(mantissa.len()) as i64 + exponent;
+
+        expanded.push_str(&string[last..word.start()]);
+
+        if point <= 0 {
+            expanded.push_str(&format!("0.{}{}", "0".repeat(-point as usize), digits));
+        } else if point as usize >= digits.len() {
+            expanded.push_str(&format!(
+                "{}{}",
+                digits,
+                "0".repeat(point as usize - digits.len())
+            ));
+        } else {
+            expanded.push_str(&format!(
+                "{}.{}",
+                &digits[..point as usize],
+                &digits[point as usize..]
+            ));
+        }
+
+        last = end;
+    }
+
+    expanded.push_str(&string[last..]);
+
+    Ok(expanded)
+}
+
+/// Replaces the unicode symbols people paste in with their ASCII equivalents.
+///
+/// ×, · and ÷ become operators, − becomes a minus sign, π becomes pi, runs of superscript digits become exponents and √ becomes sqrt, taking
+/// the number, name or parenthesis after it as its argument.
+///
+/// **PRIVATE FUNCTION**
+///
+fn replace_unicode(string: &str) -> String {
+    let chars: Vec<char> = string.chars().collect();
+    let mut replaced = String::with_capacity(string.len());
+
+    // Superscript digits count as alphanumeric, but they're exponents here
+    let is_name_char = |c: &char| {
+        (c.is_alphanumeric() || *c == '_') && *c != '⁻' && !SUPERSCRIPT_DIGITS.contains(c)
+    };
+
+    // Names directly next to each other need a multiplication sign, or they'd run together into a single name
+    let ends_with_name = |replaced: &str| {
+        replaced
+            .chars()
+            .last()
+            .is_some_and(|last| last.is_alphanumeric() || last == '_' || last == ')')
+    };
+    let starts_name = |next: Option<&char>| {
+        next.is_some_and(|next| is_name_char(next) || *next == '(' || *next == 'π' || *next == '√')
+    };
+
+    let mut i = 0;
+
+    while i < chars.len() {
+        let character = chars[i];
+
+        if let Some((_, ascii)) = UNICODE_OPERATORS
+            .iter()
+            .find(|(symbol, _)| *symbol == character)
+        {
+            replaced.push_str(ascii);
+        } else if character == 'π' {
+            if ends_with_name(&replaced) {
+                replaced.push('*');
+            }
+
+            replaced.push_str("pi");
+
+            if starts_name(chars.get(i + 1)) {
+                replaced.push('*');
+            }
+        } else if character == '√' {
+            if ends_with_name(&replaced) {
+                replaced.push('*');
+            }
+
+            replaced.push_str("sqrt");
+
+            // Spaces and a minus sign can come before the operand, like √ −4
+            let mut start = i + 1;
+
+            while chars.get(start).is_some_and(|c| c.is_whitespace()) {
+                start += 1;
+            }
+
+            let negative = matches!(chars.get(start), Some('-') | Some('−'));
+
+            if negative {
+                start += 1;
+            }
+
+            // The operand is a parenthesis, a number, π or a name
+            let length = match chars.get(start) {
+                Some('(') => {
+                    let mut depth = 0;
+
+                    chars[start..]
+                        .iter()
+                        .position(|c| {
+                            match c {
+                                '(' => depth += 1,
+                                ')' => depth -= 1,
+                                _ => {}
+                            }
+
+                            depth == 0
+                        })
+                        .map_or(chars.len() - start, |close| close + 1)
+                }
+                Some('π') => 1,
+                Some(next) if next.is_ascii_digit() || *next == '.' => chars[start..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit() || **c == '.')
+                    .count(),
+                _ => chars[start..]
+                    .iter()
+                    .take_while(|c| is_name_char(c))
+                    .count(),
+            };
+
+            // A parenthesis is already the argument list, anything else gets wrapped in one
+            if !negative && chars.get(start) == Some(&'(') {
+                i = start;
+                continue;
+            }
+
+            let operand: String = chars[start..start + length].iter().collect();
+
+            replaced.push('(');
+
+            if negative {
+                replaced.push('-');
+            }
+
+            replaced.push_str(&replace_unicode(&operand));
+            replaced.push(')');
+
+            i = start + length;
+            continue;
+        } else if character == '⁻' || SUPERSCRIPT_DIGITS.contains(&character) {
+            replaced.push('^');
+
+            while let Some(superscript) = chars.get(i) {
+                match SUPERSCRIPT_DIGITS
+                    .iter()
+                    .position(|digit| digit == superscript)
+                {
+                    Some(digit) => replaced.push_str(&digit.to_string()),
+                    None if *superscript == '⁻' => replaced.push('-'),
+                    None => break,
+                }
+
+                i += 1;
+            }
+
+            continue;
+        } else {
+            replaced.push(character);
+        }
+
+        i += 1;
+    }
+
+    replaced
+}
+
+/// Splits a line into the statements separated by semicolons, leaving out empty statements and comments.
+///
+/// Statements need to be parsed one at a time, after the statements before them have been evaluated, so they can use the variables stored by them.
+///
+/// Returns a simple error if there is an unmatched pair of parenthesis.
+///
+pub fn split_statements(string: &str) -> Result<Vec<&str>, Box<dyn Error>> {
+    // A semicolon in a comment doesn't start a new statement
+    let string = match string.find('#') {
+        Some(index) => &string[..index],
+        None => string,
+    };
+
+    Ok(split_outside_parenthesis(string, ';')?
+        .into_iter()
+        .filter(|statement| !statement.trim().is_empty())
+        .collect())
 }
 
 /// Parses a string recursively, breaking it down into Tokens.
@@ -162,26 +1085,26 @@ pub fn parse_str(string: &str, session: &mut Session) -> Result<Token, Box<dyn E
 fn parse(string: &str, session: &mut Session) -> Result<Token, Box<dyn Error>> {
     // Regex definitions n stuff
     lazy_static! {
-        static ref VAR_NAMECHECK_RE: Regex = Regex::new(r"[@=\-\+/\*\^>\u0026]").unwrap(); // Used to see if a variable name is valid; it shouldn't contain any operators
+        static ref VAR_NAMECHECK_RE: Regex = Regex::new(r"[@=:\-\+/\*\^<>!\u0026(),\[\]\x01-\x04]").unwrap(); // Used to see if a variable name is valid; it shouldn't contain any operators or brackets
+        static ref FUNCTION_RE: Regex = Regex::new(r"^(?P<id>[[:alpha:]][[:alnum:]_]*)\((?P<args>.*)\)$").unwrap(); // Used to see if the string is a function call
+        static ref IDENTIFIER_RE: Regex = Regex::new(r"^[[:alpha:]][[:alnum:]_]*$").unwrap(); // Used to see if the string is a plain identifier
     }
 
-    for opcode in ORDER_OF_OPS {
+    for group in ORDER_OF_OPS {
         // If so...
-        let op_index = match_outside_parenthesis(string, opcode)?;
-
-        if op_index.is_some() {
+        if let Some((splitpoint, opcode)) = find_operator(string, group)? {
             // Split the string at the operator...
-            let splitpoint = op_index.unwrap();
 
             // If there is nothing to the left or right of the operator, produce an error(unless the operator is the negative sign or an answer sign)...
-            if splitpoint + 1 == string.len()
-                || (splitpoint == 0 && (opcode != NEG_SYMBOL && opcode != "@"))
+            if splitpoint + opcode.len() == string.len()
+                || (splitpoint == 0
+                    && (opcode != NEG_SYMBOL && opcode != NOT_SYMBOL && opcode != "@"))
             {
                 bail!("Incomplete Expression: {}", string);
             }
 
-            // Furthermore, if there is anything left of the negative sign more parsing needs to be done...
-            if splitpoint != 0 && opcode == NEG_SYMBOL {
+            // Furthermore, if there is anything left of the negative or not sign more parsing needs to be done...
+            if splitpoint != 0 && (opcode == NEG_SYMBOL || opcode == NOT_SYMBOL) {
                 continue;
             }
 
@@ -189,6 +1112,16 @@ fn parse(string: &str, session: &mut Session) -> Result<Token, Box<dyn Error>> {
             let left = string[..splitpoint].to_string();
             let right = string[splitpoint + opcode.len()..].to_string();
 
+            if COMPARISON_OPS.contains(&opcode)
+                && (find_operator(&left, &COMPARISON_OPS)?.is_some()
+                    || find_operator(&right, &COMPARISON_OPS)?.is_some())
+            {
+                bail!(
+                    "Comparisons can't be chained, join them with and: {}",
+                    string
+                );
+            }
+
             // Parse the left and right sides recursively...
             return match opcode {
                 "@" => {
@@ -223,6 +1156,39 @@ fn parse(string: &str, session: &mut Session) -> Result<Token, Box<dyn Error>> {
                     Box::new(parse(&left, session)?),
                     Box::new(parse(&right, session)?),
                 )),
+                "<" => Ok(Token::Less(
+                    Box::new(parse(&left, session)?),
+                    Box::new(parse(&right, session)?),
+                )),
+                ">" => Ok(Token::Greater(
+                    Box::new(parse(&left, session)?),
+                    Box::new(parse(&right, session)?),
+                )),
+                "<=" => Ok(Token::LessEqual(
+                    Box::new(parse(&left, session)?),
+                    Box::new(parse(&right, session)?),
+                )),
+                ">=" => Ok(Token::GreaterEqual(
+                    Box::new(parse(&left, session)?),
+                    Box::new(parse(&right, session)?),
+                )),
+                "!=" => Ok(Token::NotEqual(
+                    Box::new(parse(&left, session)?),
+                    Box::new(parse(&right, session)?),
+                )),
+                AND_SYMBOL => Ok(Token::And(
+                    Box::new(parse(&left, session)?),
+                    Box::new(parse(&right, session)?),
+                )),
+                OR_SYMBOL => Ok(Token::Or(
+                    Box::new(parse(&left, session)?),
+                    Box::new(parse(&right, session)?),
+                )),
+                XOR_SYMBOL => Ok(Token::Xor(
+                    Box::new(parse(&left, session)?),
+                    Box::new(parse(&right, session)?),
+                )),
+                NOT_SYMBOL => Ok(Token::Not(Box::new(parse(&right, session)?))),
                 "->" => {
                     // Make sure the variable doesn't contain any operators
                     if VAR_NAMECHECK_RE.is_match(&right) {
@@ -231,6 +1197,14 @@ fn parse(string: &str, session: &mut Session) -> Result<Token, Box<dyn Error>> {
 
                     Ok(Token::Store(right, Box::new(parse(&left, session)?)))
                 }
+                ":=" => {
+                    // Same goes for formulas, where the variable is on the left
+                    if VAR_NAMECHECK_RE.is_match(&left) {
+                        bail!("Invalid variable name {}!", left);
+                    }
+
+                    Ok(Token::Formula(left, Box::new(parse(&right, session)?)))
+                }
                 // It is entrely possible I am a terrible programmer and I forgot to implement all the operators in the ORDER_OF_OPS table...
                 _ => {
                     panic!("\n\nFatal Oopsiedaisies!\n\n\tOperator found in table but no code to handle it: {}\n\n", opcode);
@@ -247,23 +1221,195 @@ fn parse(string: &str, session: &mut Session) -> Result<Token, Box<dyn Error>> {
         return Ok(Token::Number(Number::from_str(string)?));
     }
 
-    // If the string is a variable...
+    // If the string is a function call...
+    if let Some(captures) = FUNCTION_RE.captures(string) {
+        let id = &captures["id"];
+
+        let function = match functions::get_builtin(id) {
+            Some(function) => function,
+            None => bail!("Unknown function {}!", id),
+        };
+
+        // An empty argument list means no arguments, not one empty argument
+        let arg_strings = match captures["args"].is_empty() {
+            true => Vec::<&str>::new(),
+            false => split_outside_parenthesis(&captures["args"], ',')?,
+        };
+
+        function.check_arg_count(arg_strings.len())?;
+
+        // If the function binds a symbol, like the x in roots(x^2 - 1, x), it's a symbol instead of a variable while parsing the arguments
+        let bound_symbol = function
+            .bound_arg
+            .and_then(|index| arg_strings.get(index))
+            .filter(|arg| IDENTIFIER_RE.is_match(arg));
+
+        if let Some(symbol) = bound_symbol {
+            session.bound_symbols.push(symbol.to_string());
+        }
+
+        let args = arg_strings
+            .iter()
+            .map(|arg| parse(arg, session))
+            .collect::<Result<Vec<Token>, Box<dyn Error>>>();
+
+        // Unbind the symbol even if parsing failed
+        if bound_symbol.is_some() {
+            session.bound_symbols.pop();
+        }
+
+        return Ok(Token::Function(id.to_string(), args?));
+    }
+
+    // If the string is a boolean...
+    if let Ok(boolean) = bool::from_str(string) {
+        return Ok(Token::Boolean(boolean));
+    }
+
+    // If the string is a symbol bound by a function...
+    if session.bound_symbols.iter().any(|symbol| symbol == string) {
+        return Ok(Token::Symbol(string.to_string()));
+    }
+
+    // If the string is a variable, or a free symbol if there's no such variable...
     if string.chars().next().unwrap().is_alphabetic() {
-        return Ok(Token::Variable(Box::new(session.vartable.get(string)?)));
+        return match session.vartable.get(string) {
+            Ok(variable) => Ok(Token::Variable(Box::new(variable))),
+            Err(_) if CONSTANTS.contains(&string) => Ok(Token::Constant(string.to_string())),
+            Err(_) => Ok(Token::Symbol(string.to_string())),
+        };
     }
 
     // If the string is an expression surrounded in parenthesis...
-    if string.chars().next().unwrap() == '(' {
+    if string.starts_with('(') {
         return Ok(Token::Parenthesis(Box::new(parse(
             &string[1..string.len() - 1],
             session,
         )?)));
     }
 
+    // If the string is a matrix...
+    if string.starts_with('[') && string.ends_with(']') {
+        let elements = split_outside_parenthesis(&string[1..string.len() - 1], ',')?;
+
+        // A matrix made of rows looks like [[a, b], [c, d]], otherwise it's a single row like [a, b]
+        let row_strings = match elements.iter().all(|element| element.starts_with('[')) {
+            true => elements
+                .iter()
+                .map(|element| {
+                    if !element.ends_with(']') {
+                        bail!("Invalid matrix row: {}", element);
+                    }
+
+                    Ok(&element[1..element.len() - 1])
+                })
+                .collect::<Result<Vec<&str>, Box<dyn Error>>>()?,
+            false => vec![&string[1..string.len() - 1]],
+        };
+
+        let mut rows = Vec::<Vec<Token>>::new();
+
+        for row_string in row_strings {
+            let mut row = Vec::<Token>::new();
+
+            for entry in split_outside_parenthesis(row_string, ',')? {
+                if entry.is_empty() {
+                    bail!("Empty matrix entry in {}", string);
+                }
+
+                row.push(parse(entry, session)?);
+            }
+
+            if let Some(first_row) = rows.first() {
+                if first_row.len() != row.len() {
+                    bail!("Every row of a matrix must be the same length!");
+                }
+            }
+
+            rows.push(row);
+        }
+
+        return Ok(Token::Matrix(rows));
+    }
+
     // At the moment nothing else is supported so we bail!
     bail!("Invalid Expression: {}", string);
 }
 
+/// Finds the operator from a group of equal precedence operators that the string should be split at.
+///
+/// Left associative operators split at their last occurrence, everything else splits at its first.
+///
+/// **NOT PUBLIC.**
+///
+fn find_operator<'a>(
+    string: &str,
+    group: &[&'a str],
+) -> Result<Option<(usize, &'a str)>, Box<dyn Error>> {
+    let mut found: Option<(usize, &'a str)> = None;
+
+    for opcode in group {
+        let left_associative = LEFT_ASSOCIATIVE_OPS.contains(opcode);
+
+        let index = match left_associative {
+            true => rmatch_outside_parenthesis(string, opcode)?,
+            false => match_outside_parenthesis(string, opcode)?,
+        };
+
+        if let Some(index) = index {
+            let is_better = match found {
+                Some((found_index, _)) => match left_associative {
+                    true => index > found_index,
+                    false => index < found_index,
+                },
+                None => true,
+            };
+
+            if is_better {
+                found = Some((index, opcode));
+            }
+        }
+    }
+
+    Ok(found)
+}
+
+/// Searches for the last occurrence of the given substring outside of parenthesis.
+///
+/// Returns the index of the substring if found, None if otherwise.
+///
+/// Also returns a simple error if there is an unmatched pair of parenthesis.
+///
+pub fn rmatch_outside_parenthesis(
+    string: &str,
+    substring: &str,
+) -> Result<Option<usize>, Box<dyn Error>> {
+    let mut nest_level = 0;
+    let mut found: Option<usize> = None;
+
+    for (i, character) in string.char_indices() {
+        match character {
+            '(' | '[' => nest_level += 1,
+            ')' | ']' => nest_level -= 1,
+            _ => {}
+        }
+
+        if nest_level < 0 {
+            bail!("Too many closing parenthesis!");
+        }
+
+        if nest_level == 0 && string[i..].starts_with(substring) {
+            found = Some(i);
+        }
+    }
+
+    if nest_level > 0 {
+        bail!("Forgot to close parenthesis!");
+    }
+
+    Ok(found)
+}
+
 /// Searches for the given substring outside of parenthesis.
 ///
 /// Returns the index of the substring if found, None if otherwise.
@@ -279,7 +1425,7 @@ pub fn match_outside_parenthesis(
     let mut compare_string = String::with_capacity(substring.len());
 
     for (i, character) in string.chars().enumerate() {
-        if character == '(' {
+        if character == '(' || character == '[' {
             // Clear the compare string and set the index back to zero if we encounter a parenthesis mid match.
             if index != 0 {
                 compare_string.clear();
@@ -291,7 +1437,7 @@ pub fn match_outside_parenthesis(
         }
 
         // Decrement the nest level if we hit a closing parenthesis.
-        if character == ')' {
+        if character == ')' || character == ']' {
             nest_level -= 1;
         }
 
@@ -332,9 +1478,50 @@ pub fn match_outside_parenthesis(
     Ok(None)
 }
 
+/// Splits a string at every occurrence of the given separator outside of parenthesis and brackets.
+///
+/// Returns a simple error if there is an unmatched pair of parenthesis.
+///
+pub fn split_outside_parenthesis(
+    string: &str,
+    separator: char,
+) -> Result<Vec<&str>, Box<dyn Error>> {
+    let mut nest_level = 0;
+    let mut start: usize = 0;
+    let mut pieces = Vec::<&str>::new();
+
+    for (i, character) in string.char_indices() {
+        match character {
+            '(' | '[' => nest_level += 1,
+            ')' | ']' => nest_level -= 1,
+            _ => {}
+        }
+
+        if nest_level < 0 {
+            bail!("Too many closing parenthesis!");
+        }
+
+        // Only split on separators that aren't nested in anything
+        if nest_level == 0 && character == separator {
+            pieces.push(&string[start..i]);
+            start = i + character.len_utf8();
+        }
+    }
+
+    if nest_level > 0 {
+        bail!("Forgot to close parenthesis!");
+    }
+
+    pieces.push(&string[start..]);
+
+    Ok(pieces)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::op_engine;
+    use crate::session::HistoryEntry;
 
     const TWO: &str = "2";
     const X: &str = "x";
@@ -357,6 +1544,34 @@ mod tests {
         assert_eq!(tokenized_expression_ref, tokenized_expression_res);
     }
 
+    // Test to make sure numbers in scientific notation are written out, rather than multiplied by e
+    #[test]
+    fn test_parser_scientific() {
+        let mut session = Session::_new_test().unwrap();
+
+        for (scientific, decimal) in [
+            ("1e-20", "0.00000000000000000001"),
+            ("1.5E+3", "1500"),
+            ("12.5e-1", "1.25"),
+            (".5e1", "5"),
+        ] {
+            assert_eq!(
+                parse_str(scientific, &mut session).unwrap(),
+                Token::Number(Number::from_str(decimal).unwrap())
+            );
+        }
+
+        // Names with digits aren't numbers, and a number followed by e without an exponent is a multiple of e
+        assert_eq!(
+            parse_str("x1e3 + 2e", &mut session)
+                .unwrap()
+                .to_string(&session),
+            "x1e3 + 2 * e"
+        );
+
+        parse_str("1e99999", &mut session).unwrap_err();
+    }
+
     // Test to make sure the parser can recognize variables
     #[test]
     fn test_parser_var() {
@@ -378,15 +1593,341 @@ mod tests {
         assert_eq!(tokenized_expression_ref, tokenized_expression_res);
     }
 
+    // Test to make sure identifiers that aren't variables parse as free symbols
+    #[test]
+    fn test_parser_symbol() {
+        let mut session = Session::_new_test().unwrap();
+
+        let tokenized_expression_res = parse_str(X, &mut session).unwrap();
+
+        assert_eq!(tokenized_expression_res, Token::Symbol("x".to_string()));
+
+        // Numbers in front of symbols are written without the multiplication sign
+        let tokenized_expression_res = parse_str("5x^2", &mut session).unwrap();
+
+        assert_eq!(tokenized_expression_res.to_string(&session), "5x^2");
+    }
+
+    // Test to make sure the parser can recognize comparisons and logical operators
+    #[test]
+    fn test_parser_logic() {
+        let mut session = Session::_new_test().unwrap();
+
+        let tokenized_expression_res = parse_str("x <= 2 and not android", &mut session).unwrap();
+        let tokenized_expression_ref = Token::And(
+            Box::new(Token::LessEqual(
+                Box::new(Token::Symbol("x".to_string())),
+                Box::new(Token::Number(Number::from_i64(2))),
+            )),
+            Box::new(Token::Not(Box::new(Token::Symbol("android".to_string())))),
+        );
+
+        assert_eq!(tokenized_expression_res, tokenized_expression_ref);
+        assert_eq!(
+            tokenized_expression_res.to_string(&session),
+            "x <= 2 and not android"
+        );
+
+        // Comparisons bind tighter than logic, and arithmetic tighter than comparisons
+        let tokenized_expression_res = parse_str("1 + 1 > -1 or false", &mut session).unwrap();
+
+        assert!(matches!(tokenized_expression_res, Token::Or(..)));
+
+        // Chains would compare a truth value with a number, so they have to be joined with and
+        for chain in ["1 < 2 < 3", "3 > 2 > 1", "x = y = 1", "1 <= x != 2"] {
+            parse_str(chain, &mut session).unwrap_err();
+        }
+
+        parse_str("1 < 2 and 2 < 3", &mut session).unwrap();
+        parse_str("(1 < 2) = true", &mut session).unwrap();
+
+        // Conditionals point at if instead of failing on a number
+        let error = parse_str("1 ? 2 : 3", &mut session).unwrap_err();
+
+        assert!(error.to_string().contains("if(cond, a, b)"));
+    }
+
+    // Test to make sure lines are split into statements
+    #[test]
+    fn test_parser_split_statements() {
+        assert_eq!(
+            split_statements("3 -> a; max(a; 4) ;; a # b; c").unwrap(),
+            vec!["3 -> a", " max(a; 4) ", " a "]
+        );
+
+        assert!(split_statements(" ; ").unwrap().is_empty());
+
+        split_statements("(1; 2").unwrap_err();
+    }
+
+    // Test to make sure unicode operators are read as their ASCII equivalents, and can be rendered back
+    #[test]
+    fn test_parser_unicode() {
+        let mut session = Session::_new_test().unwrap();
+
+        for (unicode, ascii) in [
+            ("3 × 4 ÷ 2 − 1", "3 * 4 / 2 - 1"),
+            ("2·πr²", "2 * pi * r^2"),
+            ("√2 + √(x + 1)", "sqrt(2) + sqrt(x + 1)"),
+            ("2√x³", "2 * sqrt(x)^3"),
+            ("√−4 + √-x", "sqrt(-4) + sqrt(-x)"),
+            ("√ −(π × 2)", "sqrt(-( pi * 2 ))"),
+            ("x⁻¹", "x^-1"),
+        ] {
+            assert_eq!(
+                parse_str(unicode, &mut session)
+                    .unwrap()
+                    .to_string(&session),
+                ascii
+            );
+        }
+
+        session.unicode_output = true;
+
+        assert_eq!(
+            parse_str("-2 * pi * r^2 / sqrt(3) - x^-1", &mut session)
+                .unwrap()
+                .to_string(&session),
+            "−2 × π × r² ÷ √(3) − x^−1"
+        );
+    }
+
+    // Test to make sure source strings only keep the parenthesis the order of operations needs
+    #[test]
+    fn test_parser_source_string() {
+        let mut session = Session::_new_test().unwrap();
+
+        for (expression, source) in [
+            ("((6 + 7)) * 2", "(6 + 7) * 2"),
+            ("(2 * 3) * 4 - (5 - 6)", "2 * 3 * 4 - (5 - 6)"),
+            ("2^(3^2) + (2^3)^2", "2^3^2 + (2^3)^2"),
+            ("-(x^2) + (-x)^2 - -(-x)", "-x^2 + (-x)^2 - -(-x)"),
+            ("(not (x < 1)) and (y or z)", "not x < 1 and (y or z)"),
+            ("(a := x + 1) -> b", "(a := x + 1) -> b"),
+            ("[1, (2 + 3)]", "[[1, 2 + 3]]"),
+        ] {
+            assert_eq!(
+                parse_str(expression, &mut session)
+                    .unwrap()
+                    .to_source_string(&session),
+                source
+            );
+        }
+
+        // Numbers are written out exactly
+        let third = Token::Number(Number::one().divide(&Number::from_i64(3)));
+
+        assert_eq!(third.to_source_string(&session), "1/3");
+        assert_eq!(
+            Token::Number(Number::from_str("2.125").unwrap()).to_source_string(&session),
+            "2.125"
+        );
+
+        // Negative numbers, fractions and complex numbers parse back into expressions that work out to the same value
+        for (expression, source) in [
+            ("-4", "-4"),
+            ("4/3", "4/3"),
+            ("sqrt(-4)", "2 * sqrt(-1)"),
+            ("-sqrt(-1/9)", "-1/3 * sqrt(-1)"),
+            ("1 - sqrt(-4)", "1 - 2 * sqrt(-1)"),
+            ("[[-1, 2]]", "[[-1, 2]]"),
+        ] {
+            let value =
+                op_engine::simplify(&parse_str(expression, &mut session).unwrap(), &mut session)
+                    .unwrap();
+
+            assert_eq!(value.to_source_string(&session), source);
+            assert_eq!(
+                op_engine::simplify(&parse_str(source, &mut session).unwrap(), &mut session)
+                    .unwrap(),
+                value
+            );
+        }
+
+        assert_eq!(
+            Token::Exponent(
+                Box::new(Token::Number(Number::from_i64(-3))),
+                Box::new(Token::Number(Number::from_i64(2)))
+            )
+            .to_source_string(&session),
+            "(-3)^2"
+        );
+    }
+
+    // Test to make sure random expressions parse back into the same tokens from their source strings
+    #[test]
+    fn test_parser_source_string_round_trip() {
+        let mut session = Session::_new_test().unwrap();
+
+        session
+            .vartable
+            .add(Variable::new("v", Token::Number(Number::from_i64(5))))
+            .unwrap();
+
+        let entry = HistoryEntry::new(&Token::Number(Number::from_i64(7)), &session);
+        let answer = entry.entry_uuid;
+
+        session.add_entry(&entry);
+
+        // Xorshift, so the expressions are random but the same every run
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut random = |bound: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+
+            state % bound
+        };
+
+        fn random_number(random: &mut dyn FnMut(u64) -> u64) -> Number {
+            let number = match random(3) {
+                0 => Number::from_i64(random(100) as i64),
+                1 => Number::from_str(&format!("{}.25", random(10))).unwrap(),
+                // Thirds and sevenths never end as a decimal
+                _ => Number::from_i64(random(100) as i64 + 1)
+                    .divide(&Number::from_i64([3, 7][random(2) as usize])),
+            };
+
+            match random(3) {
+                0 => number.negative(),
+                _ => number,
+            }
+        }
+
+        fn random_token(random: &mut dyn FnMut(u64) -> u64, depth: u32, answer: &Uuid) -> Token {
+            let sub = |random: &mut dyn FnMut(u64) -> u64| {
+                Box::new(random_token(random, depth - 1, answer))
+            };
+
+            if depth == 0 {
+                return match random(8) {
+                    0 | 1 => Token::Number(random_number(random)),
+                    2 => Token::Complex(Complex::new(random_number(random), {
+                        let imaginary = random_number(random);
+
+                        match imaginary.is_zero() {
+                            true => Number::neg_one(),
+                            false => imaginary,
+                        }
+                    })),
+                    3 => Token::Symbol(["x", "y", "z"][random(3) as usize].to_string()),
+                    4 => Token::Variable(Box::new(Variable::new(
+                        "v",
+                        Token::Number(Number::from_i64(5)),
+                    ))),
+                    5 => Token::Answer(*answer),
+                    6 => Token::Constant("pi".to_string()),
+                    _ => Token::Boolean(random(2) == 0),
+                };
+            }
+
+            match random(22) {
+                0 => Token::Exponent(sub(random), sub(random)),
+                1 => Token::Multiply(sub(random), sub(random)),
+                2 => Token::Divide(sub(random), sub(random)),
+                3 => Token::Add(sub(random), sub(random)),
+                4 => Token::Subtract(sub(random), sub(random)),
+                5 => Token::Equality(sub(random), sub(random)),
+                6 => Token::Less(sub(random), sub(random)),
+                7 => Token::Greater(sub(random), sub(random)),
+                8 => Token::LessEqual(sub(random), sub(random)),
+                9 => Token::GreaterEqual(sub(random), sub(random)),
+                10 => Token::NotEqual(sub(random), sub(random)),
+                11 => Token::And(sub(random), sub(random)),
+                12 => Token::Or(sub(random), sub(random)),
+                13 => Token::Xor(sub(random), sub(random)),
+                14 => Token::Not(sub(random)),
+                15 => Token::Negative(sub(random)),
+                16 => Token::Parenthesis(sub(random)),
+                17 => Token::Store("a".to_string(), sub(random)),
+                18 => Token::Formula("b".to_string(), sub(random)),
+                19 => Token::Function("sin".to_string(), vec![*sub(random)]),
+                20 => Token::Function(
+                    "if".to_string(),
+                    vec![*sub(random), *sub(random), *sub(random)],
+                ),
+                _ => Token::Matrix(vec![
+                    vec![*sub(random), *sub(random)],
+                    vec![*sub(random), *sub(random)],
+                ]),
+            }
+        }
+
+        // Negative numbers parse as a negative of a number, fractions as a division and complex numbers as a sum with a multiple
+        // of sqrt(-1), so those get folded back into single numbers on both sides before comparing
+        fn normalized(token: &Token) -> Token {
+            token.without_parenthesis().replace(&|token| match token {
+                Token::Negative(expression) => match normalized(expression) {
+                    Token::Number(number) => Some(Token::Number(number.negative())),
+                    Token::Complex(complex) => Some(Token::Complex(
+                        Complex::from_real(Number::zero()).subtract(&complex),
+                    )),
+                    _ => None,
+                },
+                Token::Divide(left, right) => match (normalized(left), normalized(right)) {
+                    (Token::Number(left), Token::Number(right)) if !right.is_zero() => {
+                        Some(Token::Number(left.divide(&right)))
+                    }
+                    _ => None,
+                },
+                Token::Function(id, args) if id == "sqrt" => match normalized(&args[0]) {
+                    Token::Number(number) if number == Number::neg_one() => {
+                        Some(Token::Complex(Complex::new(Number::zero(), Number::one())))
+                    }
+                    _ => None,
+                },
+                Token::Multiply(left, right) => match (normalized(left), normalized(right)) {
+                    (Token::Number(left), Token::Complex(right)) if right.real.is_zero() => {
+                        Some(Token::Complex(Complex::from_real(left).multiply(&right)))
+                    }
+                    _ => None,
+                },
+                Token::Add(left, right) => match (normalized(left), normalized(right)) {
+                    (Token::Number(left), Token::Complex(right)) if right.real.is_zero() => {
+                        Some(Token::Complex(Complex::from_real(left).add(&right)))
+                    }
+                    _ => None,
+                },
+                Token::Subtract(left, right) => match (normalized(left), normalized(right)) {
+                    (Token::Number(left), Token::Complex(right)) if right.real.is_zero() => {
+                        Some(Token::Complex(Complex::from_real(left).subtract(&right)))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            })
+        }
+
+        for _ in 0..2000 {
+            let depth = random(5) as u32;
+            let expression = random_token(&mut random, depth, &answer);
+            let source = expression.to_source_string(&session);
+
+            let parsed = match parse_str(&source, &mut session) {
+                Ok(parsed) => parsed,
+                Err(error) => panic!("{} didn't parse: {}", source, error),
+            };
+
+            assert_eq!(
+                normalized(&parsed),
+                normalized(&expression),
+                "{} parsed into something else",
+                source
+            );
+        }
+    }
+
     // Test to make sure the parser can reject bad variable names
     #[test]
     fn test_parser_var_name_check() {
         let mut session = Session::_new_test().unwrap();
 
-        for opcode in ORDER_OF_OPS {
-            let bad_variable: String = format!("2->foo{}bar", opcode);
+        for group in ORDER_OF_OPS {
+            for opcode in group {
+                let bad_variable: String = format!("2->foo{}bar", opcode);
 
-            parse_str(&bad_variable, &mut session).unwrap_err();
+                parse_str(&bad_variable, &mut session).unwrap_err();
+            }
         }
     }
 
@@ -475,6 +2016,55 @@ mod tests {
         assert_eq!(tokenized_expression_ref, tokenized_expression_res);
     }
 
+    // Test to make sure the parser can recognize matrices and vectors
+    #[test]
+    fn test_parser_matrix() {
+        let mut session = Session::_new_test().unwrap();
+
+        let number = |string| Token::Number(Number::from_str(string).unwrap());
+
+        let tokenized_expression_ref = Token::Matrix(vec![
+            vec![number("1"), Token::Negative(Box::new(number("2")))],
+            vec![number("3"), number("4")],
+        ]);
+
+        let tokenized_expression_res = parse_str("[[1, -2], [3, 4]]", &mut session).unwrap();
+
+        assert_eq!(tokenized_expression_ref, tokenized_expression_res);
+
+        // A vector is a matrix with a single row
+        let tokenized_expression_ref = Token::Matrix(vec![vec![number("1"), number("2")]]);
+
+        let tokenized_expression_res = parse_str("[1, 2]", &mut session).unwrap();
+
+        assert_eq!(tokenized_expression_ref, tokenized_expression_res);
+
+        // Rows must all be the same length
+        parse_str("[[1, 2], [3]]", &mut session).unwrap_err();
+        parse_str("[[1, 2], [3, 4]", &mut session).unwrap_err();
+    }
+
+    // Test to make sure the parser can recognize function calls
+    #[test]
+    fn test_parser_function() {
+        let mut session = Session::_new_test().unwrap();
+
+        let tokenized_expression_ref = Token::Function(
+            "det".to_string(),
+            vec![Token::Matrix(vec![vec![Token::Number(
+                Number::from_str(TWO).unwrap(),
+            )]])],
+        );
+
+        let tokenized_expression_res = parse_str("det([2])", &mut session).unwrap();
+
+        assert_eq!(tokenized_expression_ref, tokenized_expression_res);
+
+        // Unknown functions and the wrong number of arguments should be rejected
+        parse_str("foo(2)", &mut session).unwrap_err();
+        parse_str("det([2], [2])", &mut session).unwrap_err();
+    }
+
     // Test to make sure the parser can recognize equality operations
     #[test]
     fn test_parser_eql() {
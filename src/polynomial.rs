@@ -0,0 +1,673 @@
+//! Built-in polynomial type and polynomial root finding.
+//!
+
+// Copyright (c) 2022 Charles M. Thompson
+//
+// This file is part of ApeCrunch.
+//
+// ApeCrunch is free software: you can redistribute it and/or modify it under
+// the terms only of version 3 of the GNU General Public License as published
+// by the Free Software Foundation
+//
+// ApeCrunch is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License
+// for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// ApeCrunch(in a file named COPYING).
+// If not, see <https://www.gnu.org/licenses/>.
+
+use crate::complex::Complex;
+use crate::number::Number;
+use crate::op_engine;
+use crate::parser::Token;
+use crate::session::Session;
+use simple_error::*;
+use std::cmp::Ordering;
+use std::error::Error;
+
+/// Maximum number of Durand-Kerner iterations before giving up on finding roots.
+///
+/// **NOT PUBLIC.**
+///
+const MAX_ROOT_ITERATIONS: usize = 1000;
+
+/// Limit on the Durand-Kerner iterations times the square of the degree, since every iteration of a higher degree polynomial does that much
+/// more work.
+///
+/// **NOT PUBLIC.**
+///
+const MAX_ROOT_WORK: usize = 100_000;
+
+/// Highest degree of polynomial roots can be found for.
+///
+pub const MAX_DEGREE: usize = 32;
+
+/// Extra decimal places carried while iterating, so rounding doesn't creep into the displayed digits.
+///
+/// **NOT PUBLIC.**
+///
+const ROOT_GUARD_DIGITS: u32 = 10;
+
+/// Polynomial in a single symbol with exact coefficients.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polynomial {
+    /// Coefficients, starting with the constant term. Never has trailing zeros, so the zero polynomial has no coefficients.
+    coefficients: Vec<Number>,
+}
+
+impl Polynomial {
+    /// Creates a polynomial from its coefficients, starting with the constant term.
+    ///
+    pub fn new(coefficients: Vec<Number>) -> Self {
+        let mut polynomial = Self { coefficients };
+
+        polynomial.trim();
+
+        polynomial
+    }
+
+    /// Creates a polynomial that's just a constant.
+    ///
+    pub fn constant(number: Number) -> Self {
+        Self::new(vec![number])
+    }
+
+    /// Creates the polynomial x, where x is whatever symbol the polynomial is in.
+    ///
+    pub fn symbol() -> Self {
+        Self::new(vec![Number::zero(), Number::one()])
+    }
+
+    /// Converts an expression into a polynomial in the given symbol.
+    ///
+    /// Anything that doesn't contain the symbol is simplified down to a constant. Returns a simple error if the expression isn't a polynomial.
+    ///
+    pub fn from_token(
+        token: &Token,
+        symbol: &str,
+        session: &mut Session,
+    ) -> Result<Self, Box<dyn Error>> {
+        // Anything without the symbol in it is just a constant
+        if !token.contains_symbol(symbol) {
+            return match op_engine::simplify(token, session)? {
+                Token::Number(number) => Ok(Self::constant(number)),
+                _ => bail!(
+                    "Coefficients of a polynomial must be numbers: {}",
+                    token.to_string(session)
+                ),
+            };
+        }
+
+        match token {
+            Token::Symbol(_) => Ok(Self::symbol()),
+            Token::Add(left, right) => Ok(Self::from_token(left, symbol, session)?
+                .add(&Self::from_token(right, symbol, session)?)),
+            Token::Subtract(left, right) => Ok(Self::from_token(left, symbol, session)?
+                .subtract(&Self::from_token(right, symbol, session)?)),
+            Token::Multiply(left, right) => Ok(Self::from_token(left, symbol, session)?
+                .multiply(&Self::from_token(right, symbol, session)?)),
+            Token::Divide(left, right) => {
+                let divisor = Self::from_token(right, symbol, session)?;
+
+                match divisor.degree() {
+                    Some(0) => Ok(Self::from_token(left, symbol, session)?
+                        .scale(&Number::one().divide(&divisor.coefficients[0]))),
+                    _ => bail!(
+                        "Polynomials can only be divided by constants: {}",
+                        token.to_string(session)
+                    ),
+                }
+            }
+            Token::Exponent(left, right) => {
+                let power = match right.contains_symbol(symbol) {
+                    true => None,
+                    false => match op_engine::simplify(right, session)? {
+                        Token::Number(number) => number.to_i64(),
+                        _ => None,
+                    },
+                };
+
+                match power {
+                    Some(power) if power >= 0 => {
+                        Ok(Self::from_token(left, symbol, session)?.pow(power as u64))
+                    }
+                    _ => bail!(
+                        "Polynomials can only be raised to whole number powers: {}",
+                        token.to_string(session)
+                    ),
+                }
+            }
+            Token::Negative(expression) => {
+                Ok(Self::from_token(expression, symbol, session)?.scale(&Number::neg_one()))
+            }
+            Token::Parenthesis(expression) => Self::from_token(expression, symbol, session),
+            _ => bail!("Not a polynomial: {}", token.to_string(session)),
+        }
+    }
+
+    /// Gets the degree of the polynomial, None for the zero polynomial.
+    ///
+    pub fn degree(&self) -> Option<usize> {
+        self.coefficients.len().checked_sub(1)
+    }
+
+    /// Gets the coefficients, starting with the constant term.
+    ///
+    pub fn coefficients(&self) -> &[Number] {
+        &self.coefficients
+    }
+
+    /// Adds this polynomial to another polynomial.
+    ///
+    pub fn add(&self, other: &Polynomial) -> Polynomial {
+        let length = self.coefficients.len().max(other.coefficients.len());
+        let zero = Number::zero();
+
+        Self::new(
+            (0..length)
+                .map(|i| {
+                    let left = self.coefficients.get(i).unwrap_or(&zero);
+                    let right = other.coefficients.get(i).unwrap_or(&zero);
+
+                    left.add(right)
+                })
+                .collect(),
+        )
+    }
+
+    /// Subtracts a polynomial from this polynomial.
+    ///
+    pub fn subtract(&self, other: &Polynomial) -> Polynomial {
+        self.add(&other.scale(&Number::neg_one()))
+    }
+
+    /// Multiplies this polynomial by another polynomial.
+    ///
+    pub fn multiply(&self, other: &Polynomial) -> Polynomial {
+        if self.coefficients.is_empty() || other.coefficients.is_empty() {
+            return Self::new(Vec::new());
+        }
+
+        let mut coefficients =
+            vec![Number::zero(); self.coefficients.len() + other.coefficients.len() - 1];
+
+        for (i, left) in self.coefficients.iter().enumerate() {
+            for (j, right) in other.coefficients.iter().enumerate() {
+                coefficients[i + j] = coefficients[i + j].add(&left.multiply(right));
+            }
+        }
+
+        Self::new(coefficients)
+    }
+
+    /// Multiplies every coefficient by a number.
+    ///
+    pub fn scale(&self, scalar: &Number) -> Polynomial {
+        Self::new(
+            self.coefficients
+                .iter()
+                .map(|coefficient| coefficient.multiply(scalar))
+                .collect(),
+        )
+    }
+
+    /// Raises the polynomial to a whole number power.
+    ///
+    pub fn pow(&self, power: u64) -> Polynomial {
+        let mut result = Self::constant(Number::one());
+
+        for _ in 0..power {
+            result = result.multiply(self);
+        }
+
+        result
+    }
+
+    /// Gets the derivative of the polynomial.
+    ///
+    pub fn derivative(&self) -> Polynomial {
+        Self::new(
+            self.coefficients
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(power, coefficient)| coefficient.multiply(&Number::from_i64(power as i64)))
+                .collect(),
+        )
+    }
+
+    /// Divides this polynomial by another, returning the quotient and the remainder.
+    ///
+    /// Returns a simple error when dividing by the zero polynomial.
+    ///
+    pub fn div_rem(
+        &self,
+        divisor: &Polynomial,
+    ) -> Result<(Polynomial, Polynomial), Box<dyn Error>> {
+        let divisor_degree = match divisor.degree() {
+            Some(degree) => degree,
+            None => bail!("Cannot divide by the zero polynomial!"),
+        };
+
+        let divisor_leading = &divisor.coefficients[divisor_degree];
+        let mut remainder = self.clone();
+        let mut quotient = vec![Number::zero(); self.coefficients.len()];
+
+        while let Some(degree) = remainder.degree() {
+            if degree < divisor_degree {
+                break;
+            }
+
+            // Cancel out the leading term of the remainder
+            let factor = remainder.coefficients[degree].divide(divisor_leading);
+            let shift = degree - divisor_degree;
+
+            quotient[shift] = factor.clone();
+
+            for (i, coefficient) in divisor.coefficients.iter().enumerate() {
+                remainder.coefficients[shift + i] =
+                    remainder.coefficients[shift + i].subtract(&coefficient.multiply(&factor));
+            }
+
+            remainder.trim();
+        }
+
+        Ok((Self::new(quotient), remainder))
+    }
+
+    /// Gets the monic greatest common divisor of two polynomials, through euclid's algorithm.
+    ///
+    pub fn gcd(&self, other: &Polynomial) -> Polynomial {
+        let mut a = self.clone();
+        let mut b = other.clone();
+
+        while b.degree().is_some() {
+            let (_, remainder) = a.div_rem(&b).unwrap(); // b is never the zero polynomial here
+
+            a = b;
+            b = remainder;
+        }
+
+        a.monic()
+    }
+
+    /// Scales the polynomial so the leading coefficient is one.
+    ///
+    pub fn monic(&self) -> Polynomial {
+        match self.coefficients.last() {
+            Some(leading) => self.scale(&Number::one().divide(leading)),
+            None => self.clone(),
+        }
+    }
+
+    /// Evaluates the polynomial at a complex number, through horner's method.
+    ///
+    pub fn evaluate(&self, x: &Complex) -> Complex {
+        let mut result = Complex::from_real(Number::zero());
+
+        for coefficient in self.coefficients.iter().rev() {
+            result = result
+                .multiply(x)
+                .add(&Complex::from_real(coefficient.clone()));
+        }
+
+        result
+    }
+
+    /// Splits the polynomial into square-free factors along with how many times each is repeated, through yun's algorithm.
+    ///
+    /// For example (x-1)^2 * (x+2) splits into [(x+2, 1), (x-1, 2)].
+    ///
+    pub fn square_free_factors(&self) -> Vec<(Polynomial, usize)> {
+        let mut factors = Vec::<(Polynomial, usize)>::new();
+
+        if self.degree().unwrap_or(0) == 0 {
+            return factors;
+        }
+
+        let mut c = self.gcd(&self.derivative());
+        let mut w = self.div_rem(&c).unwrap().0; // c is never zero, it divides a non-zero polynomial
+        let mut multiplicity = 1;
+
+        while c.degree().unwrap_or(0) > 0 {
+            let y = w.gcd(&c);
+            let z = w.div_rem(&y).unwrap().0;
+
+            if z.degree().unwrap_or(0) > 0 {
+                factors.push((z.monic(), multiplicity));
+            }
+
+            w = y.clone();
+            c = c.div_rem(&y).unwrap().0;
+            multiplicity += 1;
+        }
+
+        if w.degree().unwrap_or(0) > 0 {
+            factors.push((w.monic(), multiplicity));
+        }
+
+        factors
+    }
+
+    /// Finds every real and complex root of the polynomial, repeated roots included.
+    ///
+    /// Linear and quadratic factors are solved exactly with the usual formulas, square roots aside. Anything of a higher degree is solved
+    /// numerically with the Durand-Kerner method, good to the given number of decimal places.
+    ///
+    /// Real roots are returned first, smallest to largest, followed by the complex roots.
+    ///
+    pub fn roots(&self, decimal_places: u32) -> Result<Vec<Complex>, Box<dyn Error>> {
+        match self.degree() {
+            None => bail!("Every number is a root of zero!"),
+            Some(0) => bail!("Constants have no roots!"),
+            Some(degree) if degree > MAX_DEGREE => bail!(
+                "Can only find the roots of polynomials up to degree {}!",
+                MAX_DEGREE
+            ),
+            _ => {}
+        }
+
+        let mut roots = Vec::<Complex>::new();
+
+        // Repeated roots make the numeric methods crawl, so every factor is solved without them
+        for (factor, multiplicity) in self.square_free_factors() {
+            for root in factor.square_free_roots(decimal_places)? {
+                for _ in 0..multiplicity {
+                    roots.push(root.clone());
+                }
+            }
+        }
+
+        roots.sort_by(|a, b| {
+            a.is_real()
+                .cmp(&b.is_real())
+                .reverse()
+                .then(a.real.partial_cmp(&b.real).unwrap_or(Ordering::Equal))
+                .then(
+                    a.imaginary
+                        .partial_cmp(&b.imaginary)
+                        .unwrap_or(Ordering::Equal),
+                )
+        });
+
+        Ok(roots)
+    }
+
+    /// Finds the roots of a polynomial that has no repeated roots.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn square_free_roots(&self, decimal_places: u32) -> Result<Vec<Complex>, Box<dyn Error>> {
+        let c = &self.coefficients;
+
+        match self.degree() {
+            Some(1) => Ok(vec![Complex::from_real(c[0].negative().divide(&c[1]))]),
+            Some(2) => Ok(Self::quadratic_roots(&c[2], &c[1], &c[0], decimal_places)),
+            Some(_) => self.durand_kerner(decimal_places),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Solves ax^2 + bx + c = 0 with the quadratic formula.
+    ///
+    /// Exact if the discriminant is a perfect square, otherwise the square root is taken to the given number of decimal places.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn quadratic_roots(a: &Number, b: &Number, c: &Number, decimal_places: u32) -> Vec<Complex> {
+        let discriminant = b
+            .multiply(b)
+            .subtract(&Number::from_i64(4).multiply(a).multiply(c));
+        let two_a = Number::from_i64(2).multiply(a);
+        let vertex = b.negative().divide(&two_a);
+
+        let sqrt = match discriminant.abs().exact_sqrt() {
+            Some(sqrt) => sqrt,
+            None => discriminant
+                .abs()
+                .root(&Number::from_i64(2), decimal_places),
+        };
+
+        let offset = sqrt.divide(&two_a).abs();
+
+        match discriminant.is_negative() {
+            true => vec![
+                Complex::new(vertex.clone(), offset.negative()),
+                Complex::new(vertex, offset),
+            ],
+            false => vec![
+                Complex::from_real(vertex.subtract(&offset)),
+                Complex::from_real(vertex.add(&offset)),
+            ],
+        }
+    }
+
+    /// Finds every root of a polynomial at once through the Durand-Kerner method.
+    ///
+    /// Roots that land within rounding of a simple exact root are snapped onto it.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn durand_kerner(&self, decimal_places: u32) -> Result<Vec<Complex>, Box<dyn Error>> {
+        let degree = self.degree().unwrap_or(0);
+        let monic = self.monic();
+        let working_places = decimal_places + ROOT_GUARD_DIGITS;
+        let tolerance = Number::from_i64(10).exponent(
+            &Number::from_i64(-(decimal_places as i64) - 3),
+            decimal_places,
+        );
+
+        // Start from the powers of a number that is neither real nor a root of unity, as is tradition
+        let seed = Complex::new(
+            Number::from_str("0.4").unwrap(),
+            Number::from_str("0.9").unwrap(),
+        );
+
+        let mut roots = vec![Complex::from_real(Number::one())];
+
+        for i in 1..degree {
+            roots.push(roots[i - 1].multiply(&seed));
+        }
+
+        let mut converged = false;
+
+        let iterations = (MAX_ROOT_WORK / (degree * degree)).min(MAX_ROOT_ITERATIONS);
+
+        for _ in 0..iterations {
+            let mut largest_move = Number::zero();
+
+            for i in 0..degree {
+                let mut divisor = Complex::from_real(Number::one());
+
+                // Exact products grow hundreds of digits long every iteration, so they're rounded as they go, keeping an extra digit per digit
+                // of the root for every power so the rounding isn't blown up by the later multiplications
+                let magnitude_digits = roots[i].manhattan_norm().to_string(0).len() as u32;
+                let product_places = working_places + degree as u32 * magnitude_digits;
+
+                for (j, other) in roots.iter().enumerate() {
+                    if i != j {
+                        divisor = divisor
+                            .multiply(&roots[i].subtract(other))
+                            .approximate(product_places);
+                    }
+                }
+
+                if divisor.is_zero() {
+                    continue;
+                }
+
+                let mut value = Complex::from_real(Number::zero());
+
+                for coefficient in monic.coefficients.iter().rev() {
+                    value = value
+                        .multiply(&roots[i])
+                        .add(&Complex::from_real(coefficient.clone()))
+                        .approximate(product_places);
+                }
+
+                let step = value.divide(&divisor);
+
+                roots[i] = roots[i].subtract(&step).approximate(working_places);
+
+                let step_size = step.manhattan_norm();
+
+                if step_size > largest_move {
+                    largest_move = step_size;
+                }
+            }
+
+            if largest_move < tolerance {
+                converged = true;
+                break;
+            }
+        }
+
+        if !converged {
+            bail!("Could not find the roots of the polynomial, the root finder didn't converge!");
+        }
+
+        Ok(roots
+            .iter()
+            .map(|root| {
+                // Imaginary parts lost in the rounding are really real roots
+                let root = match root.imaginary.abs() < tolerance {
+                    true => Complex::from_real(root.real.clone()),
+                    false => root.clone(),
+                };
+
+                // If rounding the root gives something that is exactly a root, it's a much nicer answer
+                let rounded = Complex::new(
+                    root.real.round(decimal_places),
+                    root.imaginary.round(decimal_places),
+                );
+
+                match monic.evaluate(&rounded).is_zero() {
+                    true => rounded,
+                    false => root,
+                }
+            })
+            .collect())
+    }
+
+    /// Removes trailing zero coefficients.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn trim(&mut self) {
+        while let Some(coefficient) = self.coefficients.last() {
+            if !coefficient.is_zero() {
+                break;
+            }
+
+            self.coefficients.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn polynomial(coefficients: &[i64]) -> Polynomial {
+        Polynomial::new(
+            coefficients
+                .iter()
+                .map(|coefficient| Number::from_i64(*coefficient))
+                .collect(),
+        )
+    }
+
+    fn render(roots: &[Complex]) -> Vec<String> {
+        roots.iter().map(|root| root.to_string(6)).collect()
+    }
+
+    #[test]
+    fn test_polynomial_div_rem_gcd() {
+        // (x^2 - 1) / (x - 1) = x + 1
+        let (quotient, remainder) = polynomial(&[-1, 0, 1])
+            .div_rem(&polynomial(&[-1, 1]))
+            .unwrap();
+
+        assert_eq!(quotient, polynomial(&[1, 1]));
+        assert_eq!(remainder, polynomial(&[]));
+
+        // gcd((x-1)(x+2), (x-1)(x+3)) = x - 1
+        let gcd = polynomial(&[-2, 1, 1]).gcd(&polynomial(&[-3, 2, 1]));
+
+        assert_eq!(gcd, polynomial(&[-1, 1]));
+    }
+
+    #[test]
+    fn test_polynomial_square_free_factors() {
+        // (x-1)^2 * (x+2) = x^3 - 3x + 2
+        let factors = polynomial(&[2, -3, 0, 1]).square_free_factors();
+
+        assert_eq!(
+            factors,
+            vec![(polynomial(&[2, 1]), 1), (polynomial(&[-1, 1]), 2)]
+        );
+    }
+
+    #[test]
+    fn test_polynomial_roots_exact() {
+        // x^2 - 3x + 2
+        assert_eq!(
+            render(&polynomial(&[2, -3, 1]).roots(6).unwrap()),
+            ["1", "2"]
+        );
+
+        // x^2 + 2x + 5
+        assert_eq!(
+            render(&polynomial(&[5, 2, 1]).roots(6).unwrap()),
+            ["-1 - 2i", "-1 + 2i"]
+        );
+
+        // 2x - 1
+        assert_eq!(render(&polynomial(&[-1, 2]).roots(6).unwrap()), ["0.5"]);
+
+        // (x-1)^3
+        assert_eq!(
+            render(&polynomial(&[-1, 3, -3, 1]).roots(6).unwrap()),
+            ["1", "1", "1"]
+        );
+
+        polynomial(&[5]).roots(6).unwrap_err();
+    }
+
+    #[test]
+    fn test_polynomial_roots_numeric() {
+        // x^3 - 2, one real root and two complex ones
+        let roots = render(&polynomial(&[-2, 0, 0, 1]).roots(6).unwrap());
+
+        assert_eq!(
+            roots,
+            [
+                "1.259921...",
+                "-0.62996... - 1.091123...i",
+                "-0.62996... + 1.091123...i"
+            ]
+        );
+
+        // (x-1)(x-2)(x-3)(x-4) snaps onto exact roots
+        let roots = render(&polynomial(&[24, -50, 35, -10, 1]).roots(6).unwrap());
+
+        assert_eq!(roots, ["1", "2", "3", "4"]);
+
+        // x^6 - 1, the sixth roots of unity
+        assert_eq!(
+            polynomial(&[-1, 0, 0, 0, 0, 0, 1]).roots(6).unwrap().len(),
+            6
+        );
+
+        // Past the highest degree is an error rather than taking forever
+        let mut coefficients = vec![0; MAX_DEGREE + 2];
+
+        coefficients[0] = -1;
+        coefficients[MAX_DEGREE + 1] = 1;
+
+        polynomial(&coefficients).roots(6).unwrap_err();
+    }
+}
@@ -0,0 +1,634 @@
+//! Renderers for tokens beyond the single line Token::to_string.
+//!
+
+// Copyright (c) 2022 Charles M. Thompson
+//
+// This file is part of ApeCrunch.
+//
+// ApeCrunch is free software: you can redistribute it and/or modify it under
+// the terms only of version 3 of the GNU General Public License as published
+// by the Free Software Foundation
+//
+// ApeCrunch is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License
+// for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// ApeCrunch(in a file named COPYING).
+// If not, see <https://www.gnu.org/licenses/>.
+
+use crate::complex::Complex;
+use crate::number::Number;
+use crate::parser::operator_symbol;
+use crate::parser::Token;
+use crate::session::Session;
+
+/// Functions LaTeX has a command for, written as \sin rather than \operatorname{sin}.
+///
+/// **NOT PUBLIC.**
+///
+const LATEX_FUNCTIONS: [&str; 6] = ["cos", "det", "exp", "ln", "sin", "tan"];
+
+impl Token {
+    /// Renders the expression across as many lines as it needs, drawing matrices as grids.
+    ///
+    /// Expressions without matrices render to exactly the same single line as to_string().
+    ///
+    pub fn to_multiline_string(&self, session: &Session) -> String {
+        TextBlock::from_token(self, session).lines.join("\n")
+    }
+
+    /// Renders the expression as LaTeX math, without the surrounding dollar signs.
+    ///
+    /// Divisions become fractions, sqrt becomes a root and matrices become bmatrix environments.
+    ///
+    pub fn to_latex(&self, session: &Session) -> String {
+        let operand = |token: &Token, precedence: usize| {
+            let latex = token.to_latex(session);
+
+            match token.display_precedence() < precedence {
+                true => format!("\\left({}\\right)", latex),
+                false => latex,
+            }
+        };
+
+        let binary = |left: &Token, operator: &str, right: &Token| {
+            let (left_precedence, right_precedence) = self.operand_precedences();
+
+            format!(
+                "{}{}{}",
+                operand(left, left_precedence),
+                operator,
+                operand(right, right_precedence)
+            )
+        };
+
+        let list = |tokens: &[Token], separator: &str| {
+            let latex: Vec<String> = tokens.iter().map(|token| token.to_latex(session)).collect();
+
+            latex.join(separator)
+        };
+
+        match self {
+            Token::Exponent(left, right) => format!(
+                "{}^{{{}}}",
+                operand(left, self.precedence() + 1),
+                right.to_latex(session)
+            ),
+            Token::Multiply(left, right) if left.is_coefficient_of(right) => {
+                format!("{}{}", left.to_latex(session), right.to_latex(session))
+            }
+            Token::Multiply(left, right) => binary(left, " \\cdot ", right),
+            Token::Divide(left, right) => format!(
+                "\\frac{{{}}}{{{}}}",
+                left.to_latex(session),
+                right.to_latex(session)
+            ),
+            Token::Add(left, right) => binary(left, " + ", right),
+            Token::Subtract(left, right) => binary(left, " - ", right),
+            Token::Equality(left, right) => binary(left, " = ", right),
+            Token::Less(left, right) => binary(left, " < ", right),
+            Token::Greater(left, right) => binary(left, " > ", right),
+            Token::LessEqual(left, right) => binary(left, " \\leq ", right),
+            Token::GreaterEqual(left, right) => binary(left, " \\geq ", right),
+            Token::NotEqual(left, right) => binary(left, " \\neq ", right),
+            Token::And(left, right) => binary(left, " \\land ", right),
+            Token::Or(left, right) => binary(left, " \\lor ", right),
+            Token::Xor(left, right) => binary(left, " \\oplus ", right),
+            Token::Not(expression) => {
+                format!("\\lnot {}", operand(expression, self.precedence()))
+            }
+            Token::Negative(expression) => {
+                format!("-{}", operand(expression, self.precedence() + 1))
+            }
+            Token::Parenthesis(expression) => expression.to_latex(session),
+            Token::Number(number) => number
+                .to_string(session.decimal_places)
+                .replace("...", "\\ldots"),
+            Token::Complex(complex) => complex_token(complex).to_latex(session),
+            Token::Constant(id) if id == "pi" => "\\pi".to_string(),
+            Token::Constant(id) | Token::Symbol(id) => latex_identifier(id),
+            Token::Variable(variable) => latex_identifier(&variable.id),
+            Token::Boolean(boolean) => format!("\\mathrm{{{}}}", boolean),
+            Token::Answer(_) => format!("\\text{{{}}}", self.to_string(session)),
+            Token::Store(id, expression) => format!(
+                "{} \\rightarrow {}",
+                operand(expression, self.precedence() + 1),
+                latex_identifier(id)
+            ),
+            Token::Formula(id, expression) => {
+                format!(
+                    "{} := {}",
+                    latex_identifier(id),
+                    expression.to_latex(session)
+                )
+            }
+            Token::Function(id, args) if id == "sqrt" => format!("\\sqrt{{{}}}", list(args, ", ")),
+            Token::Function(id, args) if LATEX_FUNCTIONS.contains(&id.as_str()) => {
+                format!("\\{}\\left({}\\right)", id, list(args, ", "))
+            }
+            Token::Function(id, args) => format!(
+                "\\operatorname{{{}}}\\left({}\\right)",
+                id.replace('_', "\\_"),
+                list(args, ", ")
+            ),
+            Token::Matrix(rows) => {
+                let rows: Vec<String> = rows.iter().map(|row| list(row, " & ")).collect();
+
+                format!(
+                    "\\begin{{bmatrix}} {} \\end{{bmatrix}}",
+                    rows.join(" \\\\ ")
+                )
+            }
+        }
+    }
+
+    /// Renders the expression as a MathML math element.
+    ///
+    /// Divisions become fractions, sqrt becomes a root and matrices become tables.
+    ///
+    pub fn to_mathml(&self, session: &Session) -> String {
+        format!(
+            "<math xmlns=\"http://www.w3.org/1998/Math/MathML\">{}</math>",
+            self.to_mathml_element(session)
+        )
+    }
+
+    /// Renders the expression as MathML without the surrounding math element.
+    ///
+    /// **NOT PUBLIC.**
+    ///
+    fn to_mathml_element(&self, session: &Session) -> String {
+        let operand = |token: &Token, precedence: usize| {
+            let mathml = token.to_mathml_element(session);
+
+            match token.display_precedence() < precedence {
+                true => format!("<mrow><mo>(</mo>{}<mo>)</mo></mrow>", mathml),
+                false => mathml,
+            }
+        };
+
+        let binary = |left: &Token, operator: &str, right: &Token| {
+            let (left_precedence, right_precedence) = self.operand_precedences();
+
+            format!(
+                "<mrow>{}<mo>{}</mo>{}</mrow>",
+                operand(left, left_precedence),
+                operator,
+                operand(right, right_precedence)
+            )
+        };
+
+        let list = |tokens: &[Token]| {
+            let mathml: Vec<String> = tokens
+                .iter()
+                .map(|token| token.to_mathml_element(session))
+                .collect();
+
+            mathml.join("<mo>,</mo>")
+        };
+
+        match self {
+            Token::Exponent(left, right) => format!(
+                "<msup>{}{}</msup>",
+                operand(left, self.precedence() + 1),
+                right.to_mathml_element(session)
+            ),
+            // Invisible times, like the 5x in 5x
+            Token::Multiply(left, right) if left.is_coefficient_of(right) => {
+                binary(left, "\u{2062}", right)
+            }
+            Token::Multiply(left, right) => binary(left, "⋅", right),
+            Token::Divide(left, right) => format!(
+                "<mfrac>{}{}</mfrac>",
+                left.to_mathml_element(session),
+                right.to_mathml_element(session)
+            ),
+            Token::Add(left, right) => binary(left, "+", right),
+            Token::Subtract(left, right) => binary(left, "−", right),
+            Token::Equality(left, right) => binary(left, "=", right),
+            Token::Less(left, right) => binary(left, "&lt;", right),
+            Token::Greater(left, right) => binary(left, "&gt;", right),
+            Token::LessEqual(left, right) => binary(left, "≤", right),
+            Token::GreaterEqual(left, right) => binary(left, "≥", right),
+            Token::NotEqual(left, right) => binary(left, "≠", right),
+            Token::And(left, right) => binary(left, "∧", right),
+            Token::Or(left, right) => binary(left, "∨", right),
+            Token::Xor(left, right) => binary(left, "⊕", right),
+            Token::Not(expression) => format!(
+                "<mrow><mo>¬</mo>{}</mrow>",
+                operand(expression, self.precedence())
+            ),
+            Token::Negative(expression) => format!(
+                "<mrow><mo>−</mo>{}</mrow>",
+                operand(expression, self.precedence() + 1)
+            ),
+            Token::Parenthesis(expression) => expression.to_mathml_element(session),
+            Token::Number(number) => mathml_number(number, session),
+            Token::Complex(complex) => complex_token(complex).to_mathml_element(session),
+            Token::Constant(id) if id == "pi" => "<mi>π</mi>".to_string(),
+            Token::Constant(id) | Token::Symbol(id) => format!("<mi>{}</mi>", id),
+            Token::Variable(variable) => format!("<mi>{}</mi>", variable.id),
+            Token::Boolean(boolean) => format!("<mtext>{}</mtext>", boolean),
+            Token::Answer(_) => format!("<mtext>{}</mtext>", self.to_string(session)),
+            Token::Store(id, expression) => format!(
+                "<mrow>{}<mo>→</mo><mi>{}</mi></mrow>",
+                operand(expression, self.precedence() + 1),
+                id
+            ),
+            Token::Formula(id, expression) => format!(
+                "<mrow><mi>{}</mi><mo>≔</mo>{}</mrow>",
+                id,
+                expression.to_mathml_element(session)
+            ),
+            Token::Function(id, args) if id == "sqrt" => format!("<msqrt>{}</msqrt>", list(args)),
+            // Function application, so the parenthesis read as arguments rather than multiplication
+            Token::Function(id, args) => format!(
+                "<mrow><mi>{}</mi><mo>\u{2061}</mo><mrow><mo>(</mo>{}<mo>)</mo></mrow></mrow>",
+                id,
+                list(args)
+            ),
+            Token::Matrix(rows) => {
+                let rows: String = rows
+                    .iter()
+                    .map(|row| {
+                        let entries: String = row
+                            .iter()
+                            .map(|entry| format!("<mtd>{}</mtd>", entry.to_mathml_element(session)))
+                            .collect();
+
+                        format!("<mtr>{}</mtr>", entries)
+                    })
+                    .collect();
+
+                format!("<mrow><mo>[</mo><mtable>{}</mtable><mo>]</mo></mrow>", rows)
+            }
+        }
+    }
+
+    /// Gets the precedence of the token as drawn by the markup renderers, where fractions and numbers don't need the parenthesis they'd need
+    /// written out on a single line.
+    ///
+    /// **NOT PUBLIC.**
+    ///
+    fn display_precedence(&self) -> usize {
+        match self {
+            // Fractions only need parenthesis as the base of a power, so they bind like one
+            Token::Divide(left, right) => Token::Exponent(left.clone(), right.clone()).precedence(),
+            Token::Number(number) if number.is_negative() => {
+                Token::Negative(Box::new(Token::Number(number.abs()))).precedence()
+            }
+            Token::Number(_) => usize::MAX,
+            Token::Complex(complex) => complex_token(complex).display_precedence(),
+            Token::Parenthesis(expression) => expression.display_precedence(),
+            _ => self.precedence(),
+        }
+    }
+
+    /// Returns true if there is a matrix anywhere in the expression.
+    ///
+    /// **NOT PUBLIC.**
+    ///
+    fn contains_matrix(&self) -> bool {
+        match self {
+            Token::Matrix(_) => true,
+            _ => self.children().iter().any(|child| child.contains_matrix()),
+        }
+    }
+}
+
+/// Writes an identifier in LaTeX, in upright letters if it's longer than a single letter so it doesn't read as a product.
+///
+/// **NOT PUBLIC.**
+///
+fn latex_identifier(id: &str) -> String {
+    match id.chars().count() {
+        1 => id.to_string(),
+        _ => format!("\\mathrm{{{}}}", id.replace('_', "\\_")),
+    }
+}
+
+/// Writes a number in MathML, marking a loss of precision with an ellipsis.
+///
+/// **NOT PUBLIC.**
+///
+fn mathml_number(number: &Number, session: &Session) -> String {
+    let digits = number.abs().to_string(session.decimal_places);
+
+    let mathml = match digits.strip_suffix("...") {
+        Some(digits) => format!("<mrow><mn>{}</mn><mo>…</mo></mrow>", digits),
+        None => format!("<mn>{}</mn>", digits),
+    };
+
+    match number.is_negative() {
+        true => format!("<mrow><mo>−</mo>{}</mrow>", mathml),
+        false => mathml,
+    }
+}
+
+/// Builds the tokens a complex number is written as, like 1 + 2i, so the markup renderers can draw it like any other expression.
+///
+/// **NOT PUBLIC.**
+///
+fn complex_token(complex: &Complex) -> Token {
+    let i = Token::Symbol("i".to_string());
+    let real = Box::new(Token::Number(complex.real.clone()));
+
+    let imaginary = match complex.imaginary.abs() == Number::one() {
+        true => Box::new(i),
+        false => Box::new(Token::Multiply(
+            Box::new(Token::Number(complex.imaginary.abs())),
+            Box::new(i),
+        )),
+    };
+
+    match (
+        complex.is_real(),
+        complex.real.is_zero(),
+        complex.imaginary.is_negative(),
+    ) {
+        (true, _, _) => *real,
+        (false, true, false) => *imaginary,
+        (false, true, true) => Token::Negative(imaginary),
+        (false, false, false) => Token::Add(real, imaginary),
+        (false, false, true) => Token::Subtract(real, imaginary),
+    }
+}
+
+/// Rectangular block of text, used to lay out multi-line renditions.
+///
+/// **NOT PUBLIC.**
+///
+#[derive(Debug, Clone)]
+struct TextBlock {
+    /// Lines of the block, all padded to the same width.
+    lines: Vec<String>,
+    /// Line that the block lines up on when placed next to other blocks.
+    baseline: usize,
+}
+
+impl TextBlock {
+    /// Creates a single line block.
+    ///
+    fn line(text: &str) -> Self {
+        Self {
+            lines: vec![text.to_string()],
+            baseline: 0,
+        }
+    }
+
+    /// Width of the block in characters.
+    ///
+    fn width(&self) -> usize {
+        self.lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Places blocks next to each other, lining up their baselines.
+    ///
+    fn join(blocks: &[TextBlock]) -> Self {
+        let above = blocks.iter().map(|block| block.baseline).max().unwrap_or(0);
+        let below = blocks
+            .iter()
+            .map(|block| block.lines.len() - block.baseline - 1)
+            .max()
+            .unwrap_or(0);
+
+        let mut lines = vec![String::new(); above + below + 1];
+
+        for block in blocks {
+            let width = block.width();
+            let offset = above - block.baseline;
+
+            for (i, line) in lines.iter_mut().enumerate() {
+                let text = match i.checked_sub(offset) {
+                    Some(index) => block.lines.get(index).map(String::as_str).unwrap_or(""),
+                    None => "",
+                };
+
+                line.push_str(&format!("{:<width$}", text, width = width));
+            }
+        }
+
+        // Don't leave trailing whitespace from padding blocks on the right
+        for line in lines.iter_mut() {
+            line.truncate(line.trim_end().len());
+        }
+
+        Self {
+            lines,
+            baseline: above,
+        }
+    }
+
+    /// Lays out a token, recursively.
+    ///
+    fn from_token(token: &Token, session: &Session) -> Self {
+        // Only matrices need more than a single line
+        if !token.contains_matrix() {
+            return Self::line(&token.to_string(session));
+        }
+
+        let binary = |left: &Token, op: &str, right: &Token| {
+            Self::join(&[
+                Self::from_token(left, session),
+                Self::line(op),
+                Self::from_token(right, session),
+            ])
+        };
+
+        match token {
+            Token::Exponent(left, right) => binary(left, "^", right),
+            Token::Multiply(left, right) => {
+                binary(left, &format!(" {} ", operator_symbol("*", session)), right)
+            }
+            Token::Divide(left, right) => {
+                binary(left, &format!(" {} ", operator_symbol("/", session)), right)
+            }
+            Token::Add(left, right) => binary(left, " + ", right),
+            Token::Subtract(left, right) => {
+                binary(left, &format!(" {} ", operator_symbol("-", session)), right)
+            }
+            Token::Equality(left, right) => binary(left, " = ", right),
+            Token::Less(left, right) => binary(left, " < ", right),
+            Token::Greater(left, right) => binary(left, " > ", right),
+            Token::LessEqual(left, right) => binary(left, " <= ", right),
+            Token::GreaterEqual(left, right) => binary(left, " >= ", right),
+            Token::NotEqual(left, right) => binary(left, " != ", right),
+            Token::And(left, right) => binary(left, " and ", right),
+            Token::Or(left, right) => binary(left, " or ", right),
+            Token::Xor(left, right) => binary(left, " xor ", right),
+            Token::Not(expression) => {
+                Self::join(&[Self::line("not "), Self::from_token(expression, session)])
+            }
+            Token::Parenthesis(expression) => Self::join(&[
+                Self::line("( "),
+                Self::from_token(expression, session),
+                Self::line(" )"),
+            ]),
+            Token::Negative(expression) => Self::join(&[
+                Self::line(operator_symbol("-", session)),
+                Self::from_token(expression, session),
+            ]),
+            Token::Store(id, expression) => Self::join(&[
+                Self::from_token(expression, session),
+                Self::line(&format!(" -> {}", id)),
+            ]),
+            Token::Formula(id, expression) => Self::join(&[
+                Self::line(&format!("{} := ", id)),
+                Self::from_token(expression, session),
+            ]),
+            Token::Function(id, args) => {
+                let mut blocks = vec![Self::line(&format!("{}(", id))];
+
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        blocks.push(Self::line(", "));
+                    }
+
+                    blocks.push(Self::from_token(arg, session));
+                }
+
+                blocks.push(Self::line(")"));
+
+                Self::join(&blocks)
+            }
+            Token::Matrix(rows) => Self::matrix(rows, session),
+            _ => Self::line(&token.to_string(session)),
+        }
+    }
+
+    /// Lays out a matrix as a grid with right aligned columns and tall brackets.
+    ///
+    fn matrix(rows: &[Vec<Token>], session: &Session) -> Self {
+        let cells: Vec<Vec<String>> = rows
+            .iter()
+            .map(|row| row.iter().map(|entry| entry.to_string(session)).collect())
+            .collect();
+
+        let col_count = cells.first().map(|row| row.len()).unwrap_or(0);
+
+        let col_widths: Vec<usize> = (0..col_count)
+            .map(|col| {
+                cells
+                    .iter()
+                    .map(|row| row[col].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let lines = cells
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let (open, close) = match (i, cells.len()) {
+                    (_, 1) => ("[", "]"),
+                    (0, _) => ("⎡", "⎤"),
+                    (i, len) if i == len - 1 => ("⎣", "⎦"),
+                    _ => ("⎢", "⎥"),
+                };
+
+                let entries: Vec<String> = row
+                    .iter()
+                    .zip(&col_widths)
+                    .map(|(cell, width)| format!("{:>width$}", cell, width = width))
+                    .collect();
+
+                format!("{} {} {}", open, entries.join("  "), close)
+            })
+            .collect();
+
+        Self {
+            lines,
+            baseline: cells.len() / 2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::op_engine;
+    use crate::parser;
+
+    #[test]
+    fn test_render_multiline_matrix() {
+        let mut session = Session::_new_test().unwrap();
+
+        let tokens = parser::parse_str("[[1,2],[3,40]]*2", &mut session).unwrap();
+        let result = op_engine::get_equality(&tokens, &mut session).unwrap();
+
+        let expected = ["⎡ 1   2 ⎤       ⎡ 2   4 ⎤", "⎣ 3  40 ⎦ * 2 = ⎣ 6  80 ⎦"].join("\n");
+
+        assert_eq!(result.to_multiline_string(&session), expected);
+    }
+
+    #[test]
+    fn test_render_latex() {
+        let mut session = Session::_new_test().unwrap();
+
+        for (expression, latex) in [
+            ("3x^2 + (x + 1)/2", "3x^{2} + \\frac{x + 1}{2}"),
+            (
+                "(1/2)^(x - 1) <= -sqrt(pi)",
+                "\\left(\\frac{1}{2}\\right)^{x - 1} \\leq -\\sqrt{\\pi}",
+            ),
+            (
+                "sin(x) * max_speed",
+                "\\sin\\left(x\\right) \\cdot \\mathrm{max\\_speed}",
+            ),
+            (
+                "det([[1, 2], [3, 4]])",
+                "\\det\\left(\\begin{bmatrix} 1 & 2 \\\\ 3 & 4 \\end{bmatrix}\\right)",
+            ),
+        ] {
+            assert_eq!(
+                parser::parse_str(expression, &mut session)
+                    .unwrap()
+                    .to_latex(&session),
+                latex
+            );
+        }
+
+        // Results are rendered too, including the precision lost
+        let tokens = parser::parse_str("2/3", &mut session).unwrap();
+        let result = op_engine::get_equality(&tokens, &mut session).unwrap();
+
+        assert_eq!(result.to_latex(&session), "\\frac{2}{3} = 0.666666\\ldots");
+    }
+
+    #[test]
+    fn test_render_mathml() {
+        let mut session = Session::_new_test().unwrap();
+
+        let tokens = parser::parse_str("sqrt(x^2 - 1)/2 < [1, -2]", &mut session).unwrap();
+
+        assert_eq!(
+            tokens.to_mathml(&session),
+            concat!(
+                "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"><mrow>",
+                "<mfrac><msqrt><mrow><msup><mi>x</mi><mn>2</mn></msup><mo>−</mo><mn>1</mn></mrow></msqrt><mn>2</mn></mfrac>",
+                "<mo>&lt;</mo>",
+                "<mrow><mo>[</mo><mtable><mtr><mtd><mn>1</mn></mtd><mtd><mrow><mo>−</mo><mn>2</mn></mrow></mtd></mtr></mtable><mo>]</mo></mrow>",
+                "</mrow></math>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_render_multiline_no_matrix() {
+        let mut session = Session::_new_test().unwrap();
+
+        let tokens = parser::parse_str("1+2*3", &mut session).unwrap();
+
+        assert_eq!(
+            tokens.to_multiline_string(&session),
+            tokens.to_string(&session)
+        );
+    }
+}
@@ -0,0 +1,137 @@
+//! Plain line-oriented REPL, for terminals the TUI doesn't work on and screen readers.
+//!
+
+// Copyright (c) 2022 Charles M. Thompson
+//
+// This file is part of ApeCrunch.
+//
+// ApeCrunch is free software: you can redistribute it and/or modify it under
+// the terms only of version 3 of the GNU General Public License as published
+// by the Free Software Foundation
+//
+// ApeCrunch is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License
+// for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// ApeCrunch(in a file named COPYING).
+// If not, see <https://www.gnu.org/licenses/>.
+
+use crate::parser;
+use crate::session::Session;
+use rustyline::error::ReadlineError;
+use rustyline::Editor;
+use std::error::Error;
+
+/// Prompt printed in front of every line read.
+///
+/// **NOT PUBLIC.**
+///
+const REPL_PROMPT: &str = "> ";
+
+pub struct Repl {
+    editor: Editor<()>,
+    session: Session,
+}
+
+impl Repl {
+    /// Creates a new Repl instance with a given session.
+    ///
+    /// The expressions of the previous entries can be recalled with the arrow keys.
+    ///
+    /// Returns an error if the terminal can't be set up for line editing.
+    ///
+    pub fn new(session: Session) -> Result<Self, Box<dyn Error>> {
+        let mut editor = Editor::<()>::new()?;
+
+        for entry in session.get_entries() {
+            editor.add_history_entry(entry.render_without_equality(&session));
+        }
+
+        Ok(Self { editor, session })
+    }
+
+    /// Reads and evaluates lines until the end of the input, Ctrl+D on most terminals.
+    ///
+    /// Ctrl+C clears the current line instead of quitting.
+    ///
+    /// Returns an error if the input can't be read.
+    ///
+    pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        loop {
+            let line = match self.editor.readline(REPL_PROMPT) {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => return Ok(()),
+                Err(error) => return Err(Box::new(error)),
+            };
+
+            // Blank lines and comments are ignored, same as in batch mode
+            if parser::split_statements(&line).is_ok_and(|statements| statements.is_empty()) {
+                continue;
+            }
+
+            self.editor.add_history_entry(&line);
+
+            for output in self.submit(&line) {
+                println!("{}", output);
+            }
+        }
+    }
+
+    /// Evaluates a line the same way the TUI's entry bar does, saving the history file afterwards.
+    ///
+    /// Returns the lines to print, every new history entry followed by the error if there was one.
+    ///
+    /// **NOT PUBLIC.**
+    ///
+    fn submit(&mut self, line: &str) -> Vec<String> {
+        let first_index = self.session.get_entries().len();
+
+        // Statements evaluated before an error are kept and saved, same as in the TUI
+        let result = self.session.evaluate_line(line);
+        let saved = self.session.update_file();
+
+        let mut output: Vec<String> = self.session.get_entries()[first_index..]
+            .iter()
+            .map(|entry| entry.to_string())
+            .collect();
+
+        if let Result::Err(error) = result.map_err(Box::<dyn Error>::from).and(saved) {
+            output.push(format!("Error: {}", error));
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::*;
+
+    #[test]
+    #[serial]
+    fn test_repl_submit() {
+        let mut session = Session::_new_test().unwrap();
+
+        session.init().unwrap();
+
+        let mut repl = Repl::new(session).unwrap();
+
+        assert_eq!(
+            repl.submit("3 -> a; a + 1"),
+            vec!["3 -> a = 3", "a + 1 = 4"]
+        );
+        assert_eq!(
+            repl.submit("@0 * 2; 2 +"),
+            vec!["@0 * 2 = 8", "Error: Incomplete Expression: 2+"]
+        );
+
+        // Entries are saved as they're made, like in the TUI
+        assert!(repl.session.history_file_path.exists());
+
+        repl.session._test_purge().unwrap();
+    }
+}
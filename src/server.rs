@@ -0,0 +1,353 @@
+//! JSON-RPC server, for driving ApeCrunch from editor plugins over stdin and stdout.
+//!
+//! Requests and responses are JSON-RPC 2.0 objects, one per line.
+//!
+
+// Copyright (c) 2022 Charles M. Thompson
+//
+// This file is part of ApeCrunch.
+//
+// ApeCrunch is free software: you can redistribute it and/or modify it under
+// the terms only of version 3 of the GNU General Public License as published
+// by the Free Software Foundation
+//
+// ApeCrunch is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License
+// for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// ApeCrunch(in a file named COPYING).
+// If not, see <https://www.gnu.org/licenses/>.
+
+use crate::parser::EvaluationErrorCode;
+use crate::parser::EvaluationResult;
+use crate::session::Session;
+use serde::Deserialize;
+use serde_json::json;
+use serde_json::Value;
+use std::error::Error;
+use std::io::BufRead;
+use std::io::Write;
+
+/// JSON-RPC error code for requests that aren't valid JSON.
+///
+/// **NOT PUBLIC.**
+///
+const PARSE_ERROR: i64 = -32700;
+
+/// JSON-RPC error code for JSON that isn't a request.
+///
+/// **NOT PUBLIC.**
+///
+const INVALID_REQUEST: i64 = -32600;
+
+/// JSON-RPC error code for unknown methods.
+///
+/// **NOT PUBLIC.**
+///
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// JSON-RPC error code for missing or mistyped parameters.
+///
+/// **NOT PUBLIC.**
+///
+const INVALID_PARAMS: i64 = -32602;
+
+/// Most decimal places set_precision accepts, since every calculation works to at least that many digits.
+///
+/// **NOT PUBLIC.**
+///
+const MAX_DECIMAL_PLACES: u64 = 1000;
+
+/// JSON-RPC request, the id is missing for notifications that don't get a response.
+///
+/// **NOT PUBLIC.**
+///
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// JSON-RPC error, turned into the error object of a response.
+///
+/// **NOT PUBLIC.**
+///
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: &str) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+pub struct Server {
+    session: Session,
+}
+
+impl Server {
+    /// Creates a new Server instance with a given session, kept for as long as the server runs.
+    ///
+    pub fn new(session: Session) -> Self {
+        Self { session }
+    }
+
+    /// Answers requests read from the input, one per line, until the input ends.
+    ///
+    /// Returns an i/o error if the input can't be read or the output can't be written.
+    ///
+    pub fn serve(
+        &mut self,
+        input: impl BufRead,
+        output: &mut impl Write,
+    ) -> Result<(), Box<dyn Error>> {
+        for line in input.lines() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if let Some(response) = self.respond(&line) {
+                writeln!(output, "{}", response)?;
+                output.flush()?; // Clients wait on every response, so it can't sit in a buffer
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Answers a single request, None if it's a notification.
+    ///
+    /// **NOT PUBLIC.**
+    ///
+    fn respond(&mut self, line: &str) -> Option<Value> {
+        let request = match serde_json::from_str::<Value>(line) {
+            Ok(request) => request,
+            Err(error) => {
+                return Some(error_response(
+                    Value::Null,
+                    RpcError::new(PARSE_ERROR, &error.to_string()),
+                ))
+            }
+        };
+
+        let request = match serde_json::from_value::<Request>(request) {
+            Ok(request) if request.jsonrpc == "2.0" => request,
+            Ok(_) => {
+                return Some(error_response(
+                    Value::Null,
+                    RpcError::new(INVALID_REQUEST, "Only JSON-RPC 2.0 is supported!"),
+                ))
+            }
+            Err(error) => {
+                return Some(error_response(
+                    Value::Null,
+                    RpcError::new(INVALID_REQUEST, &error.to_string()),
+                ))
+            }
+        };
+
+        let result = self.call(&request.method, &request.params);
+
+        // Notifications don't get a response, even if they fail
+        let id = request.id?;
+
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => error_response(id, error),
+        })
+    }
+
+    /// Calls a method with the parameters of a request.
+    ///
+    /// **NOT PUBLIC.**
+    ///
+    fn call(&mut self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        match method {
+            "evaluate" => {
+                let expression = string_param(params, "expression")?;
+
+                Ok(json!(self.session.evaluate_line_results(expression)))
+            }
+            "set_variable" => {
+                let name = string_param(params, "name")?;
+                let value = string_param(params, "value")?;
+
+                // Names and values that don't make a statement are bad parameters, but values that fail to evaluate are a result
+                match self.session.set_variable(name, value) {
+                    Ok(entry) => Ok(json!(EvaluationResult::new(value, &entry, &self.session))),
+                    Err(error) if error.code == EvaluationErrorCode::ParseError => {
+                        Err(RpcError::new(INVALID_PARAMS, &error.to_string()))
+                    }
+                    Err(error) => Ok(json!(EvaluationResult::from_error(
+                        value,
+                        error.code,
+                        &*error.error
+                    ))),
+                }
+            }
+            "list_variables" => {
+                let variables: Vec<Value> = self
+                    .session
+                    .vartable
+                    .variables
+                    .iter()
+                    .map(|variable| {
+                        json!({
+                            "name": variable.id,
+                            "value": variable.tokens.to_source_string(&self.session),
+                        })
+                    })
+                    .collect();
+
+                Ok(json!(variables))
+            }
+            "get_history" => {
+                let entries = self.session.get_entries();
+
+                let history: Vec<Value> = entries
+                    .iter()
+                    .enumerate()
+                    .map(|(i, entry)| {
+                        json!({
+                            "index": entries.len() - i - 1,
+                            "uuid": entry.entry_uuid,
+                            "expression": entry.render_without_equality(&self.session),
+                            "result": entry.only_equality().to_string(&self.session),
+                        })
+                    })
+                    .collect();
+
+                Ok(json!(history))
+            }
+            "set_precision" => {
+                let decimal_places = match params.get("decimal_places").and_then(Value::as_u64) {
+                    Some(decimal_places) => decimal_places,
+                    None => {
+                        return Err(RpcError::new(
+                            INVALID_PARAMS,
+                            "Missing decimal_places, a whole number!",
+                        ))
+                    }
+                };
+
+                if decimal_places > MAX_DECIMAL_PLACES {
+                    return Err(RpcError::new(
+                        INVALID_PARAMS,
+                        &format!(
+                            "Too many decimal places, the most is {}!",
+                            MAX_DECIMAL_PLACES
+                        ),
+                    ));
+                }
+
+                self.session.decimal_places = decimal_places as u32; // Small enough to fit
+
+                Ok(json!(self.session.decimal_places))
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                &format!("Unknown method {}!", method),
+            )),
+        }
+    }
+}
+
+/// Gets a string parameter of a request.
+///
+/// **NOT PUBLIC.**
+///
+fn string_param<'a>(params: &'a Value, name: &str) -> Result<&'a str, RpcError> {
+    match params.get(name).and_then(Value::as_str) {
+        Some(param) => Ok(param),
+        None => Err(RpcError::new(
+            INVALID_PARAMS,
+            &format!("Missing {}, a string!", name),
+        )),
+    }
+}
+
+/// Builds the response for a request that failed.
+///
+/// **NOT PUBLIC.**
+///
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_pipe() {
+        let mut server = Server::new(Session::_new_test().unwrap());
+
+        let input = [
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "set_precision", "params": {"decimal_places": 2}}"#,
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "set_variable", "params": {"name": "r", "value": "1/3"}}"#,
+            r#"{"jsonrpc": "2.0", "method": "evaluate", "params": {"expression": "r * 2 -> d"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 3, "method": "list_variables"}"#,
+            r#"{"jsonrpc": "2.0", "id": 4, "method": "evaluate", "params": {"expression": "@0 + 1; 2 +"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 5, "method": "get_history"}"#,
+            r#"{"jsonrpc": "2.0", "id": 6, "method": "set_variable", "params": {"name": "a; b", "value": "1"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 7, "method": "solve_everything"}"#,
+            r#"{"jsonrpc": "2.0", "id": 8, "method": "set_precision", "params": {"decimal_places": 1000000}}"#,
+            "not json",
+        ]
+        .join("\n");
+
+        let mut output = Vec::<u8>::new();
+
+        server.serve(input.as_bytes(), &mut output).unwrap();
+
+        let responses: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        // The notification doesn't get a response
+        assert_eq!(responses.len(), 9);
+
+        assert_eq!(responses[0]["result"], 2);
+        assert_eq!(responses[1]["result"]["input"], "1/3");
+        assert_eq!(responses[1]["result"]["decimal"], "0.33...");
+        assert_eq!(responses[1]["result"]["fraction"]["denominator"], "3");
+
+        assert_eq!(
+            responses[2]["result"],
+            json!([{ "name": "d", "value": "2/3" }, { "name": "r", "value": "1/3" }])
+        );
+
+        assert_eq!(responses[3]["id"], 4);
+        assert_eq!(responses[3]["result"][0]["canonical"], "5/3");
+        assert_eq!(responses[3]["result"][1]["error"]["code"], "parse_error");
+
+        assert_eq!(responses[4]["result"][0]["index"], 2);
+        // Answers are numbered back from the latest entry, which is now one further away
+        assert_eq!(responses[4]["result"][2]["expression"], "@1 + 1");
+        assert_eq!(responses[4]["result"][2]["result"], "1.66...");
+
+        assert_eq!(responses[5]["error"]["code"], INVALID_PARAMS);
+        assert_eq!(responses[6]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(responses[7]["error"]["code"], INVALID_PARAMS);
+        assert_eq!(responses[8]["error"]["code"], PARSE_ERROR);
+    }
+}
@@ -18,6 +18,11 @@
 // ApeCrunch(in a file named COPYING).
 // If not, see <https://www.gnu.org/licenses/>.
 
+use crate::number::Number;
+use crate::op_engine;
+use crate::parser;
+use crate::parser::EvaluationErrorCode;
+use crate::parser::EvaluationResult;
 use crate::parser::Token;
 use crate::variable::VarTable;
 use directories::ProjectDirs;
@@ -25,8 +30,10 @@ use lazy_static::*;
 use regex::Regex;
 use serde::Deserialize;
 use serde::Serialize;
+use simple_error::*;
 use std::cmp::Ordering;
 use std::error::Error;
+use std::fmt;
 use std::fs;
 use std::fs::File;
 use std::io::Read;
@@ -37,9 +44,107 @@ use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
 use uuid::Uuid;
 
-/// Versions of history files that this version of apecrunch is compatible with
+/// Layouts history files have been written in, oldest first, along with the first version of apecrunch to write each one.
 ///
-pub const HISTORY_COMPAT_VERS: [&str; 2] = ["0.0.3", "0.0.4"];
+/// Files are read with the layout of the version that wrote them and migrated one layout at a time up to the current one. The layout hasn't
+/// changed since 0.0.3 yet. When it does, freeze the old one as a HistoryBincodeVX_X_X struct with a migration to the next, and add the
+/// version here.
+///
+pub const HISTORY_SCHEMAS: [(&str, HistorySchema); 1] = [("0.0.3", HistorySchema::V0_0_3)];
+
+/// Layouts of history files, named after the first version of apecrunch to write each one.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistorySchema {
+    /// Layout written since 0.0.3, which is still the current layout. Later versions only added token variants at the end of the enum,
+    /// which entries written before them still fit, so there has been nothing to migrate yet.
+    V0_0_3,
+}
+
+impl HistorySchema {
+    /// Gets the layout a file written by a version of apecrunch is in.
+    ///
+    /// Files from before the oldest known layout are read with the oldest layout, since they may still fit it.
+    ///
+    /// Returns a simple error if the version isn't in X.X.X format, or is newer than this version of apecrunch.
+    ///
+    pub fn from_version(version: &str) -> Result<Self, Box<dyn Error>> {
+        if parse_version(version)? > parse_version(crate::VERSION)? {
+            bail!(
+                "written by apecrunch {}, which is newer than apecrunch {}",
+                version,
+                crate::VERSION
+            );
+        }
+
+        let mut schema = HISTORY_SCHEMAS[0].1;
+
+        for (first_version, later_schema) in HISTORY_SCHEMAS {
+            if parse_version(version)? >= parse_version(first_version)? {
+                schema = later_schema;
+            }
+        }
+
+        Ok(schema)
+    }
+
+    /// Deserializes uncompressed data in this layout, migrating it to the current layout.
+    ///
+    /// **NOT PUBLIC.**
+    ///
+    fn deserialize(&self, data: &[u8]) -> Result<HistoryBincode, Box<dyn Error>> {
+        match self {
+            HistorySchema::V0_0_3 => {
+                let mut bincode = bincode::deserialize::<HistoryBincode>(data)?;
+
+                bincode.version = crate::VERSION.to_string();
+
+                Ok(bincode)
+            }
+        }
+    }
+}
+
+/// Version at the start of every history file, read before the rest to tell which layout the file is in.
+///
+/// **NOT PUBLIC.**
+///
+#[derive(Debug, Deserialize)]
+struct HistoryVersion {
+    version: String,
+}
+
+/// Error a line of statements failed with, along with the statement it failed at.
+///
+#[derive(Debug)]
+pub struct LineError {
+    /// Statement the line failed at, or the whole line if it couldn't be split into statements.
+    pub statement: String,
+    /// Stage the statement failed at.
+    pub code: EvaluationErrorCode,
+    /// Error the statement failed with.
+    pub error: Box<dyn Error>,
+}
+
+impl LineError {
+    /// Creates an error for the given statement.
+    ///
+    pub fn new(statement: &str, code: EvaluationErrorCode, error: Box<dyn Error>) -> Self {
+        Self {
+            statement: statement.trim().to_string(),
+            code,
+            error,
+        }
+    }
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl Error for LineError {}
 
 /// Individual history entry retaining it's UUID, parser tokens, and textual rendition.
 ///
@@ -49,7 +154,7 @@ pub struct HistoryEntry {
     pub entry_uuid: Uuid,
     /// Parser tokens of the entry, basically the entire expression parsed down into it's most basic form
     pub expression: Token,
-    /// Rendition of the entry's expression at the time of calculation
+    /// Rendition of the entry's expression at the time of calculation, which may span multiple lines
     rendition: String,
 }
 
@@ -60,16 +165,64 @@ impl HistoryEntry {
         let entry_uuid = Uuid::new_v4();
 
         Self {
-            entry_uuid: entry_uuid,
+            entry_uuid,
             expression: expression.clone(),
-            rendition: expression.to_string(session),
+            rendition: expression.to_multiline_string(session),
         }
     }
 
-    /// Converts the entry to a string.
+    /// Creates an entry recalculated from an older one, noting the old result in the rendition.
+    ///
+    pub fn recalculated(expression: &Token, previous: &HistoryEntry, session: &Session) -> Self {
+        let mut entry = Self::new(expression, session);
+
+        entry.rendition = format!(
+            "{}  # recalculated, was {}",
+            entry.rendition,
+            previous.only_equality().to_string(session)
+        );
+
+        entry
+    }
+
+    /// Gets the entry without the equality, the expression as it was entered
+    ///
+    pub fn without_equality(&self) -> &Token {
+        if let Token::Equality(left, _right) = &self.expression {
+            return left;
+        }
+
+        &self.expression
+    }
+
+    /// Gets the names of every variable the entry refers to
     ///
-    pub fn to_string(&self) -> String {
-        self.rendition.clone()
+    pub fn variables(&self) -> Vec<String> {
+        self.without_equality().identifiers()
+    }
+
+    /// Gets the UUIDs of every entry this entry refers to with an answer
+    ///
+    pub fn answers(&self) -> Vec<Uuid> {
+        self.without_equality().answers()
+    }
+
+    /// Gets the id of the variable the entry stores a value or formula in, if it does
+    ///
+    pub fn stored_variable(&self) -> Option<&str> {
+        match self.without_equality() {
+            Token::Store(id, _) | Token::Formula(id, _) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the entry refers to any of the given variables or entries
+    ///
+    pub fn depends_on(&self, variables: &[String], entries: &[Uuid]) -> bool {
+        self.variables()
+            .iter()
+            .any(|variable| variables.contains(variable))
+            || self.answers().iter().any(|uuid| entries.contains(uuid))
     }
 
     /// Gets the entry with only the equality
@@ -87,11 +240,15 @@ impl HistoryEntry {
     /// Just returns the expression unmodified if there is no equal sign.
     ///
     pub fn render_without_equality(&self, session: &Session) -> String {
-        if let Token::Equality(left, _right) = &self.expression {
-            return left.to_string(session);
-        }
+        self.without_equality().to_string(session)
+    }
+}
 
-        self.expression.to_string(session)
+impl fmt::Display for HistoryEntry {
+    /// Converts the entry to a string.
+    ///
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.rendition)
     }
 }
 
@@ -116,10 +273,29 @@ pub struct HistoryBincode {
 impl HistoryBincode {
     /// Read an lz4_flex-compressed bincode from a slice and return a deserialized HistoryBincode
     ///
+    /// Files written by older versions of apecrunch are migrated to the current layout, and get the current version. The file itself is left
+    /// alone until it's written again.
+    ///
+    /// Returns a simple error if the file is corrupt or was written by a newer version of apecrunch.
+    ///
     pub fn from_slice(slice: &[u8]) -> Result<Self, Box<dyn Error>> {
-        let uncompressed_data = lz4_flex::block::decompress_size_prepended(slice)?;
+        let uncompressed_data = match lz4_flex::block::decompress_size_prepended(slice) {
+            Ok(uncompressed_data) => uncompressed_data,
+            Err(_) => bail!("corrupt or not a history file"),
+        };
 
-        Ok(bincode::deserialize(&uncompressed_data)?)
+        let version = match bincode::deserialize::<HistoryVersion>(&uncompressed_data) {
+            Ok(history_version) => history_version.version,
+            Err(_) => bail!("corrupt or not a history file"),
+        };
+
+        match HistorySchema::from_version(&version)?.deserialize(&uncompressed_data) {
+            Ok(bincode) => Ok(bincode),
+            Err(_) => bail!(
+                "corrupt, or from apecrunch {} and older than the oldest readable layout",
+                version
+            ),
+        }
     }
 
     /// Serialize a HistoryBincode into an lz4_flex-compressed bincode, stored in a Vec<u8>
@@ -132,6 +308,34 @@ impl HistoryBincode {
             &self,
         )?))
     }
+
+    /// Write a HistoryBincode to a file, creating the file if it doesn't exist yet and replacing it if it does
+    ///
+    pub fn to_file(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let data = self.to_vec()?;
+
+        // Create the file if it doesn't exist yet, clear it, and write the bincode
+        let mut file = File::options()
+            .read(false)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+
+        file.write_all(&data)?;
+
+        Ok(())
+    }
+}
+
+/// History file found in the data directory, along with where it was loaded from.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryFile {
+    /// Path of the file.
+    pub path: PathBuf,
+    /// Contents of the file.
+    pub bincode: HistoryBincode,
 }
 
 /// Serializable version of session. For creating session.toml.
@@ -140,6 +344,8 @@ impl HistoryBincode {
 pub struct SessionTOML {
     pub decimal_places: Option<u32>,
     pub history_depth: Option<u32>,
+    pub equality_tolerance: Option<f64>,
+    pub unicode_output: Option<bool>,
 }
 
 /// All semi-global settings and variables that are needed for the session.
@@ -154,6 +360,10 @@ pub struct Session {
     pub decimal_places: u32,
     /// Number of history entries to render.
     pub history_depth: u32,
+    /// Largest difference between two numbers that still counts as equal, None if they have to be exactly equal.
+    pub equality_tolerance: Option<Number>,
+    /// Whether to render operators and constants with unicode symbols, like × and π.
+    pub unicode_output: bool,
     /// Variables stored in the session
     pub vartable: VarTable,
     /// ApeCrunch Version in X.X.X format.
@@ -168,6 +378,19 @@ pub struct Session {
     pub history_file_path: PathBuf,
     /// Previous entries found in previous sessions
     pub previous_entries: Vec<HistoryEntry>,
+    /// Symbols bound by the functions currently being parsed, like the x in roots(x^2 - 1, x)
+    pub bound_symbols: Vec<String>,
+    /// Values given to bound symbols while evaluating, like the i in sum(i, 1, 10, i^2), innermost last
+    pub local_bindings: Vec<(String, Token)>,
+    /// Ids of the formula variables currently being evaluated, innermost last
+    pub evaluating_formulas: Vec<String>,
+    /// Whether an approximation, like a root that doesn't come out even or a transcendental function, went into the statement being
+    /// evaluated
+    pub approximated: bool,
+    /// Ids of the variables storing approximated values, as far as this session knows
+    pub approximate_variables: Vec<String>,
+    /// UUIDs of the entries in this session with approximated results
+    pub approximate_entries: Vec<Uuid>,
 }
 
 impl Session {
@@ -198,16 +421,24 @@ impl Session {
 
         Ok(Self {
             config_dir: dirs.config_dir().to_owned(),
-            data_dir: data_dir,
+            data_dir,
             decimal_places: DEFAULT_DECIMAL_PLACES,
             history_depth: DEFAULT_HISTORY_DEPTH,
+            equality_tolerance: None,
+            unicode_output: false,
             session_start: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
-            session_uuid: session_uuid,
+            session_uuid,
             version: crate::VERSION.to_string(),
             previous_entries: Vec::<HistoryEntry>::new(),
             entries: Vec::<HistoryEntry>::new(),
-            history_file_path: history_file_path,
+            history_file_path,
             vartable: VarTable::new(),
+            bound_symbols: Vec::<String>::new(),
+            local_bindings: Vec::<(String, Token)>::new(),
+            evaluating_formulas: Vec::<String>::new(),
+            approximated: false,
+            approximate_variables: Vec::<String>::new(),
+            approximate_entries: Vec::<Uuid>::new(),
         })
     }
 
@@ -225,28 +456,30 @@ impl Session {
 
         Ok(Self {
             config_dir: Path::new("test/config").to_owned(),
-            data_dir: data_dir,
+            data_dir,
             decimal_places: DEFAULT_DECIMAL_PLACES,
             history_depth: DEFAULT_HISTORY_DEPTH,
+            equality_tolerance: None,
+            unicode_output: false,
             session_start: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
-            session_uuid: session_uuid,
+            session_uuid,
             version: crate::VERSION.to_string(),
             previous_entries: Vec::<HistoryEntry>::new(),
             entries: Vec::<HistoryEntry>::new(),
-            history_file_path: history_file_path,
+            history_file_path,
             vartable: VarTable::new(),
+            bound_symbols: Vec::<String>::new(),
+            local_bindings: Vec::<(String, Token)>::new(),
+            evaluating_formulas: Vec::<String>::new(),
+            approximated: false,
+            approximate_variables: Vec::<String>::new(),
+            approximate_entries: Vec::<Uuid>::new(),
         })
     }
 
     /// Initialize a session, reading and, if necissary, creating, various config files needed for basic operation.
     ///
     pub fn init(&mut self) -> Result<(), Box<dyn Error>> {
-        // Regex definitions for correctly identifying files
-        lazy_static! {
-            static ref HISTORY_FILE_RE: Regex =
-                Regex::new(r"(.*)(history\-)(.+)(\.bincode\.lz4)").unwrap();
-        }
-
         // Create the directories if they don't exist.
         if !self.config_dir.exists() {
             fs::create_dir_all(self.config_dir.as_path())?;
@@ -285,55 +518,79 @@ impl Session {
             self.history_depth = history_depth;
         }
 
-        // Load all previous history files
+        if let Some(unicode_output) = session_toml.unicode_output {
+            self.unicode_output = unicode_output;
+        }
+
+        if let Some(equality_tolerance) = session_toml.equality_tolerance {
+            self.equality_tolerance = Some(Number::from_str(&equality_tolerance.to_string())?);
+        }
+
+        // Load all previous history files, already sorted by session start time
+        let previous_files = self.get_history_files()?;
+
+        // Load all previous session calculations and variables
+        for history_file in previous_files {
+            // Merge previous variable declarations into the current session
+            self.vartable
+                .merge(&history_file.bincode.session_vartable)?;
+            // Add previous calculation entry
+            self.previous_entries
+                .extend_from_slice(&history_file.bincode.entries);
+        }
+
+        Ok(())
+    }
 
-        let mut previous_bincodes = Vec::<HistoryBincode>::new();
+    /// Gets the paths of every history file in the data directory, including ones that are corrupt or from a newer version.
+    ///
+    pub fn get_history_file_paths(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        // Regex definitions for correctly identifying files
+        lazy_static! {
+            static ref HISTORY_FILE_RE: Regex =
+                Regex::new(r"(.*)(history\-)(.+)(\.bincode\.lz4)").unwrap();
+        }
+
+        let mut paths = Vec::<PathBuf>::new();
 
         // Go through each file in the session's data directory...
         for entry in fs::read_dir(&self.data_dir)? {
             let path = entry?.path().as_path().to_owned();
 
+            // And if the file name matches the regex, keep it
+            if HISTORY_FILE_RE.is_match(path.to_str().unwrap_or("")) {
+                paths.push(path);
+            }
+        }
+
+        Ok(paths)
+    }
+
+    /// Loads every history file in the data directory, sorted by session start time and migrated to the current layout.
+    ///
+    /// Files that are corrupt or from a newer version of apecrunch are skipped with an error printed to stderr.
+    ///
+    pub fn get_history_files(&self) -> Result<Vec<HistoryFile>, Box<dyn Error>> {
+        let mut history_files = Vec::<HistoryFile>::new();
+
+        for path in self.get_history_file_paths()? {
             let file_name = path.to_str().unwrap_or("");
 
-            // And if the file name matches the regex...
-            if HISTORY_FILE_RE.is_match(&file_name) {
-                // Load it!
-                let data = fs::read(&path)?;
-                let history_bincode = match HistoryBincode::from_slice(&data) {
-                    Ok(bincode) => bincode,
-                    Err(_e) => {
-                        // If our history file can't be serialized, print an error and move on...
-                        eprintln!(
-                            "History file {} corrupt or incompatible, not loading...",
-                            file_name
-                        );
-                        continue;
-                    }
-                };
-
-                // If our bincode version is compatible...
-                if HISTORY_COMPAT_VERS.contains(&history_bincode.version.as_str()) {
-                    previous_bincodes.push(history_bincode);
+            // Load it, migrating it if it's from an older version!
+            let data = fs::read(&path)?;
+            match HistoryBincode::from_slice(&data) {
+                Ok(bincode) => history_files.push(HistoryFile { path, bincode }),
+                Err(error) => {
+                    // If our history file can't be read, print an error and move on...
+                    eprintln!("History file {} not loaded, {}...", file_name, error);
                 }
-                // Otherwise print an error...
-                else {
-                    eprintln!("History file version \"{}\" incompatible with apecrunch version {}, not loading...", history_bincode.version, crate::VERSION);
-                }
-            }
+            };
         }
 
         // Sort previous entries by session start time
-        previous_bincodes.sort_by(|a, b| a.session_start.cmp(&b.session_start));
+        history_files.sort_by_key(|history_file| history_file.bincode.session_start);
 
-        // Load all previous session calculations and variables
-        for bincode in previous_bincodes {
-            // Merge previous variable declarations into the current session
-            self.vartable.merge(&bincode.session_vartable)?;
-            // Add previous calculation entry
-            self.previous_entries.extend_from_slice(&bincode.entries);
-        }
-
-        Ok(())
+        Ok(history_files)
     }
 
     /// Purge all config and data files, currently only used in cargo test.
@@ -354,10 +611,24 @@ impl Session {
 
     /// Add an entry to the current session.
     ///
+    /// The entry counts as approximated if an approximation went into the statement evaluated last.
+    ///
     pub fn add_entry(&mut self, history_entry: &HistoryEntry) {
+        if self.approximated {
+            self.approximate_entries.push(history_entry.entry_uuid);
+        }
+
         self.entries.push(history_entry.clone());
     }
 
+    /// Returns true if an approximation went into the result of the entry with the given UUID.
+    ///
+    /// Only entries from this session are known, entries from previous sessions always count as exact.
+    ///
+    pub fn is_approximate(&self, uuid: &Uuid) -> bool {
+        self.approximate_entries.contains(uuid)
+    }
+
     /// Returns a concatination of all previous entries and all current entries.
     ///
     pub fn get_entries(&self) -> Vec<HistoryEntry> {
@@ -369,7 +640,7 @@ impl Session {
 
     /// Gets an entry from the inverse index of the entry
     ///
-    pub fn get_entry_inv_index<'a>(&'a self, inverse_index: usize) -> Option<&'a HistoryEntry> {
+    pub fn get_entry_inv_index(&self, inverse_index: usize) -> Option<&HistoryEntry> {
         if inverse_index >= self.previous_entries.len() + self.entries.len() {
             return None;
         }
@@ -378,11 +649,11 @@ impl Session {
 
         let (entries, index) = match inverse_index.cmp(&self.entries.len()) {
             Ordering::Greater => {
-                let index = &self.previous_entries.len() - (inverse_index - &self.entries.len());
+                let index = self.previous_entries.len() - (inverse_index - self.entries.len());
                 (&self.previous_entries, index)
             }
             _ => {
-                let index = &self.entries.len() - inverse_index;
+                let index = self.entries.len() - inverse_index;
                 (&self.entries, index)
             }
         };
@@ -414,9 +685,19 @@ impl Session {
         None
     }
 
+    /// Gets the value a symbol is locally bound to, if any, preferring the innermost binding
+    ///
+    pub fn get_binding(&self, id: &str) -> Option<&Token> {
+        self.local_bindings
+            .iter()
+            .rev()
+            .find(|(symbol, _)| symbol == id)
+            .map(|(_, value)| value)
+    }
+
     /// Gets an entry from a given UUID
     ///
-    pub fn get_entry_from_uuid<'a>(&'a self, uuid: &Uuid) -> Option<&'a HistoryEntry> {
+    pub fn get_entry_from_uuid(&self, uuid: &Uuid) -> Option<&HistoryEntry> {
         if let Some(entry) = self.entries.iter().find(|entry| &entry.entry_uuid == uuid) {
             return Some(entry);
         }
@@ -431,6 +712,181 @@ impl Session {
         todo!()
     }
 
+    /// Evaluates every statement in a line, separated by semicolons, adding an entry for each to the session.
+    ///
+    /// Statements are parsed and evaluated in order, so later statements can use the variables stored and answers given by earlier ones.
+    /// A statement like a = 3 with a name on the left always stores to it, the same as 3 -> a. Putting it in parenthesis, like (a = 3),
+    /// compares instead.
+    ///
+    /// Returns the new entries, or the first error along with the statement it happened at. Entries for the statements before an error
+    /// are kept.
+    ///
+    pub fn evaluate_line(&mut self, line: &str) -> Result<Vec<HistoryEntry>, LineError> {
+        let statements = match parser::split_statements(line) {
+            Ok(statements) if !statements.is_empty() => statements,
+            Ok(_) => {
+                return Err(LineError::new(
+                    line,
+                    EvaluationErrorCode::ParseError,
+                    Box::new(SimpleError::new("Empty Expression!")),
+                ))
+            }
+            Err(error) => return Err(LineError::new(line, EvaluationErrorCode::ParseError, error)),
+        };
+
+        let mut entries = Vec::<HistoryEntry>::with_capacity(statements.len());
+
+        for statement in statements {
+            let tokens = parser::parse_str(statement, self).map_err(|error| {
+                LineError::new(statement, EvaluationErrorCode::ParseError, error)
+            })?;
+
+            // A statement setting a name equal to something stores to it whether or not it's a variable yet, so a = 3 always means 3 -> a
+            let tokens = match tokens {
+                Token::Equality(left, right) => match *left {
+                    Token::Symbol(id) => Token::Store(id, right),
+                    Token::Variable(variable) => Token::Store(variable.id, right),
+                    left => Token::Equality(Box::new(left), right),
+                },
+                tokens => tokens,
+            };
+            let result = op_engine::get_equality(&tokens, self).map_err(|error| {
+                LineError::new(statement, EvaluationErrorCode::EvaluationError, error)
+            })?;
+            let entry = HistoryEntry::new(&result, self);
+
+            self.add_entry(&entry);
+            entries.push(entry);
+        }
+
+        Ok(entries)
+    }
+
+    /// Stores the value of an expression in a variable, adding an entry for it to the session the same way "(value) -> name" would.
+    ///
+    /// Returns the new entry, or a parse error if the name can't be stored to or the value isn't a single expression, or the error the
+    /// value failed with.
+    ///
+    pub fn set_variable(&mut self, name: &str, value: &str) -> Result<HistoryEntry, LineError> {
+        lazy_static! {
+            static ref VARIABLE_NAME_RE: Regex = Regex::new(r"^[[:alpha:]][[:alnum:]_]*$").unwrap(); // Used to see if a variable name can be stored to
+        }
+
+        let statement = format!("({}) -> {}", value, name);
+        let parse_error = |message: String| {
+            LineError::new(
+                &statement,
+                EvaluationErrorCode::ParseError,
+                Box::new(SimpleError::new(message)),
+            )
+        };
+
+        // Both are put together into a statement of its own, so neither can end it early and start another one
+        if !VARIABLE_NAME_RE.is_match(name) {
+            return Err(parse_error(format!("Invalid variable name {}!", name)));
+        }
+
+        if parser::split_statements(value).map_or(true, |statements| statements.len() != 1) {
+            return Err(parse_error(
+                "The value must be a single expression!".to_string(),
+            ));
+        }
+
+        Ok(self.evaluate_line(&statement)?.remove(0)) // There's always an entry for the one statement
+    }
+
+    /// Evaluates every statement in a line with evaluate_line(), reporting each statement as a result that tells parse errors apart from
+    /// evaluation errors.
+    ///
+    /// Statements after the first error aren't evaluated, and the failed statement is the last result.
+    ///
+    pub fn evaluate_line_results(&mut self, line: &str) -> Vec<EvaluationResult> {
+        let first_index = self.get_entry_count();
+        let outcome = self.evaluate_line(line);
+
+        // The line split fine if any entries were added, and there's an entry for each statement up to the error
+        let statements = parser::split_statements(line).unwrap_or_default();
+
+        let mut results: Vec<EvaluationResult> = self.get_entries()[first_index..]
+            .iter()
+            .zip(statements)
+            .map(|(entry, statement)| EvaluationResult::new(statement, entry, self))
+            .collect();
+
+        if let Err(error) = outcome {
+            results.push(EvaluationResult::from_error(
+                &error.statement,
+                error.code,
+                &*error.error,
+            ));
+        }
+
+        results
+    }
+
+    /// Recalculates the entry with the given UUID and every later entry depending on it, with the variables as they are now.
+    ///
+    /// An entry depends on a recalculated entry if it uses its answer, or a variable it stored, or a formula built on that variable.
+    /// Recalculated entries are added as new entries, with any answers pointing at the new entries instead of the old ones.
+    ///
+    /// Returns the new entries, or an error if the UUID isn't found or an entry fails to recalculate.
+    ///
+    pub fn recalculate_from(&mut self, uuid: &Uuid) -> Result<Vec<HistoryEntry>, Box<dyn Error>> {
+        let entries = self.get_entries();
+
+        let start = match entries.iter().position(|entry| &entry.entry_uuid == uuid) {
+            Some(start) => start,
+            None => bail!("Invalid entry uuid {}!", uuid),
+        };
+
+        let mut changed_variables = Vec::<String>::new();
+        let mut replaced_entries = Vec::<(Uuid, Uuid)>::new();
+        let mut recalculated = Vec::<HistoryEntry>::new();
+
+        for entry in &entries[start..] {
+            let replaced_uuids: Vec<Uuid> = replaced_entries.iter().map(|(old, _)| *old).collect();
+
+            if &entry.entry_uuid != uuid && !entry.depends_on(&changed_variables, &replaced_uuids) {
+                continue;
+            }
+
+            let expression = self.refresh_references(entry.without_equality(), &replaced_entries);
+            let result = op_engine::get_equality(&expression, self)?;
+            let new_entry = HistoryEntry::recalculated(&result, entry, self);
+
+            if let Some(id) = entry.stored_variable() {
+                changed_variables.push(id.to_string());
+                changed_variables.extend(self.vartable.dependents(id));
+            }
+
+            replaced_entries.push((entry.entry_uuid, new_entry.entry_uuid));
+
+            self.add_entry(&new_entry);
+            recalculated.push(new_entry);
+        }
+
+        Ok(recalculated)
+    }
+
+    /// Points the variables in an expression at their current values, and answers at the entries that replaced them.
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn refresh_references(&self, token: &Token, replaced_entries: &[(Uuid, Uuid)]) -> Token {
+        token.replace(&|token| match token {
+            Token::Variable(variable) => self
+                .vartable
+                .get(&variable.id)
+                .ok()
+                .map(|variable| Token::Variable(Box::new(variable))),
+            Token::Answer(uuid) => replaced_entries
+                .iter()
+                .find(|(old, _)| old == uuid)
+                .map(|(_, new)| Token::Answer(*new)),
+            _ => None,
+        })
+    }
+
     /// Gets the total count of all entries
     ///
     pub fn get_entry_count(&self) -> usize {
@@ -457,6 +913,29 @@ impl Session {
         file_path
     }
 
+    /// Get the path to the file LaTeX exports are written to, given the default export filename.
+    ///
+    pub fn get_latex_export_file_path(&self) -> PathBuf {
+        let mut file_path: PathBuf = self.data_dir.clone();
+
+        file_path.push(Path::new(DEFAULT_LATEX_EXPORT_NAME));
+
+        file_path
+    }
+
+    /// Get the path to the file history exports are written to, given the default export filename and the extension of the format.
+    ///
+    pub fn get_history_export_file_path(&self, extension: &str) -> PathBuf {
+        let mut file_path: PathBuf = self.data_dir.clone();
+
+        file_path.push(Path::new(&format!(
+            "{}.{}",
+            DEFAULT_HISTORY_EXPORT_NAME, extension
+        )));
+
+        file_path
+    }
+
     /// Create a given default config file given a path and the contents of the file in a string.
     ///
     /// Returns file i/o errors if any. **Will refuse to overwrite existing files.**
@@ -495,19 +974,27 @@ impl Session {
     pub fn update_file(&mut self) -> Result<(), Box<dyn Error>> {
         let history_bincode = self.create_history_bincode();
 
-        let data = history_bincode.to_vec()?;
-
-        // Create the file if it doesn't exist yet, clear it, and write the bincode
-        let mut file = File::options()
-            .read(false)
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&self.history_file_path)?;
-
-        file.write_all(&data)?;
+        history_bincode.to_file(&self.history_file_path)
+    }
+}
 
-        Ok(())
+/// Parses a version in X.X.X format into its numbers, so versions can be compared.
+///
+/// **PRIVATE FUNCTION**
+///
+fn parse_version(version: &str) -> Result<(u64, u64, u64), Box<dyn Error>> {
+    let numbers: Vec<u64> = match version
+        .split('.')
+        .map(|number| number.parse::<u64>())
+        .collect()
+    {
+        Ok(numbers) => numbers,
+        Err(_) => bail!("invalid version \"{}\"", version),
+    };
+
+    match numbers[..] {
+        [major, minor, patch] => Ok((major, minor, patch)),
+        _ => bail!("invalid version \"{}\"", version),
     }
 }
 
@@ -523,11 +1010,23 @@ pub static DEFAULT_SESSION_TOML_NAME: &str = "session.toml";
 /// Default filename of the theme config file.
 pub static DEFAULT_THEME_TOML_NAME: &str = "theme.toml";
 
+/// Default filename of the LaTeX export file.
+pub static DEFAULT_LATEX_EXPORT_NAME: &str = "export.tex";
+
+/// Default filename of history export files, without the extension. Kept apart from history files so they're never loaded as one.
+pub static DEFAULT_HISTORY_EXPORT_NAME: &str = "history_export";
+
 /// Contents of the default session config file.
 pub static DEFAULT_SESSION_TOML: &str = r##"# Auto generated session config
 
 decimal_places = 6
 history_depth = 1000
+
+# Uncomment to count numbers this close together as equal when comparing inexact results
+# equality_tolerance = 0.000000001
+
+# Uncomment to show results with symbols like × and π
+# unicode_output = true
 "##;
 
 /// Contents of the default theme config file. Kinda going for a darkula theme here
@@ -550,7 +1049,6 @@ borders = "simple"
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::parser;
     use serial_test::*;
 
     const TWOPTWO: &str = "2 + 2";
@@ -657,6 +1155,72 @@ mod tests {
         session1._test_purge().unwrap();
     }
 
+    // Test reading history files written by other versions of apecrunch
+    #[test]
+    #[serial]
+    fn test_history_migration() {
+        let mut session = Session::_new_test().unwrap();
+
+        session.init().unwrap();
+
+        let expression = parser::parse_str(TWOPTWO, &mut session).unwrap();
+
+        session.add_entry(&HistoryEntry::new(&expression, &session));
+
+        let with_version = |version: &str| {
+            let old_bincode = HistoryBincode {
+                version: version.to_string(),
+                session_start: session.session_start,
+                session_uuid: session.session_uuid,
+                decimal_places: session.decimal_places,
+                session_vartable: session.vartable.clone(),
+                entries: session.entries.clone(),
+            };
+
+            lz4_flex::block::compress_prepend_size(&bincode::serialize(&old_bincode).unwrap())
+        };
+
+        // The layout hasn't changed since 0.0.3, so older files are read as they are and get the current version
+        assert_eq!(
+            HistoryBincode::from_slice(&with_version("0.0.3")).unwrap(),
+            session.create_history_bincode()
+        );
+        // Files from before the oldest layout are still read if they fit it
+        HistoryBincode::from_slice(&with_version("0.0.1")).unwrap();
+
+        let error = HistoryBincode::from_slice(&with_version("99.0.0")).unwrap_err();
+
+        assert!(error.to_string().contains("newer than apecrunch"));
+
+        HistoryBincode::from_slice(&with_version("0.0")).unwrap_err();
+        HistoryBincode::from_slice(&[1, 2, 3]).unwrap_err();
+
+        assert_eq!(
+            HistorySchema::from_version(crate::VERSION).unwrap(),
+            HistorySchema::V0_0_3
+        );
+
+        // Files from the future are skipped, but the rest are still loaded
+        fs::write(
+            session.data_dir.join("history-future.bincode.lz4"),
+            with_version("99.0.0"),
+        )
+        .unwrap();
+        fs::write(
+            session.data_dir.join("history-old.bincode.lz4"),
+            with_version("0.0.3"),
+        )
+        .unwrap();
+
+        let mut session2 = Session::_new_test().unwrap();
+
+        session2.init().unwrap();
+
+        assert_eq!(session2.previous_entries, session.entries);
+
+        session._test_purge().unwrap();
+    }
+
     // Test using the get by inverse index and get by uuid functions
     #[test]
     #[serial]
@@ -684,4 +1248,81 @@ mod tests {
 
         session._test_purge().unwrap();
     }
+
+    // Test evaluating several statements on one line
+    #[test]
+    fn test_evaluate_line() {
+        let mut session = Session::_new_test().unwrap();
+
+        let entries = session
+            .evaluate_line("3 -> a; 4 -> b; sqrt(a^2 + b^2); @0 * 2")
+            .unwrap();
+
+        let results: Vec<String> = entries
+            .iter()
+            .map(|entry| entry.only_equality().to_string(&session))
+            .collect();
+
+        assert_eq!(results, vec!["3", "4", "5", "10"]);
+
+        // Names are always stored to with = at the start of a statement, and compared with it in parenthesis
+        let entries = session
+            .evaluate_line("p = 3; q = 4; sqrt(p^2 + q^2); p = 5; (p = 5); (p = 4); p")
+            .unwrap();
+
+        let results: Vec<String> = entries
+            .iter()
+            .map(|entry| entry.only_equality().to_string(&session))
+            .collect();
+
+        assert_eq!(results, vec!["3", "4", "5", "5", "true", "false", "5"]);
+
+        // Statements before an error still count
+        session
+            .evaluate_line("5 -> c; c / [1]; 6 -> d")
+            .unwrap_err();
+
+        assert_eq!(session.get_entry_count(), 12);
+        assert!(session.vartable.get("d").is_err());
+
+        session.evaluate_line(" ; ").unwrap_err();
+    }
+
+    // Test recalculating the entries depending on a changed variable
+    #[test]
+    fn test_recalculate_from() {
+        let mut session = Session::_new_test().unwrap();
+
+        let enter = |expression: &str, session: &mut Session| {
+            let tokens = parser::parse_str(expression, session).unwrap();
+            let result = op_engine::get_equality(&tokens, session).unwrap();
+
+            session.add_entry(&HistoryEntry::new(&result, session));
+        };
+
+        enter("5 -> rate", &mut session);
+        enter("100 * rate -> cost", &mut session);
+        enter("cost + 3", &mut session);
+        enter("7 * 2", &mut session);
+        enter("@1 * 2", &mut session);
+        enter("6 -> rate", &mut session);
+
+        let uuid = session.get_entries()[1].entry_uuid;
+        let recalculated = session.recalculate_from(&uuid).unwrap();
+
+        // Only the entries depending on cost get recalculated, and the answer follows the new entry
+        let results: Vec<String> = recalculated
+            .iter()
+            .map(|entry| entry.only_equality().to_string(&session))
+            .collect();
+
+        assert_eq!(results, vec!["600", "603", "1206"]);
+        assert_eq!(session.get_entry_count(), 9);
+
+        assert_eq!(
+            recalculated[0].to_string(),
+            "100 * rate -> cost = 600  # recalculated, was 500"
+        );
+        assert_eq!(recalculated[2].answers(), vec![recalculated[1].entry_uuid]);
+    }
 }
@@ -18,7 +18,9 @@
 // ApeCrunch(in a file named COPYING).
 // If not, see <https://www.gnu.org/licenses/>.
 
-use crate::session::HistoryEntry;
+use crate::export;
+use crate::export::ExportFormat;
+use crate::parser;
 use crate::session::Session;
 use cursive::view::Nameable;
 use cursive::view::Selector;
@@ -27,6 +29,7 @@ use cursive::views::TextView;
 use cursive::views::ViewRef;
 use cursive::View;
 use std::error::Error;
+use std::fs;
 
 use cursive::views::Dialog;
 use cursive::views::ScrollView;
@@ -42,8 +45,6 @@ use cursive::views::EditView;
 use cursive::event::Event;
 use cursive::event::Key;
 
-use crate::op_engine;
-use crate::parser;
 use cursive::align::HAlign;
 use cursive::align::VAlign;
 
@@ -78,13 +79,13 @@ impl Tui {
         // Create a new Cursive instance.
         let cursive = Cursive::new();
 
-        let mut tui = Self { cursive: cursive };
+        let mut tui = Self { cursive };
 
         tui.apply_theme_toml(session.get_theme_file_path().to_str().unwrap());
 
         let cache = TuiCache {
             entry_bar_cursor_pos: 0,
-            session: session,
+            session,
         };
 
         tui.cursive.set_user_data(cache);
@@ -120,11 +121,23 @@ impl Tui {
 
         // Bind the 'a' key to grabbing the answer of the selected entry
         self.cursive
-            .set_on_post_event(Event::Char('a'), |cursive| Self::grab_answer(cursive));
+            .set_on_post_event(Event::Char('a'), Self::grab_answer);
 
         // Bind the 'e' key to focus on the entry bar
         self.cursive
-            .set_on_post_event(Event::Char('e'), |cursive| Self::focus_entry_bar(cursive));
+            .set_on_post_event(Event::Char('e'), Self::focus_entry_bar);
+
+        // Bind the 'r' key to recalculating from the selected entry
+        self.cursive
+            .set_on_post_event(Event::Char('r'), Self::recalculate_from_selected);
+
+        // Bind the 'l' key to exporting the selected entry as LaTeX
+        self.cursive
+            .set_on_post_event(Event::Char('l'), Self::export_selected_latex);
+
+        // Bind the 'x' key to exporting the whole history
+        self.cursive
+            .set_on_post_event(Event::Char('x'), Self::export_history_dialog);
     }
 
     /// Lay out all of the views.
@@ -144,26 +157,23 @@ impl Tui {
         //
 
         let entries = cache.session.get_entries();
-        let history_depth = cache.session.history_depth as usize;
 
         let mut history_list = SelectView::new()
             .on_submit(|cursive, index| Self::history_on_submit(cursive, *index))
             .h_align(HAlign::Left)
             .v_align(VAlign::Top);
 
-        let digit_count = ((history_depth as f64).log10() as usize) + 1;
-
-        let mut history_nums_str = String::new();
-
+        // Entries spanning multiple lines get one item per line, all pointing to the same entry
         for (i, entry) in entries.iter().enumerate() {
-            let inv_index = entries.len() - i - 1;
-            history_nums_str
-                .push_str(format!("{num:0>dc$}: \n", num = inv_index, dc = digit_count).as_str());
-            history_list.add_item(entry.to_string(), i);
+            for line in entry.to_string().lines() {
+                history_list.add_item(line, i);
+            }
         }
 
+        let history_nums_str = Self::history_nums_string(&cache.session);
+
         // Set the selection to the bottom element, if there are any elements in the list
-        if history_list.len() > 0 {
+        if !history_list.is_empty() {
             history_list.set_selection(history_list.len() - 1); // Ignore the callback, we don't need to do anything...
         }
 
@@ -189,8 +199,8 @@ impl Tui {
 
         let entry_bar = EditView::new()
             .style(entry_bar_style)
-            .on_edit(|cursive, text, cursor| Self::entry_bar_on_edit(cursive, text, cursor))
-            .on_submit(|cursive, text| Self::entry_bar_on_submit(cursive, text));
+            .on_edit(Self::entry_bar_on_edit)
+            .on_submit(Self::entry_bar_on_submit);
 
         let entry_bar = entry_bar
             .with_name(TUI_ENTRYBAR_ID)
@@ -247,41 +257,41 @@ impl Tui {
         // Add the current entry bar contents to the history cache and clear the entry bar.
         //
 
-        // parse the text in the entry box
-        let tokens = match parser::parse_str(text, &mut cache.session) {
-            Ok(tokens) => tokens,
-            Err(error) => {
-                Self::nonfatal_error_dialog(cursive, error);
-                return;
-            }
-        };
+        let first_index = cache.session.get_entries().len();
 
-        // Go through the tokens an operate on them, getting an equality.
-        let result = match op_engine::get_equality(&tokens, &mut cache.session) {
-            Ok(result) => result,
-            Err(error) => {
-                Self::nonfatal_error_dialog(cursive, error);
-                return;
-            }
-        };
+        // Evaluate every statement in the entry box, each one getting its own history entry.
+        let result = cache.session.evaluate_line(text);
+        let added = cache.session.get_entries().len() - first_index;
 
-        let entry = &HistoryEntry::new(&result, &cache.session);
-        let index = cache.session.get_entries().len();
+        for (i, entry) in cache.session.get_entries()[first_index..]
+            .iter()
+            .enumerate()
+        {
+            for line in entry.to_string().lines() {
+                history.add_item(line, first_index + i);
+            }
+        }
 
-        cache.session.add_entry(&entry);
-        history.add_item(entry.to_string(), index);
+        history_nums.set_content(Self::history_nums_string(&cache.session));
 
-        let digit_count = ((cache.session.history_depth as f64).log10() as usize) + 1;
-        let max_num = index;
-        let history_nums_str_old = history_nums.get_content().source().to_owned();
-        let mut history_nums_str: String =
-            format!("{num:0>dc$}: \n", num = max_num, dc = digit_count);
+        // Statements evaluated before an error are kept and saved, so only the ones from the error on are left in the entry bar to be fixed
+        let saved = cache.session.update_file();
 
-        history_nums_str.push_str(history_nums_str_old.as_str());
+        if let Result::Err(error) = result {
+            let remaining = match parser::split_statements(text) {
+                Ok(statements) => statements[added..].join(";").trim().to_string(),
+                Err(_) => text.to_string(), // Nothing was evaluated if the line couldn't be split
+            };
 
-        history_nums.set_content(history_nums_str);
+            cache.entry_bar_cursor_pos = remaining.len();
+            entry_bar.set_content(remaining);
+            cursive.set_user_data(cache);
+            Self::nonfatal_error_dialog(cursive, Box::new(error));
+            return;
+        }
 
-        if let Result::Err(error) = cache.session.update_file() {
+        if let Result::Err(error) = saved {
+            cursive.set_user_data(cache);
             Self::nonfatal_error_dialog(cursive, error);
             return;
         }
@@ -354,10 +364,10 @@ impl Tui {
         let mut curser_pos = cache.entry_bar_cursor_pos;
 
         // Get the selected history entry.
-        let answer_inv_index = match history.selected_id() {
+        let answer_inv_index = match history.selection() {
             Some(index) => {
                 let inv_index_start = cache.session.get_entry_count() - 1;
-                format!("@{}", inv_index_start - index)
+                format!("@{}", inv_index_start - *index)
             }
             None => return,
         };
@@ -377,6 +387,178 @@ impl Tui {
         layout.focus_view(&Selector::Name(TUI_ENTRYBAR_ID)).unwrap();
     }
 
+    /// Handles the 'r' key for recalculating the selected history entry and every later entry depending on it
+    ///
+    /// The recalculated entries are added to the end of the history.
+    ///
+    /// **NOT PUBLIC**
+    ///
+    fn recalculate_from_selected(cursive: &mut Cursive) {
+        // Grab the cache
+        let mut cache = match cursive.user_data::<TuiCache>() {
+            Some(cache) => cache.clone(),
+            None => {
+                panic!("Failed to initialize Cursive instance with cache! this should not happen!");
+            }
+        };
+
+        let mut history: ViewRef<SelectView<usize>> = cursive.find_name(TUI_HISTORY_ID).unwrap();
+        let mut history_nums: ViewRef<TextView> = cursive.find_name(TUI_HISTORY_NUM_ID).unwrap();
+
+        // Get the selected history entry.
+        let uuid = match history.selection() {
+            Some(index) => cache.session.get_entries()[*index].entry_uuid,
+            None => return,
+        };
+
+        let first_index = cache.session.get_entries().len();
+
+        // Entries recalculated before an error are kept, so the history is updated either way
+        let result = cache.session.recalculate_from(&uuid);
+
+        for (i, entry) in cache.session.get_entries()[first_index..]
+            .iter()
+            .enumerate()
+        {
+            for line in entry.to_string().lines() {
+                history.add_item(line, first_index + i);
+            }
+        }
+
+        history_nums.set_content(Self::history_nums_string(&cache.session));
+
+        let result = result.and_then(|_| cache.session.update_file());
+
+        cursive.set_user_data(cache); // Store the cache back with the recalculated entries.
+
+        if let Result::Err(error) = result {
+            Self::nonfatal_error_dialog(cursive, error);
+        }
+    }
+
+    /// Handles the 'l' key for exporting the selected history entry as LaTeX
+    ///
+    /// The export file is overwritten every time, so it always holds the latest export.
+    ///
+    /// **NOT PUBLIC**
+    ///
+    fn export_selected_latex(cursive: &mut Cursive) {
+        // Grab the cache
+        let cache = match cursive.user_data::<TuiCache>() {
+            Some(cache) => cache.clone(),
+            None => {
+                panic!("Failed to initialize Cursive instance with cache! this should not happen!");
+            }
+        };
+
+        let history: ViewRef<SelectView<usize>> = cursive.find_name(TUI_HISTORY_ID).unwrap();
+
+        // Get the selected history entry.
+        let latex = match history.selection() {
+            Some(index) => cache.session.get_entries()[*index]
+                .expression
+                .to_latex(&cache.session),
+            None => return,
+        };
+
+        let file_path = cache.session.get_latex_export_file_path();
+
+        match fs::write(&file_path, format!("{}\n", latex)) {
+            Ok(_) => {
+                let export_dialog = Dialog::text(format!("Exported to {}", file_path.display()))
+                    .title("LaTeX")
+                    .button("Ok", |cursive| {
+                        cursive.pop_layer().unwrap();
+                    });
+
+                cursive.add_layer(export_dialog);
+            }
+            Err(error) => Self::nonfatal_error_dialog(cursive, Box::new(error)),
+        }
+    }
+
+    /// Handles the 'x' key for exporting the whole history, asking which format to export it in
+    ///
+    /// **NOT PUBLIC**
+    ///
+    fn export_history_dialog(cursive: &mut Cursive) {
+        let mut export_dialog = Dialog::text("Export the whole history as").title("Export");
+
+        for (label, format) in [
+            ("CSV", ExportFormat::Csv),
+            ("JSON", ExportFormat::Json),
+            ("Markdown", ExportFormat::Markdown),
+        ] {
+            export_dialog.add_button(label, move |cursive| {
+                cursive.pop_layer().unwrap();
+                Self::export_history(cursive, format);
+            });
+        }
+
+        cursive.add_layer(export_dialog.dismiss_button("Cancel"));
+    }
+
+    /// Exports the whole history in the given format
+    ///
+    /// The export file of each format is overwritten every time, so it always holds the latest export.
+    ///
+    /// **NOT PUBLIC**
+    ///
+    fn export_history(cursive: &mut Cursive, format: ExportFormat) {
+        // Grab the cache
+        let cache = match cursive.user_data::<TuiCache>() {
+            Some(cache) => cache.clone(),
+            None => {
+                panic!("Failed to initialize Cursive instance with cache! this should not happen!");
+            }
+        };
+
+        let file_path = cache
+            .session
+            .get_history_export_file_path(format.extension());
+
+        let result = export::export(&cache.session, format)
+            .and_then(|contents| Ok(fs::write(&file_path, contents)?));
+
+        match result {
+            Ok(_) => {
+                let export_dialog = Dialog::text(format!("Exported to {}", file_path.display()))
+                    .title("Export")
+                    .button("Ok", |cursive| {
+                        cursive.pop_layer().unwrap();
+                    });
+
+                cursive.add_layer(export_dialog);
+            }
+            Err(error) => Self::nonfatal_error_dialog(cursive, error),
+        }
+    }
+
+    /// Renders the line numbers shown next to the history, one number per entry.
+    ///
+    /// Entries spanning multiple lines only get numbered on their first line.
+    ///
+    /// **NOT PUBLIC**
+    ///
+    fn history_nums_string(session: &Session) -> String {
+        let entries = session.get_entries();
+        let digit_count = ((session.history_depth as f64).log10() as usize) + 1;
+
+        let mut history_nums_str = String::new();
+
+        for (i, entry) in entries.iter().enumerate() {
+            let inv_index = entries.len() - i - 1;
+            history_nums_str
+                .push_str(format!("{num:0>dc$}: \n", num = inv_index, dc = digit_count).as_str());
+
+            for _ in 1..entry.to_string().lines().count() {
+                history_nums_str.push_str(&format!("{:dc$}  \n", "", dc = digit_count));
+            }
+        }
+
+        history_nums_str
+    }
+
     /// Focus on the entry bar
     ///
     /// **NOT PUBLIC**
@@ -22,6 +22,7 @@ use crate::parser::Token;
 use serde::Deserialize;
 use serde::Serialize;
 use simple_error::*;
+use std::collections::BTreeMap;
 use std::error::Error;
 
 /// Struct for the built-in variable type
@@ -41,6 +42,15 @@ impl Variable {
             tokens: value,
         }
     }
+
+    /// Gets the names of the variables a formula variable is defined in terms of, nothing if it's a plain value
+    ///
+    pub fn dependencies(&self) -> Vec<String> {
+        match &self.tokens {
+            Token::Formula(_, expression) => expression.identifiers(),
+            _ => Vec::new(),
+        }
+    }
 }
 
 /// Struct for the built-in vartable type
@@ -49,6 +59,15 @@ impl Variable {
 pub struct VarTable {
     /// Vector of variables
     pub variables: Vec<Variable>,
+    /// Names each formula variable is defined in terms of, keyed by the id of the formula. Rebuilt from the variables, so never stored
+    #[serde(skip)]
+    pub dependencies: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for VarTable {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl VarTable {
@@ -57,14 +76,17 @@ impl VarTable {
     pub fn new() -> Self {
         Self {
             variables: Vec::<Variable>::new(),
+            dependencies: BTreeMap::<String, Vec<String>>::new(),
         }
     }
 
     /// Merge with another vartable, overwriting all existing variables with ones found in the other vartable
     ///
+    /// Formulas are merged even if they end up defined in terms of themselves, that gets caught when they're evaluated instead.
+    ///
     pub fn merge(&mut self, vartable: &VarTable) -> Result<(), Box<dyn Error>> {
         for variable in &vartable.variables {
-            self.store(variable.to_owned())?;
+            self.insert(variable.to_owned());
         }
 
         Ok(())
@@ -77,8 +99,9 @@ impl VarTable {
             Ok(_) => {
                 bail!("Variable \"{}\" already found!", var.id);
             }
-            Err(i) => {
-                self.variables.insert(i, var);
+            Err(_) => {
+                self.check_cycle(&var)?;
+                self.insert(var);
             }
         }
 
@@ -88,9 +111,10 @@ impl VarTable {
     /// Remove a variable from the VarTable given just the id, fail if the variable doesn't exist
     ///
     pub fn remove(&mut self, id: &str) -> Result<(), Box<dyn Error>> {
-        match self.variables.binary_search_by(|i| i.id.as_str().cmp(&id)) {
+        match self.variables.binary_search_by(|i| i.id.as_str().cmp(id)) {
             Ok(i) => {
                 self.variables.remove(i);
+                self.dependencies.remove(id);
             }
             Err(_) => {
                 bail!("Variable \"{}\" not found!", id);
@@ -102,23 +126,90 @@ impl VarTable {
 
     /// Store a variable to the VarTable, replacing a variable if it exists with the updated value
     ///
+    /// Fails if the variable is a formula that would end up defined in terms of itself.
+    ///
     pub fn store(&mut self, var: Variable) -> Result<(), Box<dyn Error>> {
+        self.check_cycle(&var)?;
+        self.insert(var);
+
+        Ok(())
+    }
+
+    /// Returns true if the variable is a formula that depends on the other variable, directly or through other formulas
+    ///
+    pub fn depends_on(&self, id: &str, other: &str) -> bool {
+        let mut visited = Vec::<&str>::new();
+        let mut pending = vec![id];
+
+        while let Some(current) = pending.pop() {
+            for dependency in self.dependencies.get(current).into_iter().flatten() {
+                if dependency == other {
+                    return true;
+                }
+
+                if !visited.contains(&dependency.as_str()) {
+                    visited.push(dependency);
+                    pending.push(dependency);
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Gets the ids of every formula that depends on the variable, directly or through other formulas
+    ///
+    pub fn dependents(&self, id: &str) -> Vec<String> {
+        self.dependencies
+            .keys()
+            .filter(|formula| self.depends_on(formula, id))
+            .cloned()
+            .collect()
+    }
+
+    /// Makes sure a variable isn't a formula that depends on itself once stored
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn check_cycle(&self, var: &Variable) -> Result<(), Box<dyn Error>> {
+        for dependency in var.dependencies() {
+            if dependency == var.id || self.depends_on(&dependency, &var.id) {
+                bail!(
+                    "Variable \"{}\" can't be defined in terms of itself!",
+                    var.id
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Inserts or replaces a variable and updates the dependency graph, without checking for cycles
+    ///
+    /// **PRIVATE FUNCTION**
+    ///
+    fn insert(&mut self, var: Variable) {
+        let dependencies = var.dependencies();
+
+        match dependencies.is_empty() {
+            true => self.dependencies.remove(&var.id),
+            false => self.dependencies.insert(var.id.clone(), dependencies),
+        };
+
         match self.variables.binary_search_by(|i| i.id.cmp(&var.id)) {
             Ok(i) => {
-                self.variables[i] = var.clone(); // If the variable exists replace it with the new variable
+                self.variables[i] = var; // If the variable exists replace it with the new variable
             }
             Err(i) => {
                 self.variables.insert(i, var); // Otherwise insert it
             }
         }
-
-        Ok(())
     }
 
     /// Get a variable from the VarTable given just the id
     ///
-    pub fn get(&mut self, id: &str) -> Result<Variable, Box<dyn Error>> {
-        match self.variables.binary_search_by(|i| i.id.as_str().cmp(&id)) {
+    pub fn get(&self, id: &str) -> Result<Variable, Box<dyn Error>> {
+        match self.variables.binary_search_by(|i| i.id.as_str().cmp(id)) {
             Ok(i) => Ok(self.variables.get(i).unwrap().clone()),
             Err(_) => {
                 bail!("Variable \"{}\" not found!", id);
@@ -179,4 +270,38 @@ mod tests {
         // Make sure it's removed(expect error)
         vartable.get("x").unwrap_err();
     }
+
+    #[test]
+    fn test_vartable_dependencies() {
+        let formula = |id: &str, dependency: &str| {
+            Variable::new(
+                id,
+                Token::Formula(
+                    id.to_string(),
+                    Box::new(Token::Symbol(dependency.to_string())),
+                ),
+            )
+        };
+
+        let mut vartable = VarTable::new();
+
+        // b depends on a, and c depends on b
+        vartable.store(formula("b", "a")).unwrap();
+        vartable.store(formula("c", "b")).unwrap();
+
+        assert!(vartable.depends_on("c", "a"));
+        assert!(!vartable.depends_on("a", "c"));
+        assert_eq!(vartable.dependents("a"), vec!["b", "c"]);
+
+        // Neither a formula of itself nor a loop through other formulas is allowed
+        vartable.store(formula("a", "a")).unwrap_err();
+        vartable.store(formula("a", "c")).unwrap_err();
+
+        // Replacing a formula with a value drops its dependencies
+        vartable
+            .store(Variable::new("b", Token::Number(Number::one())))
+            .unwrap();
+
+        assert!(vartable.dependents("a").is_empty());
+    }
 }